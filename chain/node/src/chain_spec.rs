@@ -210,5 +210,7 @@ fn testnet_genesis(
 		pallet_collective_Instance1: Some(Default::default()),
 		pallet_elections_phragmen: Some(Default::default()),
 		pallet_democracy: Some(Default::default()),
+		product_registry: Some(Default::default()),
+		product_tracking: Some(Default::default()),
 	}
 }
@@ -16,6 +16,7 @@ native_executor_instance!(
 	pub Executor,
 	enterprise_sample_runtime::api::dispatch,
 	enterprise_sample_runtime::native_version,
+	frame_benchmarking::benchmarking::HostFunctions,
 );
 
 type FullClient = sc_service::TFullClient<Block, RuntimeApi, Executor>;
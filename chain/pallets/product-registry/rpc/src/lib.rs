@@ -0,0 +1,159 @@
+//! # Substrate Enterprise Sample - Product Registry RPC
+//!
+//! Exposes the products of the registry over JSON-RPC, with product IDs and
+//! properties decoded as UTF-8 strings.
+
+use std::{fmt::Display, marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use product_registry::{Product, ProductProperty, PropValue};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use product_registry_runtime_api::ProductRegistryApi as ProductRegistryRuntimeApi;
+
+// Error code returned when the runtime API call fails
+const RUNTIME_ERROR: i64 = 1;
+
+// Product as returned by the RPC, with its binary fields decoded as UTF-8
+// and its property values rendered as strings
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProductDetails<AccountId, Moment> {
+    pub id: String,
+    pub owner: AccountId,
+    pub props: Vec<ProductPropertyDetails>,
+    pub registered: Moment,
+    pub status: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ProductPropertyDetails {
+    pub name: String,
+    // Type of the property value: text, integer, decimal, date or boolean
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: String,
+}
+
+impl<AccountId: Clone, Moment: Clone + Display> From<Product<AccountId, Moment>>
+    for ProductDetails<AccountId, Moment>
+{
+    fn from(product: Product<AccountId, Moment>) -> Self {
+        ProductDetails {
+            id: to_utf8(product.id()),
+            owner: product.owner().clone(),
+            props: product
+                .props()
+                .unwrap_or_default()
+                .iter()
+                .map(ProductPropertyDetails::from)
+                .collect(),
+            registered: product.registered().clone(),
+            status: format!("{:?}", product.status()),
+        }
+    }
+}
+
+impl<Moment: Display> From<&ProductProperty<Moment>> for ProductPropertyDetails {
+    fn from(prop: &ProductProperty<Moment>) -> Self {
+        let (kind, value) = match prop.value() {
+            PropValue::Text(text) => ("text", to_utf8(text)),
+            PropValue::Integer(integer) => ("integer", integer.to_string()),
+            PropValue::Decimal(decimal) => ("decimal", decimal.to_string()),
+            PropValue::Date(date) => ("date", date.to_string()),
+            PropValue::Boolean(boolean) => ("boolean", boolean.to_string()),
+        };
+        ProductPropertyDetails {
+            name: to_utf8(prop.name()),
+            kind: kind.into(),
+            value,
+        }
+    }
+}
+
+// Invalid UTF-8 sequences are replaced rather than failing the whole query
+fn to_utf8(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+#[rpc]
+pub trait ProductRegistryApi<BlockHash, AccountId, Moment> {
+    #[rpc(name = "productRegistry_productsOfOrg")]
+    fn products_of_org(
+        &self,
+        org: AccountId,
+        at: Option<BlockHash>,
+    ) -> Result<Vec<ProductDetails<AccountId, Moment>>>;
+
+    #[rpc(name = "productRegistry_getProduct")]
+    fn get_product(
+        &self,
+        id: String,
+        at: Option<BlockHash>,
+    ) -> Result<Option<ProductDetails<AccountId, Moment>>>;
+}
+
+// Implements the product registry RPC on top of the runtime API
+pub struct ProductRegistry<C, B> {
+    client: Arc<C>,
+    _marker: PhantomData<B>,
+}
+
+impl<C, B> ProductRegistry<C, B> {
+    pub fn new(client: Arc<C>) -> Self {
+        ProductRegistry {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+impl<C, Block, AccountId, Moment> ProductRegistryApi<<Block as BlockT>::Hash, AccountId, Moment>
+    for ProductRegistry<C, Block>
+where
+    Block: BlockT,
+    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: ProductRegistryRuntimeApi<Block, AccountId, Moment>,
+    AccountId: Codec + Clone,
+    Moment: Codec + Clone + Display,
+{
+    fn products_of_org(
+        &self,
+        org: AccountId,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Vec<ProductDetails<AccountId, Moment>>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+        let products = api.products_of_org(&at, org).map_err(runtime_error)?;
+        Ok(products.into_iter().map(ProductDetails::from).collect())
+    }
+
+    fn get_product(
+        &self,
+        id: String,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<Option<ProductDetails<AccountId, Moment>>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+        let product = api
+            .get_product(&at, id.into_bytes())
+            .map_err(runtime_error)?;
+        Ok(product.map(ProductDetails::from))
+    }
+}
+
+fn runtime_error<E: std::fmt::Debug>(err: E) -> RpcError {
+    RpcError {
+        code: ErrorCode::ServerError(RUNTIME_ERROR),
+        message: "Unable to query products.".into(),
+        data: Some(format!("{:?}", err).into()),
+    }
+}
@@ -0,0 +1,27 @@
+//! # Substrate Enterprise Sample - Product Registry runtime API
+//!
+//! Allows clients (e.g. the node's RPC layer) to query products of the registry
+//! without decoding the pallet's storage themselves.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use product_registry::{Product, ProductId, PropName, PropValue};
+use sp_std::prelude::*;
+
+sp_api::decl_runtime_apis! {
+    pub trait ProductRegistryApi<AccountId, Moment> where
+        AccountId: Codec,
+        Moment: Codec,
+    {
+        // Returns the products registered by an organization
+        fn products_of_org(org: AccountId) -> Vec<Product<AccountId, Moment>>;
+
+        // Returns the product with the given ID, if it is registered
+        fn get_product(id: ProductId) -> Option<Product<AccountId, Moment>>;
+
+        // Returns the products of an organization whose prop has the given value,
+        // provided the prop is indexed by the runtime (see IndexedPropNames)
+        fn products_by_prop(org: AccountId, name: PropName, value: PropValue<Moment>) -> Vec<Product<AccountId, Moment>>;
+    }
+}
@@ -0,0 +1,287 @@
+//! Benchmarking setup for the product registry pallet
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+
+use frame_benchmarking::{account, benchmarks};
+use frame_system::RawOrigin;
+
+const SEED: u32 = 0;
+// Upper bound of the items (e.g. shipments) affected by a recall to benchmark
+const RECALL_MAX_AFFECTED: u32 = 1_000;
+
+// Origin passing the pallet's CreateRoleOrigin check, along with its account.
+// Products are owned by that account, so it also passes the ownership checks.
+fn org_origin<T: Trait>() -> (T::Origin, T::AccountId) {
+    let origin = T::CreateRoleOrigin::successful_origin();
+    let who = ensure_signed(origin.clone()).expect("successful origin is signed; qed");
+    (origin, who)
+}
+
+// Non-GTIN product ID, so that no GS1 validation is skipped or failed
+fn product_id(i: u32) -> ProductId {
+    let mut id = b"SKU-".to_vec();
+    id.extend_from_slice(&i.to_le_bytes());
+    id
+}
+
+// Worst case product props, with names & values of maximal length
+fn product_props<T: Trait>(count: u32, value: u8) -> Vec<ProductProperty<T::Moment>> {
+    (0..count)
+        .map(|i| {
+            let mut name = vec![b'n'; T::ProductPropNameMaxLength::get() as usize];
+            name[0] = i as u8;
+            let value = vec![value; T::ProductPropValueMaxLength::get() as usize];
+            ProductProperty::new(&name, PropValue::Text(value))
+        })
+        .collect()
+}
+
+// Certifier account, along with a product holding the maximum number of
+// certifications (but `free` ones) from that certifier
+fn certified_product<T: Trait>(free: u32) -> (T::AccountId, ProductId) {
+    let (_, owner) = org_origin::<T>();
+    let certifier: T::AccountId = account("certifier", 0, SEED);
+    T::Certifiers::add_certifier(&certifier);
+
+    let id = product_id(0);
+    Module::<T>::insert_product(id.clone(), owner, None);
+    let count = T::ProductMaxCertifications::get().saturating_sub(free);
+    let certifications = (0..count)
+        .map(|i| Certification {
+            id: certification_id::<T>(i),
+            certifier: certifier.clone(),
+            scheme: vec![b's'; T::CertificationMaxLength::get() as usize],
+            issued: 0u32.into(),
+            expires: None,
+            revoked: None,
+        })
+        .collect::<Vec<_>>();
+    <Certifications<T>>::insert(&id, certifications);
+    (certifier, id)
+}
+
+// Certificate number of maximal length
+fn certification_id<T: Trait>(i: u32) -> CertificateId {
+    let mut id = vec![b'c'; T::CertificationMaxLength::get() as usize];
+    id[..4].copy_from_slice(&i.to_le_bytes());
+    id
+}
+
+// Product with the maximum number of documents (but one), all of them superseded
+// except for the last one, along with the type of the latter
+fn documented_product<T: Trait>(owner: T::AccountId) -> (ProductId, DocumentType) {
+    let id = product_id(0);
+    Module::<T>::insert_product(id.clone(), owner, None);
+    let doc_type = vec![b't'; T::DocumentTypeMaxLength::get() as usize];
+    // Full list, so that the oldest superseded document gets pruned
+    let count = T::ProductMaxDocuments::get();
+    let documents = (0..count)
+        .map(|i| ProductDocument {
+            doc_type: doc_type.clone(),
+            hash: T::Hashing::hash(&i.to_le_bytes()),
+            uri: document_uri::<T>(),
+            added: 0u32.into(),
+            superseded: if i + 1 < count { Some(0u32.into()) } else { None },
+        })
+        .collect::<Vec<_>>();
+    <ProductDocuments<T>>::insert(&id, documents);
+    (id, doc_type)
+}
+
+// Document URI of maximal length
+fn document_uri<T: Trait>() -> Vec<u8> {
+    vec![b'u'; T::DocumentUriMaxLength::get() as usize]
+}
+
+benchmarks! {
+    _ { }
+
+    register_product {
+        let p in 0 .. T::ProductMaxProps::get();
+        let (origin, caller) = org_origin::<T>();
+        let id = product_id(0);
+    }: _(origin, id.clone(), caller.clone(), Some(product_props::<T>(p, b'v')))
+    verify {
+        assert_eq!(Module::<T>::owner_of(&id), Some(caller));
+    }
+
+    register_products {
+        let n in 1 .. T::ProductBatchMaxSize::get();
+        let (origin, caller) = org_origin::<T>();
+        let props = product_props::<T>(T::ProductMaxProps::get(), b'v');
+        let products: Vec<_> = (0..n)
+            .map(|i| (product_id(i), caller.clone(), Some(props.clone())))
+            .collect();
+    }: _(origin, products)
+    verify {
+        assert_eq!(Module::<T>::products_of_org(&caller).len(), n as usize);
+    }
+
+    transfer_product {
+        let (origin, caller) = org_origin::<T>();
+        let id = product_id(0);
+        Module::<T>::insert_product(id.clone(), caller, None);
+        let new_owner: T::AccountId = account("new_owner", 0, SEED);
+        T::Organizations::add_organization(&new_owner);
+    }: _(origin, id.clone(), new_owner.clone())
+    verify {
+        assert_eq!(Module::<T>::owner_of(&id), Some(new_owner));
+    }
+
+    set_product_props {
+        let p in 1 .. T::ProductMaxProps::get();
+        let (origin, caller) = org_origin::<T>();
+        let id = product_id(0);
+        Module::<T>::insert_product(id.clone(), caller, Some(product_props::<T>(p, b'v')));
+        // Replacing every existing prop records the most revisions
+        let props = product_props::<T>(p, b'w');
+    }: _(origin, id.clone(), props)
+    verify {
+        assert_eq!(Module::<T>::revisions_of(&id).len(), p as usize);
+    }
+
+    remove_product_prop {
+        let (origin, caller) = org_origin::<T>();
+        let id = product_id(0);
+        let props = product_props::<T>(T::ProductMaxProps::get(), b'v');
+        let name = props[props.len() - 1].name().to_vec();
+        Module::<T>::insert_product(id.clone(), caller, Some(props));
+    }: _(origin, id.clone(), name)
+    verify {
+        assert_eq!(Module::<T>::revisions_of(&id).len(), 1);
+    }
+
+    set_product_status {
+        let (origin, caller) = org_origin::<T>();
+        let id = product_id(0);
+        Module::<T>::insert_product(id.clone(), caller, None);
+    }: _(origin, id.clone(), ProductStatus::Discontinued)
+    verify {
+        assert_eq!(Module::<T>::status_of(&id), Some(ProductStatus::Discontinued));
+    }
+
+    recall_product {
+        let a in 0 .. RECALL_MAX_AFFECTED;
+        let (origin, caller) = org_origin::<T>();
+        let id = product_id(0);
+        Module::<T>::insert_product(id.clone(), caller, None);
+        T::OnProductRecalled::add_affected(&id, a);
+        let reason = vec![b'r'; T::RecallReasonMaxLength::get() as usize];
+    }: _(origin, id.clone(), reason, a)
+    verify {
+        assert!(Module::<T>::recall_of(&id).is_some());
+    }
+
+    register_lot {
+        let (origin, caller) = org_origin::<T>();
+        let id = product_id(0);
+        Module::<T>::insert_product(id.clone(), caller, None);
+        let lot_number = vec![b'l'; T::LotNumberMaxLength::get() as usize];
+    }: _(origin, id.clone(), lot_number.clone(), 1_000, 1u32.into(), Some(2u32.into()))
+    verify {
+        assert!(Module::<T>::lot_by_id(&id, &lot_number).is_some());
+    }
+
+    issue_certification {
+        let (certifier, id) = certified_product::<T>(1);
+        let last = T::ProductMaxCertifications::get() - 1;
+        let certification_id = certification_id::<T>(last);
+        let scheme = vec![b's'; T::CertificationMaxLength::get() as usize];
+    }: _(RawOrigin::Signed(certifier), id.clone(), certification_id, scheme, Some(1u32.into()))
+    verify {
+        assert_eq!(
+            Module::<T>::certifications_of(&id).len() as u32,
+            T::ProductMaxCertifications::get()
+        );
+    }
+
+    expire_certification {
+        let (certifier, id) = certified_product::<T>(0);
+        let last = T::ProductMaxCertifications::get() - 1;
+    }: _(RawOrigin::Signed(certifier), id.clone(), certification_id::<T>(last))
+    verify {
+        assert!(Module::<T>::certifications_of(&id)[last as usize].expires.is_some());
+    }
+
+    revoke_certification {
+        let (certifier, id) = certified_product::<T>(0);
+        let last = T::ProductMaxCertifications::get() - 1;
+    }: _(RawOrigin::Signed(certifier), id.clone(), certification_id::<T>(last))
+    verify {
+        assert!(Module::<T>::certifications_of(&id)[last as usize].revoked.is_some());
+    }
+
+    set_product_components {
+        let c in 1 .. T::ProductMaxComponents::get();
+        // Products below the components, walked to rule out cycles
+        let n in 0 .. T::ProductMaxComponentNodes::get() - T::ProductMaxComponents::get();
+        let (origin, caller) = org_origin::<T>();
+        let id = product_id(0);
+        Module::<T>::insert_product(id.clone(), caller.clone(), None);
+        let components = (1..=c)
+            .map(|i| {
+                Module::<T>::insert_product(product_id(i), caller.clone(), None);
+                ProductComponent { product_id: product_id(i), quantity: i }
+            })
+            .collect::<Vec<_>>();
+        // Chain of n products below the first component
+        let mut parent = product_id(1);
+        for i in c + 1..=c + n {
+            Module::<T>::insert_product(product_id(i), caller.clone(), None);
+            <ProductComponents>::insert(&parent, vec![ProductComponent { product_id: product_id(i), quantity: 1 }]);
+            parent = product_id(i);
+        }
+    }: _(origin, id.clone(), components)
+    verify {
+        assert_eq!(Module::<T>::components_of(&id).len() as u32, c);
+    }
+
+    add_product_document {
+        let (origin, caller) = org_origin::<T>();
+        let (id, _) = documented_product::<T>(caller);
+        // Document type of maximal length, not in use yet
+        let doc_type = vec![b'd'; T::DocumentTypeMaxLength::get() as usize];
+        let hash = T::Hashing::hash(b"content");
+    }: _(origin, id.clone(), doc_type.clone(), hash, document_uri::<T>())
+    verify {
+        assert!(Module::<T>::verify_document(&id, &doc_type, b"content"));
+    }
+
+    supersede_product_document {
+        let (origin, caller) = org_origin::<T>();
+        let (id, doc_type) = documented_product::<T>(caller);
+        let hash = T::Hashing::hash(b"content");
+    }: _(origin, id.clone(), doc_type.clone(), hash, document_uri::<T>())
+    verify {
+        assert!(Module::<T>::verify_document(&id, &doc_type, b"content"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{new_test_ext, Test};
+    use frame_support::assert_ok;
+
+    #[test]
+    fn test_benchmarks() {
+        new_test_ext().execute_with(|| {
+            assert_ok!(test_benchmark_register_product::<Test>());
+            assert_ok!(test_benchmark_register_products::<Test>());
+            assert_ok!(test_benchmark_transfer_product::<Test>());
+            assert_ok!(test_benchmark_set_product_props::<Test>());
+            assert_ok!(test_benchmark_remove_product_prop::<Test>());
+            assert_ok!(test_benchmark_set_product_status::<Test>());
+            assert_ok!(test_benchmark_recall_product::<Test>());
+            assert_ok!(test_benchmark_register_lot::<Test>());
+            assert_ok!(test_benchmark_issue_certification::<Test>());
+            assert_ok!(test_benchmark_expire_certification::<Test>());
+            assert_ok!(test_benchmark_revoke_certification::<Test>());
+            assert_ok!(test_benchmark_set_product_components::<Test>());
+            assert_ok!(test_benchmark_add_product_document::<Test>());
+            assert_ok!(test_benchmark_supersede_product_document::<Test>());
+        });
+    }
+}
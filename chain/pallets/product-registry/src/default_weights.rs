@@ -0,0 +1,91 @@
+//! Default weights for the product registry pallet, hand-estimated rather than benchmarked, to be used in tests & mock runtimes.
+//! Runtimes should use weights generated by benchmarking on their reference hardware.
+
+#![allow(unused_parens)]
+
+use frame_support::weights::{constants::RocksDbWeight as DbWeight, Weight};
+
+impl crate::WeightInfo for () {
+    fn register_product(p: u32) -> Weight {
+        (52_000_000 as Weight)
+            .saturating_add((1_200_000 as Weight).saturating_mul(p as Weight))
+            .saturating_add(DbWeight::get().reads(12 as Weight))
+            .saturating_add(DbWeight::get().writes(3 as Weight))
+            .saturating_add(DbWeight::get().writes((1 as Weight).saturating_mul(p as Weight)))
+    }
+    fn register_products(n: u32) -> Weight {
+        (10_000_000 as Weight)
+            .saturating_add((48_000_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(DbWeight::get().reads(1 as Weight))
+            .saturating_add(DbWeight::get().reads((11 as Weight).saturating_mul(n as Weight)))
+            .saturating_add(DbWeight::get().writes((6 as Weight).saturating_mul(n as Weight)))
+    }
+    fn transfer_product() -> Weight {
+        (45_000_000 as Weight)
+            .saturating_add(DbWeight::get().reads(4 as Weight))
+            .saturating_add(DbWeight::get().writes(10 as Weight))
+    }
+    fn set_product_props(p: u32) -> Weight {
+        (40_000_000 as Weight)
+            .saturating_add((2_500_000 as Weight).saturating_mul(p as Weight))
+            .saturating_add(DbWeight::get().reads(3 as Weight))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+            .saturating_add(DbWeight::get().writes((3 as Weight).saturating_mul(p as Weight)))
+    }
+    fn remove_product_prop() -> Weight {
+        (38_000_000 as Weight)
+            .saturating_add(DbWeight::get().reads(3 as Weight))
+            .saturating_add(DbWeight::get().writes(4 as Weight))
+    }
+    fn set_product_status() -> Weight {
+        (33_000_000 as Weight)
+            .saturating_add(DbWeight::get().reads(2 as Weight))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+    }
+    fn recall_product(a: u32) -> Weight {
+        (42_000_000 as Weight)
+            .saturating_add((9_000_000 as Weight).saturating_mul(a as Weight))
+            .saturating_add(DbWeight::get().reads(5 as Weight))
+            .saturating_add(DbWeight::get().reads((1 as Weight).saturating_mul(a as Weight)))
+            .saturating_add(DbWeight::get().writes(2 as Weight))
+    }
+    fn register_lot() -> Weight {
+        (36_000_000 as Weight)
+            .saturating_add(DbWeight::get().reads(3 as Weight))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+    }
+    fn issue_certification() -> Weight {
+        (40_000_000 as Weight)
+            .saturating_add(DbWeight::get().reads(3 as Weight))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+    }
+    fn expire_certification() -> Weight {
+        (35_000_000 as Weight)
+            .saturating_add(DbWeight::get().reads(1 as Weight))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+    }
+    fn revoke_certification() -> Weight {
+        (35_000_000 as Weight)
+            .saturating_add(DbWeight::get().reads(1 as Weight))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+    }
+    fn set_product_components(c: u32, n: u32) -> Weight {
+        (35_000_000 as Weight)
+            .saturating_add((6_000_000 as Weight).saturating_mul(c as Weight))
+            .saturating_add((6_000_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(DbWeight::get().reads(2 as Weight))
+            .saturating_add(DbWeight::get().reads((1 as Weight).saturating_mul(c as Weight)))
+            .saturating_add(DbWeight::get().reads((1 as Weight).saturating_mul(n as Weight)))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+    }
+    fn add_product_document() -> Weight {
+        (38_000_000 as Weight)
+            .saturating_add(DbWeight::get().reads(3 as Weight))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+    }
+    fn supersede_product_document() -> Weight {
+        (40_000_000 as Weight)
+            .saturating_add(DbWeight::get().reads(3 as Weight))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+    }
+}
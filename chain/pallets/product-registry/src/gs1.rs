@@ -0,0 +1,57 @@
+// Helpers for GS1 identification keys
+// See https://www.gs1.org/standards/id-keys/gtin
+
+use frame_support::sp_std::prelude::*;
+
+// Valid lengths of a GTIN: GTIN-8, GTIN-12 (UPC), GTIN-13 (EAN) & GTIN-14
+const GTIN_LENGTHS: [usize; 4] = [8, 12, 13, 14];
+// GS1 Company Prefixes are expressed relative to a 13-digit GTIN, and are 4 to 12 digits long
+const GTIN_PREFIX_LENGTH: usize = 13;
+const COMPANY_PREFIX_MIN_LENGTH: usize = 4;
+const COMPANY_PREFIX_MAX_LENGTH: usize = 12;
+
+// Returns true if the identifier is structured like a GTIN,
+// i.e. it is made only of digits and has a valid GTIN length.
+pub fn is_gtin(id: &[u8]) -> bool {
+    GTIN_LENGTHS.contains(&id.len()) && id.iter().all(u8::is_ascii_digit)
+}
+
+// Returns true if the last digit of the GTIN matches the check digit
+// computed from the other digits (standard GS1 modulo 10 algorithm).
+pub fn has_valid_check_digit(gtin: &[u8]) -> bool {
+    match gtin.split_last() {
+        Some((check_digit, digits)) => {
+            let sum: u32 = digits
+                .iter()
+                .rev()
+                .enumerate()
+                .map(|(i, d)| {
+                    let d = u32::from(d - b'0');
+                    if i % 2 == 0 {
+                        d * 3
+                    } else {
+                        d
+                    }
+                })
+                .sum();
+            u32::from(check_digit - b'0') == (10 - sum % 10) % 10
+        }
+        None => false,
+    }
+}
+
+// Returns the GS1 Company Prefixes the GTIN may have been allocated under, shortest first.
+// The GTIN is first normalised to 13 digits, by dropping the indicator digit
+// of a GTIN-14 or left-padding shorter GTINs with zeros.
+pub fn candidate_company_prefixes(gtin: &[u8]) -> Vec<Vec<u8>> {
+    let normalized: Vec<u8> = if gtin.len() > GTIN_PREFIX_LENGTH {
+        gtin[gtin.len() - GTIN_PREFIX_LENGTH..].to_vec()
+    } else {
+        let mut padded = vec![b'0'; GTIN_PREFIX_LENGTH - gtin.len()];
+        padded.extend_from_slice(gtin);
+        padded
+    };
+    (COMPANY_PREFIX_MIN_LENGTH..=COMPANY_PREFIX_MAX_LENGTH)
+        .map(|len| normalized[..len].to_vec())
+        .collect()
+}
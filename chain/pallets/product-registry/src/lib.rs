@@ -4,9 +4,16 @@
 
 use codec::{Decode, Encode};
 use core::result::Result;
+use fixed::types::I16F16;
 use frame_support::{
-    decl_error, decl_event, decl_module, decl_storage, dispatch, ensure, sp_runtime::RuntimeDebug,
-    sp_std::prelude::*, traits::EnsureOrigin,
+    decl_error, decl_event, decl_module, decl_storage, dispatch, ensure,
+    sp_runtime::{
+        traits::{Hash, Zero},
+        RuntimeDebug,
+    },
+    sp_std::{collections::btree_set::BTreeSet, prelude::*},
+    traits::{EnsureOrigin, Get},
+    weights::Weight,
 };
 use frame_system::{self as system, ensure_signed};
 
@@ -16,17 +23,19 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
-// General constraints to limit data size
-// Note: these could also be passed as trait config parameters
-pub const PRODUCT_ID_MAX_LENGTH: usize = 36;
-pub const PRODUCT_PROP_NAME_MAX_LENGTH: usize = 10;
-pub const PRODUCT_PROP_VALUE_MAX_LENGTH: usize = 20;
-pub const PRODUCT_MAX_PROPS: usize = 3;
+mod benchmarking;
+mod default_weights;
+mod gs1;
+mod migration;
 
 // Custom types
 pub type ProductId = Vec<u8>;
 pub type PropName = Vec<u8>;
-pub type PropValue = Vec<u8>;
+pub type LotNumber = Vec<u8>;
+pub type LotId = (ProductId, LotNumber);
+pub type CertificateId = Vec<u8>;
+pub type DocumentType = Vec<u8>;
+pub type Decimal = I16F16;
 
 // Product contains master data (aka class-level) about a trade item.
 // This data is typically registered once by the product's manufacturer / supplier,
@@ -44,25 +53,66 @@ pub struct Product<AccountId, Moment> {
     // This a series of properties describing the product.
     // Typically, there would at least be a textual description, and SKU.
     // It could also contain instance / lot master data e.g. expiration, weight, harvest date.
-    props: Option<Vec<ProductProperty>>,
+    props: Option<Vec<ProductProperty<Moment>>>,
     // Timestamp (approximate) at which the prodct was registered on-chain.
     registered: Moment,
+    // Lifecycle status of the product, products are Active when registered.
+    status: ProductStatus,
+}
+
+impl<AccountId, Moment> Product<AccountId, Moment> {
+    pub fn id(&self) -> &[u8] {
+        self.id.as_ref()
+    }
+
+    pub fn owner(&self) -> &AccountId {
+        &self.owner
+    }
+
+    pub fn props(&self) -> Option<&[ProductProperty<Moment>]> {
+        self.props.as_deref()
+    }
+
+    pub fn registered(&self) -> &Moment {
+        &self.registered
+    }
+
+    pub fn status(&self) -> &ProductStatus {
+        &self.status
+    }
+}
+
+// Lifecycle status of a product
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum ProductStatus {
+    // Product is available and can be shipped
+    Active,
+    // Product is no longer manufactured or supplied
+    Discontinued,
+    // Product has been recalled and must not be shipped
+    Recalled,
+}
+
+impl Default for ProductStatus {
+    fn default() -> Self {
+        ProductStatus::Active
+    }
 }
 
 // Contains a name-value pair for a product property e.g. description: Ingredient ABC
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
-pub struct ProductProperty {
+pub struct ProductProperty<Moment> {
     // Name of the product property e.g. desc or description
     name: PropName,
     // Value of the product property e.g. Ingredient ABC
-    value: PropValue,
+    value: PropValue<Moment>,
 }
 
-impl ProductProperty {
-    pub fn new(name: &[u8], value: &[u8]) -> Self {
+impl<Moment> ProductProperty<Moment> {
+    pub fn new(name: &[u8], value: PropValue<Moment>) -> Self {
         Self {
             name: name.to_vec(),
-            value: value.to_vec(),
+            value,
         }
     }
 
@@ -70,14 +120,253 @@ impl ProductProperty {
         self.name.as_ref()
     }
 
-    pub fn value(&self) -> &[u8] {
-        self.value.as_ref()
+    pub fn value(&self) -> &PropValue<Moment> {
+        &self.value
+    }
+}
+
+// Typed value of a product property
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum PropValue<Moment> {
+    // UTF-8 encoded text e.g. a description or SKU
+    Text(Vec<u8>),
+    // Whole number e.g. a quantity
+    Integer(i64),
+    // Fixed-point number e.g. a weight or volume
+    Decimal(Decimal),
+    // Point in time e.g. an expiry date
+    Date(Moment),
+    // Flag e.g. whether the product is organic
+    Boolean(bool),
+}
+
+// Records a single change made to a product property, so that a product's
+// master data can be reconstructed at any point in time.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ProductRevision<AccountId, BlockNumber, Moment> {
+    // Block number at which the change was made.
+    pub block_number: BlockNumber,
+    // Account that made the change.
+    pub author: AccountId,
+    // Name of the changed product property.
+    pub name: PropName,
+    // Value before the change, or None if the property was added.
+    pub old_value: Option<PropValue<Moment>>,
+    // Value after the change, or None if the property was removed.
+    pub new_value: Option<PropValue<Moment>>,
+}
+
+// Instance-level (lot / batch) master data of a product, e.g. a production run.
+// A lot is identified by its lot number, which is unique for a given product.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ProductLot<Moment> {
+    // Lot / batch number, typically a GS1 batch/lot number (AI 10)
+    pub lot_number: LotNumber,
+    // ID of the product this lot is made of
+    pub product_id: ProductId,
+    // Number of product units in the lot
+    pub quantity: u32,
+    // Timestamp at which the lot was produced
+    pub produced: Moment,
+    // Timestamp at which the lot expires, if it does
+    pub expires: Option<Moment>,
+}
+
+// Versions of the pallet's storage layout, used to run migrations on runtime upgrades
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Releases {
+    // Unversioned storage, from before the version was tracked: product
+    // properties hold raw byte values, and the products registered before
+    // lifecycle statuses were introduced have none
+    V1,
+    // Product properties hold typed values (see PropValue)
+    V2,
+    // Products are indexed by the value of their indexed props (see ProductsByProp)
+    V3,
+}
+
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1
+    }
+}
+
+// Gives the pallet knowledge of which accounts may act on behalf of an organization.
+pub trait Organizations<AccountId> {
+    // Returns true if the account is the organization itself or one of its members.
+    fn is_member(org: &AccountId, account: &AccountId) -> bool;
+    // Returns true if the account is a registered organization.
+    fn is_organization(account: &AccountId) -> bool;
+    // Returns the GS1 Company Prefix held by the organization, if any.
+    fn company_prefix(org: &AccountId) -> Option<Vec<u8>>;
+    // Returns the organization holding the GS1 Company Prefix, if any.
+    fn company_prefix_holder(prefix: &[u8]) -> Option<AccountId>;
+    // Registers the account as an organization, so that transfers can be benchmarked.
+    #[cfg(feature = "runtime-benchmarks")]
+    fn add_organization(account: &AccountId);
+}
+
+// Notice recorded when a product is recalled by its owner
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct RecallNotice<AccountId, Moment> {
+    // Reason of the recall e.g. contamination, labeling error
+    pub reason: Vec<u8>,
+    // Account that issued the recall
+    pub issuer: AccountId,
+    // Timestamp (approximate) at which the recall was issued on-chain
+    pub timestamp: Moment,
+}
+
+// Off-chain document (e.g. spec sheet, safety data sheet) anchored to a product
+// by the hash of its content, so that copies of it can be verified.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ProductDocument<Hash, Moment> {
+    // Document type e.g. spec, sds, coa
+    pub doc_type: DocumentType,
+    // Hash of the document content
+    pub hash: Hash,
+    // Location of the document e.g. https:// or ipfs:// URI
+    pub uri: Vec<u8>,
+    // Timestamp (approximate) at which the document was anchored
+    pub added: Moment,
+    // Timestamp (approximate) at which a newer version superseded the document, if it did
+    pub superseded: Option<Moment>,
+}
+
+// Component of a product's bill of materials (BOM), i.e. a quantity of
+// another (possibly third-party) product used to make it.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ProductComponent {
+    pub product_id: ProductId,
+    // Number of units of the component used per unit of the product
+    pub quantity: u32,
+}
+
+// Node of a product's upstream supply tree, as returned by `Module::provenance`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ProvenanceNode<AccountId> {
+    pub product_id: ProductId,
+    // Organization owning (i.e. supplying) the product
+    pub supplier: AccountId,
+    // Number of units used by the downstream product (1 for the root)
+    pub quantity: u32,
+    pub components: Vec<ProvenanceNode<AccountId>>,
+}
+
+// Attestation by a third-party certifier that a product complies with
+// a certification scheme e.g. organic farming, ISO 22000, halal.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Certification<AccountId, Moment> {
+    // Certificate number, unique for the product
+    pub id: CertificateId,
+    // Account of the certifier that issued (and signed) the certification
+    pub certifier: AccountId,
+    // Certification scheme e.g. organic, ISO 22000, halal
+    pub scheme: Vec<u8>,
+    // Timestamp (approximate) at which the certification was issued on-chain
+    pub issued: Moment,
+    // Timestamp at which the certification expires, if it does
+    pub expires: Option<Moment>,
+    // Timestamp (approximate) at which the certification was revoked, if it was
+    pub revoked: Option<Moment>,
+}
+
+impl<AccountId, Moment: PartialOrd> Certification<AccountId, Moment> {
+    // Returns true if the certification is in effect at the given time.
+    pub fn is_valid_at(&self, now: &Moment) -> bool {
+        self.revoked.is_none()
+            && &self.issued <= now
+            && self.expires.as_ref().map_or(true, |expires| now < expires)
+    }
+}
+
+// Gives the pallet knowledge of which accounts are accredited certifiers.
+pub trait Certifiers<AccountId> {
+    // Returns true if the account may issue certifications.
+    fn is_certifier(account: &AccountId) -> bool;
+    // Makes the account a certifier, so that certification calls can be benchmarked.
+    #[cfg(feature = "runtime-benchmarks")]
+    fn add_certifier(account: &AccountId);
+}
+
+// Handler for when a product has been recalled, to be implemented by pallets
+// that need to react to recalls (e.g. shipments carrying the product).
+// Its weight grows with the number of items (e.g. shipments) it processes.
+pub trait OnProductRecalled {
+    // Returns the number of items processed when the product gets recalled.
+    fn affected_count(id: &ProductId) -> u32;
+    fn on_product_recalled(id: &ProductId);
+    // Adds items processed on recall of the product, so that recalls can be benchmarked.
+    #[cfg(feature = "runtime-benchmarks")]
+    fn add_affected(id: &ProductId, count: u32);
+}
+
+impl OnProductRecalled for () {
+    fn affected_count(_id: &ProductId) -> u32 {
+        0
     }
+    fn on_product_recalled(_id: &ProductId) {}
+    #[cfg(feature = "runtime-benchmarks")]
+    fn add_affected(_id: &ProductId, _count: u32) {}
+}
+
+// Gives other pallets read access to the products in the registry.
+pub trait ProductInfo {
+    // Returns the status of the product, or None if the product is unknown.
+    fn status_of(id: &[u8]) -> Option<ProductStatus>;
+    // Returns true if the lot has been registered for the product.
+    fn lot_exists(id: &[u8], lot_number: &[u8]) -> bool;
+    // Registers an active product, so that shipments of it can be benchmarked.
+    #[cfg(feature = "runtime-benchmarks")]
+    fn add_product(id: &[u8]);
+}
+
+pub trait WeightInfo {
+    fn register_product(p: u32) -> Weight;
+    fn register_products(n: u32) -> Weight;
+    fn transfer_product() -> Weight;
+    fn set_product_props(p: u32) -> Weight;
+    fn remove_product_prop() -> Weight;
+    fn set_product_status() -> Weight;
+    fn recall_product(a: u32) -> Weight;
+    fn register_lot() -> Weight;
+    fn issue_certification() -> Weight;
+    fn expire_certification() -> Weight;
+    fn revoke_certification() -> Weight;
+    fn set_product_components(c: u32, n: u32) -> Weight;
+    fn add_product_document() -> Weight;
+    fn supersede_product_document() -> Weight;
 }
 
 pub trait Trait: system::Trait + timestamp::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
     type CreateRoleOrigin: EnsureOrigin<Self::Origin>;
+    type Organizations: Organizations<Self::AccountId>;
+    type OnProductRecalled: OnProductRecalled;
+    type Certifiers: Certifiers<Self::AccountId>;
+
+    // General constraints to limit data size
+    type ProductIdMaxLength: Get<u32>;
+    type ProductPropNameMaxLength: Get<u32>;
+    type ProductPropValueMaxLength: Get<u32>;
+    type ProductMaxProps: Get<u32>;
+    type RecallReasonMaxLength: Get<u32>;
+    type ProductBatchMaxSize: Get<u32>;
+    type LotNumberMaxLength: Get<u32>;
+    type CertificationMaxLength: Get<u32>;
+    type ProductMaxCertifications: Get<u32>;
+    type ProductMaxComponents: Get<u32>;
+    type ProductMaxComponentNodes: Get<u32>;
+    type DocumentTypeMaxLength: Get<u32>;
+    type DocumentUriMaxLength: Get<u32>;
+    type ProductMaxDocuments: Get<u32>;
+
+    // Names of the product props to index by value, for each organization
+    // (see `products_by_prop`). Leave empty to disable the index.
+    type IndexedPropNames: Get<Vec<PropName>>;
+
+    // Weight information for the extrinsics of this pallet
+    type WeightInfo: WeightInfo;
 }
 
 decl_storage! {
@@ -85,6 +374,17 @@ decl_storage! {
         pub Products get(fn product_by_id): map hasher(blake2_128_concat) ProductId => Option<Product<T::AccountId, T::Moment>>;
         pub ProductsOfOrganization get(fn products_of_org): map hasher(blake2_128_concat) T::AccountId => Vec<ProductId>;
         pub OwnerOf get(fn owner_of): map hasher(blake2_128_concat) ProductId => Option<T::AccountId>;
+        pub ProductRevisions get(fn revisions_of): map hasher(blake2_128_concat) ProductId => Vec<ProductRevision<T::AccountId, T::BlockNumber, T::Moment>>;
+        // Index of (owner, prop name, hash of prop value) to product IDs, for indexed props only
+        pub ProductsByProp: map hasher(blake2_128_concat) (T::AccountId, PropName, T::Hash) => Vec<ProductId>;
+        pub Recalls get(fn recall_of): map hasher(blake2_128_concat) ProductId => Option<RecallNotice<T::AccountId, T::Moment>>;
+        pub Certifications get(fn certifications_of): map hasher(blake2_128_concat) ProductId => Vec<Certification<T::AccountId, T::Moment>>;
+        pub ProductComponents get(fn components_of): map hasher(blake2_128_concat) ProductId => Vec<ProductComponent>;
+        pub ProductDocuments get(fn documents_of): map hasher(blake2_128_concat) ProductId => Vec<ProductDocument<T::Hash, T::Moment>>;
+        pub ProductLots get(fn lot_by_id): double_map hasher(blake2_128_concat) ProductId, hasher(blake2_128_concat) LotNumber => Option<ProductLot<T::Moment>>;
+
+        // Storage layout version, new chains start with the latest one
+        StorageVersion build(|_| Releases::V3): Releases;
     }
 }
 
@@ -94,6 +394,18 @@ decl_event!(
         AccountId = <T as system::Trait>::AccountId,
     {
         ProductRegistered(AccountId, ProductId, AccountId),
+        ProductOwnershipTransferred(AccountId, ProductId, AccountId, AccountId),
+        ProductPropsUpdated(AccountId, ProductId),
+        ProductPropRemoved(AccountId, ProductId, PropName),
+        ProductStatusUpdated(AccountId, ProductId, ProductStatus),
+        ProductRecalled(AccountId, ProductId),
+        ProductLotRegistered(AccountId, ProductId, LotNumber),
+        CertificationIssued(AccountId, ProductId, CertificateId),
+        CertificationExpired(AccountId, ProductId, CertificateId),
+        CertificationRevoked(AccountId, ProductId, CertificateId),
+        ProductComponentsUpdated(AccountId, ProductId),
+        ProductDocumentAdded(AccountId, ProductId, DocumentType),
+        ProductDocumentSuperseded(AccountId, ProductId, DocumentType),
     }
 );
 
@@ -101,10 +413,48 @@ decl_error! {
     pub enum Error for Module<T: Trait> {
         ProductIdMissing,
         ProductIdTooLong,
+        ProductIdInvalidGtin,
+        ProductIdPrefixMismatch,
         ProductIdExists,
+        ProductIdUnknown,
+        ProductBatchTooLarge,
+        ProductOwnerUnchanged,
+        ProductOwnerNotOrganization,
         ProductTooManyProps,
         ProductInvalidPropName,
-        ProductInvalidPropValue
+        ProductInvalidPropValue,
+        ProductPropUnknown,
+        ProductStatusUnchanged,
+        ProductAlreadyRecalled,
+        ProductRecallRequiresNotice,
+        ProductRecallReasonTooLong,
+        ProductRecallWitnessTooLow,
+        ProductLotNumberMissing,
+        ProductLotNumberTooLong,
+        ProductLotExists,
+        ProductLotInvalidQuantity,
+        ProductLotInvalidDates,
+        ProductTooManyCertifications,
+        CertificationInvalidId,
+        CertificationInvalidScheme,
+        CertificationInvalidExpiry,
+        CertificationExists,
+        CertificationUnknown,
+        CertificationEnded,
+        NotCertifier,
+        NotCertificationIssuer,
+        ProductTooManyComponents,
+        ProductComponentUnknown,
+        ProductComponentDuplicate,
+        ProductComponentInvalidQuantity,
+        ProductComponentCycle,
+        ProductComponentTreeTooLarge,
+        ProductTooManyDocuments,
+        ProductDocumentInvalidType,
+        ProductDocumentInvalidUri,
+        ProductDocumentExists,
+        ProductDocumentUnknown,
+        NotProductOwner
     }
 }
 
@@ -113,8 +463,27 @@ decl_module! {
         type Error = Error<T>;
         fn deposit_event() = default;
 
-        #[weight = 10_000]
-        pub fn register_product(origin, id: ProductId, owner: T::AccountId, props: Option<Vec<ProductProperty>>) -> dispatch::DispatchResult {
+        const ProductIdMaxLength: u32 = T::ProductIdMaxLength::get();
+        const ProductPropNameMaxLength: u32 = T::ProductPropNameMaxLength::get();
+        const ProductPropValueMaxLength: u32 = T::ProductPropValueMaxLength::get();
+        const ProductMaxProps: u32 = T::ProductMaxProps::get();
+        const RecallReasonMaxLength: u32 = T::RecallReasonMaxLength::get();
+        const ProductBatchMaxSize: u32 = T::ProductBatchMaxSize::get();
+        const LotNumberMaxLength: u32 = T::LotNumberMaxLength::get();
+        const CertificationMaxLength: u32 = T::CertificationMaxLength::get();
+        const ProductMaxCertifications: u32 = T::ProductMaxCertifications::get();
+        const ProductMaxComponents: u32 = T::ProductMaxComponents::get();
+        const ProductMaxComponentNodes: u32 = T::ProductMaxComponentNodes::get();
+        const DocumentTypeMaxLength: u32 = T::DocumentTypeMaxLength::get();
+        const DocumentUriMaxLength: u32 = T::DocumentUriMaxLength::get();
+        const ProductMaxDocuments: u32 = T::ProductMaxDocuments::get();
+
+        fn on_runtime_upgrade() -> Weight {
+            migration::migrate::<T>()
+        }
+
+        #[weight = T::WeightInfo::register_product(props.as_ref().map_or(0, |p| p.len()) as u32)]
+        pub fn register_product(origin, id: ProductId, owner: T::AccountId, props: Option<Vec<ProductProperty<T::Moment>>>) -> dispatch::DispatchResult {
             T::CreateRoleOrigin::ensure_origin(origin.clone())?;
             let who = ensure_signed(origin)?;
 
@@ -127,27 +496,476 @@ decl_module! {
             // Check product doesn't exist yet (1 DB read)
             Self::validate_new_product(&id)?;
 
-            // TODO: if organization has an attribute w/ GS1 Company prefix,
-            //       additional validation could be applied to the product ID
-            //       to ensure its validity (same company prefix as org).
-
-            // Create a product instance
-            let product = Self::new_product()
-                .identified_by(id.clone())
-                .owned_by(owner.clone())
-                .registered_on(<timestamp::Module<T>>::now())
-                .with_props(props)
-                .build();
+            // Check product ID against the GS1 Company Prefixes held by organizations (up to 10 DB reads)
+            Self::validate_product_id_prefix(&id, &owner)?;
 
             // Add product & ownerOf (3 DB writes)
-            <Products<T>>::insert(&id, product);
-            <ProductsOfOrganization<T>>::append(&owner, &id);
-            <OwnerOf<T>>::insert(&id, &owner);
+            Self::insert_product(id.clone(), owner.clone(), props);
 
             Self::deposit_event(RawEvent::ProductRegistered(who, id, owner));
 
             Ok(())
         }
+
+        #[weight = T::WeightInfo::register_products(products.len() as u32)]
+        pub fn register_products(origin, products: Vec<(ProductId, T::AccountId, Option<Vec<ProductProperty<T::Moment>>>)>) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            ensure!(
+                products.len() <= T::ProductBatchMaxSize::get() as usize,
+                Error::<T>::ProductBatchTooLarge
+            );
+
+            // Validate the whole batch before storing anything (up to 11 DB reads per product)
+            let mut batch_ids = BTreeSet::new();
+            for (id, owner, props) in products.iter() {
+                Self::validate_product_id(id)?;
+                Self::validate_product_props(props)?;
+                Self::validate_new_product(id)?;
+                Self::validate_product_id_prefix(id, owner)?;
+                ensure!(batch_ids.insert(id), Error::<T>::ProductIdExists);
+            }
+
+            for (id, owner, props) in products {
+                // Add product & ownerOf (3 DB writes per product)
+                Self::insert_product(id.clone(), owner.clone(), props);
+
+                Self::deposit_event(RawEvent::ProductRegistered(who.clone(), id, owner));
+            }
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::transfer_product()]
+        pub fn transfer_product(origin, id: ProductId, new_owner: T::AccountId) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            // Check product is known & sender acts for its owner (1 DB read)
+            let mut product = <Products<T>>::get(&id).ok_or(Error::<T>::ProductIdUnknown)?;
+            Self::validate_product_owner(&product, &who)?;
+            let old_owner = product.owner.clone();
+            ensure!(old_owner != new_owner, Error::<T>::ProductOwnerUnchanged);
+            // Products may only be transferred to registered organizations (1 DB read)
+            ensure!(
+                T::Organizations::is_organization(&new_owner),
+                Error::<T>::ProductOwnerNotOrganization
+            );
+
+            let old_index_keys = Self::index_keys(&product);
+            product.owner = new_owner.clone();
+
+            // Move indexed props to the new owner (2 DB writes per indexed prop)
+            Self::reindex_product(&id, old_index_keys, Self::index_keys(&product));
+
+            // Update product & ownerOf (4 DB writes)
+            <Products<T>>::insert(&id, product);
+            <ProductsOfOrganization<T>>::mutate(&old_owner, |ids| ids.retain(|pid| pid != &id));
+            <ProductsOfOrganization<T>>::append(&new_owner, &id);
+            <OwnerOf<T>>::insert(&id, &new_owner);
+
+            Self::deposit_event(RawEvent::ProductOwnershipTransferred(who, id, old_owner, new_owner));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::set_product_props(props.len() as u32)]
+        pub fn set_product_props(origin, id: ProductId, props: Vec<ProductProperty<T::Moment>>) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            // Validate product props
+            let props = Some(props);
+            Self::validate_product_props(&props)?;
+
+            // Check product is known & sender acts for its owner (1 DB read)
+            let mut product = <Products<T>>::get(&id).ok_or(Error::<T>::ProductIdUnknown)?;
+            Self::validate_product_owner(&product, &who)?;
+            let old_index_keys = Self::index_keys(&product);
+
+            // Add new props or replace existing ones, keeping track of changes
+            let block_number = <system::Module<T>>::block_number();
+            let mut current_props = product.props.unwrap_or_default();
+            let mut revisions = Vec::new();
+            for prop in props.unwrap_or_default() {
+                let old_value = match current_props.iter().position(|p| p.name == prop.name) {
+                    Some(pos) if current_props[pos].value == prop.value => continue,
+                    Some(pos) => Some(core::mem::replace(&mut current_props[pos].value, prop.value.clone())),
+                    None => {
+                        current_props.push(prop.clone());
+                        None
+                    }
+                };
+                revisions.push(ProductRevision {
+                    block_number,
+                    author: who.clone(),
+                    name: prop.name,
+                    old_value,
+                    new_value: Some(prop.value),
+                });
+            }
+
+            ensure!(
+                current_props.len() <= T::ProductMaxProps::get() as usize,
+                Error::<T>::ProductTooManyProps,
+            );
+            product.props = Some(current_props);
+
+            // Update index for changed indexed props (2 DB writes per indexed prop)
+            Self::reindex_product(&id, old_index_keys, Self::index_keys(&product));
+
+            // Update product (1 DB write) & append revisions, without decoding
+            // the existing log (1 DB write per revision)
+            <Products<T>>::insert(&id, product);
+            for revision in revisions {
+                <ProductRevisions<T>>::append(&id, revision);
+            }
+
+            Self::deposit_event(RawEvent::ProductPropsUpdated(who, id));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::remove_product_prop()]
+        pub fn remove_product_prop(origin, id: ProductId, name: PropName) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            // Check product is known & sender acts for its owner (1 DB read)
+            let mut product = <Products<T>>::get(&id).ok_or(Error::<T>::ProductIdUnknown)?;
+            Self::validate_product_owner(&product, &who)?;
+
+            // Remove the prop, dropping the props altogether when none are left
+            let old_index_keys = Self::index_keys(&product);
+            let mut current_props = product.props.unwrap_or_default();
+            let pos = current_props
+                .iter()
+                .position(|p| p.name == name)
+                .ok_or(Error::<T>::ProductPropUnknown)?;
+            let removed = current_props.remove(pos);
+            product.props = if current_props.is_empty() { None } else { Some(current_props) };
+
+            let revision = ProductRevision {
+                block_number: <system::Module<T>>::block_number(),
+                author: who.clone(),
+                name: name.clone(),
+                old_value: Some(removed.value),
+                new_value: None,
+            };
+
+            // Update index if the prop was indexed (1 DB write)
+            Self::reindex_product(&id, old_index_keys, Self::index_keys(&product));
+
+            // Update product & revisions (2 DB writes)
+            <Products<T>>::insert(&id, product);
+            <ProductRevisions<T>>::append(&id, revision);
+
+            Self::deposit_event(RawEvent::ProductPropRemoved(who, id, name));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::set_product_status()]
+        pub fn set_product_status(origin, id: ProductId, status: ProductStatus) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            // Check product is known & sender acts for its owner (1 DB read)
+            let mut product = <Products<T>>::get(&id).ok_or(Error::<T>::ProductIdUnknown)?;
+            Self::validate_product_owner(&product, &who)?;
+            ensure!(product.status != status, Error::<T>::ProductStatusUnchanged);
+            // Recalls are final
+            ensure!(product.status != ProductStatus::Recalled, Error::<T>::ProductAlreadyRecalled);
+            // Recalls must go through recall_product, so that a notice is recorded
+            ensure!(status != ProductStatus::Recalled, Error::<T>::ProductRecallRequiresNotice);
+
+            product.status = status.clone();
+
+            // Update product (1 DB write)
+            <Products<T>>::insert(&id, product);
+
+            Self::deposit_event(RawEvent::ProductStatusUpdated(who, id, status));
+
+            Ok(())
+        }
+
+        // The weight is charged for the `affected_witness` items processed by the OnProductRecalled
+        // handler (e.g. shipments carrying the product), and refunded down to the actual count.
+        #[weight = T::WeightInfo::recall_product(*affected_witness)]
+        pub fn recall_product(
+            origin,
+            id: ProductId,
+            reason: Vec<u8>,
+            affected_witness: u32
+        ) -> dispatch::DispatchResultWithPostInfo {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            ensure!(
+                reason.len() <= T::RecallReasonMaxLength::get() as usize,
+                Error::<T>::ProductRecallReasonTooLong
+            );
+
+            // Check product is known & sender acts for its owner (1 DB read)
+            let mut product = <Products<T>>::get(&id).ok_or(Error::<T>::ProductIdUnknown)?;
+            Self::validate_product_owner(&product, &who)?;
+            ensure!(product.status != ProductStatus::Recalled, Error::<T>::ProductAlreadyRecalled);
+
+            // Check the weight paid covers the items the recall affects (1 DB read)
+            let affected = T::OnProductRecalled::affected_count(&id);
+            ensure!(affected <= affected_witness, Error::<T>::ProductRecallWitnessTooLow);
+
+            product.status = ProductStatus::Recalled;
+            let notice = RecallNotice {
+                reason,
+                issuer: who.clone(),
+                timestamp: <timestamp::Module<T>>::now(),
+            };
+
+            // Update product & store recall notice (2 DB writes)
+            <Products<T>>::insert(&id, product);
+            <Recalls<T>>::insert(&id, notice);
+
+            Self::deposit_event(RawEvent::ProductRecalled(who, id.clone()));
+
+            // Let other pallets react to the recall
+            T::OnProductRecalled::on_product_recalled(&id);
+
+            Ok(Some(T::WeightInfo::recall_product(affected)).into())
+        }
+
+        #[weight = T::WeightInfo::register_lot()]
+        pub fn register_lot(
+            origin,
+            product_id: ProductId,
+            lot_number: LotNumber,
+            quantity: u32,
+            produced: T::Moment,
+            expires: Option<T::Moment>
+        ) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            // Validate lot number & data
+            Self::validate_lot_number(&lot_number)?;
+            ensure!(quantity > 0, Error::<T>::ProductLotInvalidQuantity);
+            ensure!(
+                expires.map_or(true, |expires| expires > produced),
+                Error::<T>::ProductLotInvalidDates
+            );
+
+            // Check product is known & sender acts for its owner (1 DB read)
+            let product = <Products<T>>::get(&product_id).ok_or(Error::<T>::ProductIdUnknown)?;
+            Self::validate_product_owner(&product, &who)?;
+
+            // Check lot doesn't exist yet (1 DB read)
+            ensure!(
+                !<ProductLots<T>>::contains_key(&product_id, &lot_number),
+                Error::<T>::ProductLotExists
+            );
+
+            let lot = ProductLot {
+                lot_number: lot_number.clone(),
+                product_id: product_id.clone(),
+                quantity,
+                produced,
+                expires,
+            };
+
+            // Add lot (1 DB write)
+            <ProductLots<T>>::insert(&product_id, &lot_number, lot);
+
+            Self::deposit_event(RawEvent::ProductLotRegistered(who, product_id, lot_number));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::set_product_components(
+            components.len() as u32,
+            T::ProductMaxComponentNodes::get()
+        )]
+        pub fn set_product_components(
+            origin,
+            product_id: ProductId,
+            components: Vec<ProductComponent>
+        ) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            ensure!(
+                components.len() <= T::ProductMaxComponents::get() as usize,
+                Error::<T>::ProductTooManyComponents
+            );
+
+            // Check product is known & sender acts for its owner (1 DB read)
+            let product = <Products<T>>::get(&product_id).ok_or(Error::<T>::ProductIdUnknown)?;
+            Self::validate_product_owner(&product, &who)?;
+
+            // Validate components (1 DB read per component, plus up to
+            // ProductMaxComponentNodes reads of components to rule out cycles)
+            Self::validate_product_components(&product_id, &components)?;
+
+            // Replace the bill of materials (1 DB write)
+            if components.is_empty() {
+                <ProductComponents>::remove(&product_id);
+            } else {
+                <ProductComponents>::insert(&product_id, components);
+            }
+
+            Self::deposit_event(RawEvent::ProductComponentsUpdated(who, product_id));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::add_product_document()]
+        pub fn add_product_document(
+            origin,
+            product_id: ProductId,
+            doc_type: DocumentType,
+            hash: T::Hash,
+            uri: Vec<u8>
+        ) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            Self::validate_product_document(&doc_type, &uri)?;
+
+            // Check product is known & sender acts for its owner (1 DB read)
+            let product = <Products<T>>::get(&product_id).ok_or(Error::<T>::ProductIdUnknown)?;
+            Self::validate_product_owner(&product, &who)?;
+
+            // Check there's no current document of that type (1 DB read)
+            let mut documents = <ProductDocuments<T>>::get(&product_id);
+            ensure!(
+                Self::current_document(&documents, &doc_type).is_none(),
+                Error::<T>::ProductDocumentExists
+            );
+
+            Self::push_document(&mut documents, doc_type.clone(), hash, uri)?;
+
+            // Update documents (1 DB write)
+            <ProductDocuments<T>>::insert(&product_id, documents);
+
+            Self::deposit_event(RawEvent::ProductDocumentAdded(who, product_id, doc_type));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::supersede_product_document()]
+        pub fn supersede_product_document(
+            origin,
+            product_id: ProductId,
+            doc_type: DocumentType,
+            hash: T::Hash,
+            uri: Vec<u8>
+        ) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            Self::validate_product_document(&doc_type, &uri)?;
+
+            // Check product is known & sender acts for its owner (1 DB read)
+            let product = <Products<T>>::get(&product_id).ok_or(Error::<T>::ProductIdUnknown)?;
+            Self::validate_product_owner(&product, &who)?;
+
+            // Retire the current document of that type (1 DB read)
+            let mut documents = <ProductDocuments<T>>::get(&product_id);
+            let index = Self::current_document(&documents, &doc_type)
+                .ok_or(Error::<T>::ProductDocumentUnknown)?;
+            documents[index].superseded = Some(<timestamp::Module<T>>::now());
+
+            Self::push_document(&mut documents, doc_type.clone(), hash, uri)?;
+
+            // Update documents (1 DB write)
+            <ProductDocuments<T>>::insert(&product_id, documents);
+
+            Self::deposit_event(RawEvent::ProductDocumentSuperseded(who, product_id, doc_type));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::issue_certification()]
+        pub fn issue_certification(
+            origin,
+            product_id: ProductId,
+            id: CertificateId,
+            scheme: Vec<u8>,
+            expires: Option<T::Moment>
+        ) -> dispatch::DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            // Check sender is an accredited certifier (1 DB read)
+            ensure!(T::Certifiers::is_certifier(&who), Error::<T>::NotCertifier);
+
+            // Validate certification data
+            let max_length = T::CertificationMaxLength::get() as usize;
+            ensure!(!id.is_empty() && id.len() <= max_length, Error::<T>::CertificationInvalidId);
+            ensure!(!scheme.is_empty() && scheme.len() <= max_length, Error::<T>::CertificationInvalidScheme);
+            let now = <timestamp::Module<T>>::now();
+            ensure!(
+                expires.map_or(true, |expires| expires > now),
+                Error::<T>::CertificationInvalidExpiry
+            );
+
+            // Check product is known (1 DB read)
+            ensure!(<Products<T>>::contains_key(&product_id), Error::<T>::ProductIdUnknown);
+
+            // Check certificate number is unused for the product (1 DB read)
+            let mut certifications = <Certifications<T>>::get(&product_id);
+            ensure!(
+                certifications.iter().all(|c| c.id != id),
+                Error::<T>::CertificationExists
+            );
+            ensure!(
+                certifications.len() < T::ProductMaxCertifications::get() as usize,
+                Error::<T>::ProductTooManyCertifications
+            );
+
+            certifications.push(Certification {
+                id: id.clone(),
+                certifier: who.clone(),
+                scheme,
+                issued: now,
+                expires,
+                revoked: None,
+            });
+
+            // Update certifications (1 DB write)
+            <Certifications<T>>::insert(&product_id, certifications);
+
+            Self::deposit_event(RawEvent::CertificationIssued(who, product_id, id));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::expire_certification()]
+        pub fn expire_certification(origin, product_id: ProductId, id: CertificateId) -> dispatch::DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            // Certification ends now, as planned by its certifier (1 DB read, 1 DB write)
+            Self::end_certification(&who, &product_id, &id, |certification, now| {
+                certification.expires = Some(now)
+            })?;
+
+            Self::deposit_event(RawEvent::CertificationExpired(who, product_id, id));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::revoke_certification()]
+        pub fn revoke_certification(origin, product_id: ProductId, id: CertificateId) -> dispatch::DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            // Certification is withdrawn by its certifier (1 DB read, 1 DB write)
+            Self::end_certification(&who, &product_id, &id, |certification, now| {
+                certification.revoked = Some(now)
+            })?;
+
+            Self::deposit_event(RawEvent::CertificationRevoked(who, product_id, id));
+
+            Ok(())
+        }
     }
 }
 
@@ -157,13 +975,274 @@ impl<T: Trait> Module<T> {
         ProductBuilder::<T::AccountId, T::Moment>::default()
     }
 
+    fn insert_product(id: ProductId, owner: T::AccountId, props: Option<Vec<ProductProperty<T::Moment>>>) {
+        // Create a product instance
+        let product = Self::new_product()
+            .identified_by(id.clone())
+            .owned_by(owner.clone())
+            .registered_on(<timestamp::Module<T>>::now())
+            .with_props(props)
+            .build();
+
+        Self::reindex_product(&id, BTreeSet::new(), Self::index_keys(&product));
+        <Products<T>>::insert(&id, product);
+        <ProductsOfOrganization<T>>::append(&owner, &id);
+        <OwnerOf<T>>::insert(&id, &owner);
+    }
+
     pub fn validate_product_id(id: &[u8]) -> Result<(), Error<T>> {
         // Basic product ID validation
         ensure!(!id.is_empty(), Error::<T>::ProductIdMissing);
         ensure!(
-            id.len() <= PRODUCT_ID_MAX_LENGTH,
+            id.len() <= T::ProductIdMaxLength::get() as usize,
             Error::<T>::ProductIdTooLong
         );
+        // GTIN-structured IDs must carry a valid check digit
+        ensure!(
+            !gs1::is_gtin(id) || gs1::has_valid_check_digit(id),
+            Error::<T>::ProductIdInvalidGtin
+        );
+        Ok(())
+    }
+
+    pub fn validate_product_id_prefix(id: &[u8], owner: &T::AccountId) -> Result<(), Error<T>> {
+        // A GTIN allocated under a GS1 Company Prefix may only be
+        // registered by the organization holding that prefix
+        if gs1::is_gtin(id) {
+            if let Some(holder) = gs1::candidate_company_prefixes(id)
+                .iter()
+                .find_map(|prefix| T::Organizations::company_prefix_holder(prefix))
+            {
+                ensure!(&holder == owner, Error::<T>::ProductIdPrefixMismatch);
+                return Ok(());
+            }
+        }
+        // When the owning organization has a GS1 Company Prefix,
+        // the product ID must be a GTIN allocated under that prefix
+        ensure!(
+            T::Organizations::company_prefix(owner).is_none(),
+            Error::<T>::ProductIdPrefixMismatch
+        );
+        Ok(())
+    }
+
+    fn end_certification(
+        who: &T::AccountId,
+        product_id: &[u8],
+        id: &[u8],
+        end: impl FnOnce(&mut Certification<T::AccountId, T::Moment>, T::Moment),
+    ) -> Result<(), Error<T>> {
+        let now = <timestamp::Module<T>>::now();
+        let mut certifications = <Certifications<T>>::get(product_id);
+        let certification = certifications
+            .iter_mut()
+            .find(|c| c.id == id)
+            .ok_or(Error::<T>::CertificationUnknown)?;
+        // Only the issuing certifier may end a certification, which must still be in effect
+        ensure!(&certification.certifier == who, Error::<T>::NotCertificationIssuer);
+        ensure!(certification.is_valid_at(&now), Error::<T>::CertificationEnded);
+
+        end(certification, now);
+        <Certifications<T>>::insert(product_id, certifications);
+        Ok(())
+    }
+
+    // Returns the certifications of the product that are in effect at the given time
+    pub fn valid_certifications(
+        product_id: &[u8],
+        now: T::Moment,
+    ) -> Vec<Certification<T::AccountId, T::Moment>> {
+        <Certifications<T>>::get(product_id)
+            .into_iter()
+            .filter(|certification| certification.is_valid_at(&now))
+            .collect()
+    }
+
+    // Returns the IDs of the products of the organization whose (indexed) prop has the given value
+    pub fn products_by_prop(
+        org: T::AccountId,
+        name: PropName,
+        value: &PropValue<T::Moment>,
+    ) -> Vec<ProductId> {
+        <ProductsByProp<T>>::get((org, name, T::Hashing::hash_of(value)))
+    }
+
+    // Returns the keys under which the product is indexed
+    fn index_keys(
+        product: &Product<T::AccountId, T::Moment>,
+    ) -> BTreeSet<(T::AccountId, PropName, T::Hash)> {
+        let indexed_names = T::IndexedPropNames::get();
+        product
+            .props
+            .iter()
+            .flatten()
+            .filter(|prop| indexed_names.contains(&prop.name))
+            .map(|prop| {
+                (
+                    product.owner.clone(),
+                    prop.name.clone(),
+                    T::Hashing::hash_of(&prop.value),
+                )
+            })
+            .collect()
+    }
+
+    // Moves the product from the index keys it no longer matches to the new ones
+    fn reindex_product(
+        id: &[u8],
+        old_keys: BTreeSet<(T::AccountId, PropName, T::Hash)>,
+        new_keys: BTreeSet<(T::AccountId, PropName, T::Hash)>,
+    ) {
+        for key in old_keys.difference(&new_keys) {
+            <ProductsByProp<T>>::mutate(key, |ids| ids.retain(|pid| pid.as_slice() != id));
+        }
+        for key in new_keys.difference(&old_keys) {
+            <ProductsByProp<T>>::append(key, id);
+        }
+    }
+
+    pub fn validate_product_document(doc_type: &[u8], uri: &[u8]) -> Result<(), Error<T>> {
+        ensure!(
+            !doc_type.is_empty() && doc_type.len() <= T::DocumentTypeMaxLength::get() as usize,
+            Error::<T>::ProductDocumentInvalidType
+        );
+        ensure!(
+            !uri.is_empty() && uri.len() <= T::DocumentUriMaxLength::get() as usize,
+            Error::<T>::ProductDocumentInvalidUri
+        );
+        Ok(())
+    }
+
+    // Returns the index of the current (i.e. not superseded) document of the given type
+    fn current_document(
+        documents: &[ProductDocument<T::Hash, T::Moment>],
+        doc_type: &[u8],
+    ) -> Option<usize> {
+        documents
+            .iter()
+            .position(|document| document.doc_type == doc_type && document.superseded.is_none())
+    }
+
+    fn push_document(
+        documents: &mut Vec<ProductDocument<T::Hash, T::Moment>>,
+        doc_type: DocumentType,
+        hash: T::Hash,
+        uri: Vec<u8>,
+    ) -> Result<(), Error<T>> {
+        // Superseded documents are kept until the limit is reached, the oldest
+        // one being pruned to make room. Current documents are never pruned.
+        if documents.len() >= T::ProductMaxDocuments::get() as usize {
+            let oldest = documents
+                .iter()
+                .position(|document| document.superseded.is_some())
+                .ok_or(Error::<T>::ProductTooManyDocuments)?;
+            documents.remove(oldest);
+        }
+        documents.push(ProductDocument {
+            doc_type,
+            hash,
+            uri,
+            added: <timestamp::Module<T>>::now(),
+            superseded: None,
+        });
+        Ok(())
+    }
+
+    // Returns true if the content matches the current document of the given type
+    pub fn verify_document(product_id: &[u8], doc_type: &[u8], content: &[u8]) -> bool {
+        let documents = <ProductDocuments<T>>::get(product_id);
+        Self::current_document(&documents, doc_type)
+            .map_or(false, |index| documents[index].hash == T::Hashing::hash(content))
+    }
+
+    pub fn validate_product_components(
+        product_id: &[u8],
+        components: &[ProductComponent],
+    ) -> Result<(), Error<T>> {
+        let mut component_ids = BTreeSet::new();
+        for component in components {
+            ensure!(
+                component_ids.insert(&component.product_id),
+                Error::<T>::ProductComponentDuplicate
+            );
+            ensure!(component.quantity > 0, Error::<T>::ProductComponentInvalidQuantity);
+            ensure!(
+                <Products<T>>::contains_key(&component.product_id),
+                Error::<T>::ProductComponentUnknown
+            );
+        }
+        // A product may not (even indirectly) be made of itself
+        ensure!(
+            !Self::is_made_of(component_ids, product_id)?,
+            Error::<T>::ProductComponentCycle
+        );
+        Ok(())
+    }
+
+    // Returns true if one of the products is the given component, or is (even indirectly)
+    // made of it. Fails past ProductMaxComponentNodes distinct products visited.
+    fn is_made_of(product_ids: BTreeSet<&ProductId>, component_id: &[u8]) -> Result<bool, Error<T>> {
+        let max_nodes = T::ProductMaxComponentNodes::get() as usize;
+        let mut visited = BTreeSet::new();
+        let mut pending: Vec<ProductId> = product_ids.into_iter().cloned().collect();
+        while let Some(id) = pending.pop() {
+            if id == component_id {
+                return Ok(true);
+            }
+            if visited.insert(id.clone()) {
+                ensure!(visited.len() <= max_nodes, Error::<T>::ProductComponentTreeTooLarge);
+                pending.extend(
+                    <ProductComponents>::get(&id)
+                        .into_iter()
+                        .map(|component| component.product_id),
+                );
+            }
+        }
+        Ok(false)
+    }
+
+    // Returns the upstream supply tree of the product, i.e. its components
+    // along with their suppliers, recursively. Fails past ProductMaxComponentNodes nodes.
+    pub fn provenance(product_id: &[u8]) -> Result<Option<ProvenanceNode<T::AccountId>>, Error<T>> {
+        let mut remaining_nodes = T::ProductMaxComponentNodes::get();
+        Self::provenance_node(product_id, 1, &mut remaining_nodes)
+    }
+
+    fn provenance_node(
+        product_id: &[u8],
+        quantity: u32,
+        remaining_nodes: &mut u32,
+    ) -> Result<Option<ProvenanceNode<T::AccountId>>, Error<T>> {
+        *remaining_nodes = remaining_nodes
+            .checked_sub(1)
+            .ok_or(Error::<T>::ProductComponentTreeTooLarge)?;
+        let supplier = match <Products<T>>::get(product_id) {
+            Some(product) => product.owner,
+            None => return Ok(None),
+        };
+        let mut components = Vec::new();
+        for component in <ProductComponents>::get(product_id) {
+            if let Some(node) =
+                Self::provenance_node(&component.product_id, component.quantity, remaining_nodes)?
+            {
+                components.push(node);
+            }
+        }
+        Ok(Some(ProvenanceNode {
+            product_id: product_id.to_vec(),
+            supplier,
+            quantity,
+            components,
+        }))
+    }
+
+    pub fn validate_lot_number(lot_number: &[u8]) -> Result<(), Error<T>> {
+        // Basic lot number validation
+        ensure!(!lot_number.is_empty(), Error::<T>::ProductLotNumberMissing);
+        ensure!(
+            lot_number.len() <= T::LotNumberMaxLength::get() as usize,
+            Error::<T>::ProductLotNumberTooLong
+        );
         Ok(())
     }
 
@@ -176,25 +1255,64 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
-    pub fn validate_product_props(props: &Option<Vec<ProductProperty>>) -> Result<(), Error<T>> {
+    pub fn validate_product_owner(
+        product: &Product<T::AccountId, T::Moment>,
+        who: &T::AccountId,
+    ) -> Result<(), Error<T>> {
+        // Only the owning organization (or its members) may alter a product
+        ensure!(
+            T::Organizations::is_member(&product.owner, who),
+            Error::<T>::NotProductOwner
+        );
+        Ok(())
+    }
+
+    pub fn validate_product_props(props: &Option<Vec<ProductProperty<T::Moment>>>) -> Result<(), Error<T>> {
         if let Some(props) = props {
             ensure!(
-                props.len() <= PRODUCT_MAX_PROPS,
+                props.len() <= T::ProductMaxProps::get() as usize,
                 Error::<T>::ProductTooManyProps,
             );
             for prop in props {
                 ensure!(
-                    prop.name().len() <= PRODUCT_PROP_NAME_MAX_LENGTH,
+                    prop.name().len() <= T::ProductPropNameMaxLength::get() as usize,
                     Error::<T>::ProductInvalidPropName
                 );
-                ensure!(
-                    prop.value().len() <= PRODUCT_PROP_VALUE_MAX_LENGTH,
-                    Error::<T>::ProductInvalidPropValue
-                );
+                Self::validate_product_prop_value(prop.value())?;
             }
         }
         Ok(())
     }
+
+    pub fn validate_product_prop_value(value: &PropValue<T::Moment>) -> Result<(), Error<T>> {
+        let valid = match value {
+            // Text must be UTF-8, of limited length
+            PropValue::Text(text) => {
+                text.len() <= T::ProductPropValueMaxLength::get() as usize
+                    && core::str::from_utf8(text).is_ok()
+            }
+            // Dates must be set
+            PropValue::Date(date) => !date.is_zero(),
+            PropValue::Integer(_) | PropValue::Decimal(_) | PropValue::Boolean(_) => true,
+        };
+        ensure!(valid, Error::<T>::ProductInvalidPropValue);
+        Ok(())
+    }
+}
+
+impl<T: Trait> ProductInfo for Module<T> {
+    fn status_of(id: &[u8]) -> Option<ProductStatus> {
+        Self::product_by_id(id).map(|product| product.status)
+    }
+
+    fn lot_exists(id: &[u8], lot_number: &[u8]) -> bool {
+        <ProductLots<T>>::contains_key(id, lot_number)
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn add_product(id: &[u8]) {
+        Self::insert_product(id.to_vec(), Default::default(), None);
+    }
 }
 
 #[derive(Default)]
@@ -205,7 +1323,7 @@ where
 {
     id: ProductId,
     owner: AccountId,
-    props: Option<Vec<ProductProperty>>,
+    props: Option<Vec<ProductProperty<Moment>>>,
     registered: Moment,
 }
 
@@ -224,7 +1342,7 @@ where
         self
     }
 
-    pub fn with_props(mut self, props: Option<Vec<ProductProperty>>) -> Self {
+    pub fn with_props(mut self, props: Option<Vec<ProductProperty<Moment>>>) -> Self {
         self.props = props;
         self
     }
@@ -240,6 +1358,7 @@ where
             owner: self.owner,
             props: self.props,
             registered: self.registered,
+            status: ProductStatus::Active,
         }
     }
 }
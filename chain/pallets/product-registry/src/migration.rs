@@ -0,0 +1,153 @@
+// Storage migrations of the product registry, run on runtime upgrades.
+//
+// Each migration upgrades the storage from one version (see Releases) to the
+// next one, so that chains can skip runtime versions: `migrate` runs all the
+// migrations needed, in order, from the version currently in storage.
+
+use super::*;
+use codec::{Error as CodecError, Input};
+use core::cell::Cell;
+use frame_support::storage::{IterableStorageMap, StorageValue};
+
+// Product property holding a raw byte value (storage V1)
+#[derive(Decode)]
+struct ProductPropertyV1 {
+    name: PropName,
+    value: Vec<u8>,
+}
+
+// Product as stored before typed property values (storage V1)
+struct ProductV1<AccountId, Moment> {
+    id: ProductId,
+    owner: AccountId,
+    props: Option<Vec<ProductPropertyV1>>,
+    registered: Moment,
+    status: ProductStatus,
+}
+
+// Products registered before lifecycle statuses were introduced are stored
+// without one, and are decoded as active ones.
+impl<AccountId: Decode, Moment: Decode> Decode for ProductV1<AccountId, Moment> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        let id = Decode::decode(input)?;
+        let owner = Decode::decode(input)?;
+        let props = Decode::decode(input)?;
+        let registered = Decode::decode(input)?;
+        let status = match input.remaining_len()? {
+            Some(0) => ProductStatus::Active,
+            _ => Decode::decode(input)?,
+        };
+        Ok(ProductV1 {
+            id,
+            owner,
+            props,
+            registered,
+            status,
+        })
+    }
+}
+
+// Product revision as stored before typed property values (storage V1)
+#[derive(Decode)]
+struct ProductRevisionV1<AccountId, BlockNumber> {
+    block_number: BlockNumber,
+    author: AccountId,
+    name: PropName,
+    old_value: Option<Vec<u8>>,
+    new_value: Option<Vec<u8>>,
+}
+
+impl<Moment> From<ProductPropertyV1> for ProductProperty<Moment> {
+    fn from(prop: ProductPropertyV1) -> Self {
+        ProductProperty {
+            name: prop.name,
+            value: PropValue::Text(prop.value),
+        }
+    }
+}
+
+impl<AccountId, Moment> From<ProductV1<AccountId, Moment>> for Product<AccountId, Moment> {
+    fn from(product: ProductV1<AccountId, Moment>) -> Self {
+        Product {
+            id: product.id,
+            owner: product.owner,
+            props: product
+                .props
+                .map(|props| props.into_iter().map(Into::into).collect()),
+            registered: product.registered,
+            status: product.status,
+        }
+    }
+}
+
+impl<AccountId, BlockNumber, Moment> From<ProductRevisionV1<AccountId, BlockNumber>>
+    for ProductRevision<AccountId, BlockNumber, Moment>
+{
+    fn from(revision: ProductRevisionV1<AccountId, BlockNumber>) -> Self {
+        ProductRevision {
+            block_number: revision.block_number,
+            author: revision.author,
+            name: revision.name,
+            old_value: revision.old_value.map(PropValue::Text),
+            new_value: revision.new_value.map(PropValue::Text),
+        }
+    }
+}
+
+pub fn migrate<T: Trait>() -> Weight {
+    // Storage version (1 DB read)
+    let mut weight = T::DbWeight::get().reads(1);
+
+    if StorageVersion::get() == Releases::V1 {
+        weight = weight.saturating_add(migrate_to_v2::<T>());
+    }
+    if StorageVersion::get() == Releases::V2 {
+        weight = weight.saturating_add(migrate_to_v3::<T>());
+    }
+
+    weight
+}
+
+// Raw byte values of existing product properties become text values
+fn migrate_to_v2<T: Trait>() -> Weight {
+    let count = Cell::new(0 as Weight);
+    <Products<T>>::translate::<ProductV1<T::AccountId, T::Moment>, _>(|_, product| {
+        count.set(count.get() + 1);
+        Some(product.into())
+    });
+    <ProductRevisions<T>>::translate::<Vec<ProductRevisionV1<T::AccountId, T::BlockNumber>>, _>(
+        |_, revisions| {
+            count.set(count.get() + 1);
+            Some(revisions.into_iter().map(Into::into).collect())
+        },
+    );
+
+    StorageVersion::put(Releases::V2);
+
+    // Each migrated entry is read & written, as well as the storage version
+    let count = count.get();
+    T::DbWeight::get().reads_writes(count, count.saturating_add(1))
+}
+
+// Existing products are added to the props index, which only tracked
+// the products registered or updated since it was introduced
+fn migrate_to_v3<T: Trait>() -> Weight {
+    let mut products = 0 as Weight;
+    let mut entries = 0 as Weight;
+    for (id, product) in <Products<T>>::iter() {
+        products += 1;
+        for key in Module::<T>::index_keys(&product) {
+            entries += 1;
+            <ProductsByProp<T>>::mutate(key, |ids| {
+                if !ids.contains(&id) {
+                    ids.push(id.clone());
+                }
+            });
+        }
+    }
+
+    StorageVersion::put(Releases::V3);
+
+    // Each product is read, and each index entry read & written, as well as the storage version
+    T::DbWeight::get().reads_writes(products.saturating_add(entries), entries.saturating_add(1))
+}
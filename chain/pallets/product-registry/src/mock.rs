@@ -1,7 +1,8 @@
 // Creating mock runtime here
 
-use crate::{Module, Trait};
+use crate::{Certifiers, Module, OnProductRecalled, Organizations, ProductId, PropName, Trait};
 use core::marker::PhantomData;
+use std::cell::RefCell;
 use frame_support::{
     impl_outer_event, impl_outer_origin, parameter_types, traits::EnsureOrigin, weights::Weight,
 };
@@ -74,9 +75,46 @@ impl timestamp::Trait for Test {
     type WeightInfo = ();
 }
 
+parameter_types! {
+    pub const ProductIdMaxLength: u32 = 36;
+    pub const ProductPropNameMaxLength: u32 = 10;
+    pub const ProductPropValueMaxLength: u32 = 20;
+    pub const ProductMaxProps: u32 = 3;
+    pub const RecallReasonMaxLength: u32 = 64;
+    pub const ProductBatchMaxSize: u32 = 3;
+    pub const LotNumberMaxLength: u32 = 20;
+    pub const CertificationMaxLength: u32 = 20;
+    pub const ProductMaxCertifications: u32 = 2;
+    pub const ProductMaxComponents: u32 = 3;
+    pub const ProductMaxComponentNodes: u32 = 5;
+    pub const DocumentTypeMaxLength: u32 = 10;
+    pub const DocumentUriMaxLength: u32 = 64;
+    pub const ProductMaxDocuments: u32 = 3;
+    pub IndexedPropNames: Vec<PropName> = vec![b"category".to_vec()];
+}
+
 impl Trait for Test {
     type Event = TestEvent;
     type CreateRoleOrigin = MockOrigin<Test>;
+    type Organizations = MockOrganizations;
+    type OnProductRecalled = MockRecallHandler;
+    type Certifiers = MockCertifiers;
+    type ProductIdMaxLength = ProductIdMaxLength;
+    type ProductPropNameMaxLength = ProductPropNameMaxLength;
+    type ProductPropValueMaxLength = ProductPropValueMaxLength;
+    type ProductMaxProps = ProductMaxProps;
+    type RecallReasonMaxLength = RecallReasonMaxLength;
+    type ProductBatchMaxSize = ProductBatchMaxSize;
+    type LotNumberMaxLength = LotNumberMaxLength;
+    type CertificationMaxLength = CertificationMaxLength;
+    type ProductMaxCertifications = ProductMaxCertifications;
+    type ProductMaxComponents = ProductMaxComponents;
+    type ProductMaxComponentNodes = ProductMaxComponentNodes;
+    type DocumentTypeMaxLength = DocumentTypeMaxLength;
+    type DocumentUriMaxLength = DocumentUriMaxLength;
+    type ProductMaxDocuments = ProductMaxDocuments;
+    type IndexedPropNames = IndexedPropNames;
+    type WeightInfo = ();
 }
 
 pub type ProductRegistry = Module<Test>;
@@ -93,6 +131,102 @@ impl<T: Trait> EnsureOrigin<T::Origin> for MockOrigin<T> {
             r => Err(T::Origin::from(r)),
         })
     }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn successful_origin() -> T::Origin {
+        T::Origin::from(RawOrigin::Signed(Default::default()))
+    }
+}
+
+thread_local! {
+    static ORGANIZATIONS: RefCell<Vec<sr25519::Public>> = RefCell::new(vec![]);
+    static ORG_MEMBERS: RefCell<Vec<(sr25519::Public, sr25519::Public)>> = RefCell::new(vec![]);
+    static ORG_PREFIXES: RefCell<Vec<(sr25519::Public, Vec<u8>)>> = RefCell::new(vec![]);
+    static CERTIFIERS: RefCell<Vec<sr25519::Public>> = RefCell::new(vec![]);
+    static RECALL_AFFECTED: RefCell<Vec<ProductId>> = RefCell::new(vec![]);
+}
+
+pub struct MockOrganizations;
+
+impl Organizations<sr25519::Public> for MockOrganizations {
+    fn is_member(org: &sr25519::Public, account: &sr25519::Public) -> bool {
+        org == account || ORG_MEMBERS.with(|m| m.borrow().contains(&(*org, *account)))
+    }
+
+    fn is_organization(account: &sr25519::Public) -> bool {
+        ORGANIZATIONS.with(|o| o.borrow().contains(account))
+    }
+
+    fn company_prefix(org: &sr25519::Public) -> Option<Vec<u8>> {
+        ORG_PREFIXES.with(|p| {
+            p.borrow()
+                .iter()
+                .find(|(holder, _)| holder == org)
+                .map(|(_, prefix)| prefix.clone())
+        })
+    }
+
+    fn company_prefix_holder(prefix: &[u8]) -> Option<sr25519::Public> {
+        ORG_PREFIXES.with(|p| {
+            p.borrow()
+                .iter()
+                .find(|(_, held)| held.as_slice() == prefix)
+                .map(|(holder, _)| *holder)
+        })
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn add_organization(account: &sr25519::Public) {
+        add_organization(*account);
+    }
+}
+
+pub fn add_organization(org: sr25519::Public) {
+    ORGANIZATIONS.with(|o| o.borrow_mut().push(org));
+}
+
+pub fn add_org_member(org: sr25519::Public, account: sr25519::Public) {
+    ORG_MEMBERS.with(|m| m.borrow_mut().push((org, account)));
+}
+
+pub fn set_company_prefix(org: sr25519::Public, prefix: &[u8]) {
+    ORG_PREFIXES.with(|p| p.borrow_mut().push((org, prefix.to_vec())));
+}
+
+pub struct MockCertifiers;
+
+impl Certifiers<sr25519::Public> for MockCertifiers {
+    fn is_certifier(account: &sr25519::Public) -> bool {
+        CERTIFIERS.with(|c| c.borrow().contains(account))
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn add_certifier(account: &sr25519::Public) {
+        add_certifier(*account);
+    }
+}
+
+pub fn add_certifier(account: sr25519::Public) {
+    CERTIFIERS.with(|c| c.borrow_mut().push(account));
+}
+
+pub struct MockRecallHandler;
+
+impl OnProductRecalled for MockRecallHandler {
+    fn affected_count(id: &ProductId) -> u32 {
+        RECALL_AFFECTED.with(|a| a.borrow().iter().filter(|p| *p == id).count() as u32)
+    }
+
+    fn on_product_recalled(_id: &ProductId) {}
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn add_affected(id: &ProductId, count: u32) {
+        add_recall_affected(id, count);
+    }
+}
+
+pub fn add_recall_affected(id: &ProductId, count: u32) {
+    RECALL_AFFECTED.with(|a| a.borrow_mut().extend((0..count).map(|_| id.clone())));
 }
 
 // This function basically just builds a genesis storage key/value store according to
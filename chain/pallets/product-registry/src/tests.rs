@@ -2,7 +2,7 @@
 
 use super::*;
 use crate::{mock::*, Error};
-use frame_support::{assert_noop, assert_ok, dispatch};
+use frame_support::{assert_noop, assert_ok, dispatch, storage::unhashed, traits::OnRuntimeUpgrade};
 
 pub fn store_test_product<T: Trait>(id: ProductId, owner: T::AccountId, registered: T::Moment) {
     Products::<T>::insert(
@@ -12,13 +12,20 @@ pub fn store_test_product<T: Trait>(id: ProductId, owner: T::AccountId, register
             owner,
             registered,
             props: None,
+            status: ProductStatus::Active,
         },
     );
 }
 
+pub fn text_prop(name: &[u8], value: &[u8]) -> ProductProperty<u64> {
+    ProductProperty::new(name, PropValue::Text(value.to_vec()))
+}
+
 const TEST_PRODUCT_ID: &str = "00012345600012";
 const TEST_ORGANIZATION: &str = "Northwind";
+const TEST_OTHER_ORGANIZATION: &str = "Contoso";
 const TEST_SENDER: &str = "Alice";
+const TEST_CERTIFIER: &str = "Bureau";
 const LONG_VALUE : &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. Donec aliquam ut tortor nec congue. Pellente";
 
 #[test]
@@ -45,7 +52,8 @@ fn create_product_without_props() {
                 id: id.clone(),
                 owner: owner,
                 registered: now,
-                props: None
+                props: None,
+                status: ProductStatus::Active
             })
         );
 
@@ -77,9 +85,9 @@ fn create_product_with_valid_props() {
             id.clone(),
             owner.clone(),
             Some(vec![
-                ProductProperty::new(b"prop1", b"val1"),
-                ProductProperty::new(b"prop2", b"val2"),
-                ProductProperty::new(b"prop3", b"val3"),
+                text_prop(b"prop1", b"val1"),
+                text_prop(b"prop2", b"val2"),
+                text_prop(b"prop3", b"val3"),
             ]),
         );
 
@@ -92,10 +100,11 @@ fn create_product_with_valid_props() {
                 owner: owner,
                 registered: now,
                 props: Some(vec![
-                    ProductProperty::new(b"prop1", b"val1"),
-                    ProductProperty::new(b"prop2", b"val2"),
-                    ProductProperty::new(b"prop3", b"val3"),
+                    text_prop(b"prop1", b"val1"),
+                    text_prop(b"prop2", b"val2"),
+                    text_prop(b"prop3", b"val3"),
                 ]),
+                status: ProductStatus::Active,
             })
         );
 
@@ -191,10 +200,10 @@ fn create_product_with_too_many_props() {
                 TEST_PRODUCT_ID.as_bytes().to_owned(),
                 account_key(TEST_ORGANIZATION),
                 Some(vec![
-                    ProductProperty::new(b"prop1", b"val1"),
-                    ProductProperty::new(b"prop2", b"val2"),
-                    ProductProperty::new(b"prop3", b"val3"),
-                    ProductProperty::new(b"prop4", b"val4")
+                    text_prop(b"prop1", b"val1"),
+                    text_prop(b"prop2", b"val2"),
+                    text_prop(b"prop3", b"val3"),
+                    text_prop(b"prop4", b"val4")
                 ])
             ),
             Error::<Test>::ProductTooManyProps
@@ -211,9 +220,9 @@ fn create_product_with_invalid_prop_name() {
                 TEST_PRODUCT_ID.as_bytes().to_owned(),
                 account_key(TEST_ORGANIZATION),
                 Some(vec![
-                    ProductProperty::new(b"prop1", b"val1"),
-                    ProductProperty::new(b"prop2", b"val2"),
-                    ProductProperty::new(&LONG_VALUE.as_bytes().to_owned(), b"val3"),
+                    text_prop(b"prop1", b"val1"),
+                    text_prop(b"prop2", b"val2"),
+                    text_prop(&LONG_VALUE.as_bytes().to_owned(), b"val3"),
                 ])
             ),
             Error::<Test>::ProductInvalidPropName
@@ -230,12 +239,1883 @@ fn create_product_with_invalid_prop_value() {
                 TEST_PRODUCT_ID.as_bytes().to_owned(),
                 account_key(TEST_ORGANIZATION),
                 Some(vec![
-                    ProductProperty::new(b"prop1", b"val1"),
-                    ProductProperty::new(b"prop2", b"val2"),
-                    ProductProperty::new(b"prop3", &LONG_VALUE.as_bytes().to_owned()),
+                    text_prop(b"prop1", b"val1"),
+                    text_prop(b"prop2", b"val2"),
+                    text_prop(b"prop3", &LONG_VALUE.as_bytes().to_owned()),
                 ])
             ),
             Error::<Test>::ProductInvalidPropValue
         );
     })
 }
+
+#[test]
+fn create_product_with_typed_props() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let props = vec![
+            ProductProperty::new(b"weight", PropValue::Decimal(Decimal::from_num(0.75))),
+            ProductProperty::new(b"expiry", PropValue::Date(1_640_995_200_000)),
+            ProductProperty::new(b"organic", PropValue::Boolean(true)),
+        ];
+
+        assert_ok!(ProductRegistry::register_product(
+            Origin::signed(account_key(TEST_SENDER)),
+            id.clone(),
+            account_key(TEST_ORGANIZATION),
+            Some(props.clone()),
+        ));
+
+        assert_eq!(
+            ProductRegistry::product_by_id(&id).and_then(|p| p.props),
+            Some(props)
+        );
+    })
+}
+
+#[test]
+fn create_product_with_invalid_text_prop() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            ProductRegistry::register_product(
+                Origin::signed(account_key(TEST_SENDER)),
+                TEST_PRODUCT_ID.as_bytes().to_owned(),
+                account_key(TEST_ORGANIZATION),
+                Some(vec![text_prop(b"prop1", &[0xf0, 0x28, 0x8c, 0x28])])
+            ),
+            Error::<Test>::ProductInvalidPropValue
+        );
+    })
+}
+
+#[test]
+fn create_product_with_unset_date_prop() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            ProductRegistry::register_product(
+                Origin::signed(account_key(TEST_SENDER)),
+                TEST_PRODUCT_ID.as_bytes().to_owned(),
+                account_key(TEST_ORGANIZATION),
+                Some(vec![ProductProperty::new(b"expiry", PropValue::Date(0))])
+            ),
+            Error::<Test>::ProductInvalidPropValue
+        );
+    })
+}
+
+#[test]
+fn transfer_product_to_other_organization() {
+    new_test_ext().execute_with(|| {
+        let sender = account_key(TEST_SENDER);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+        let new_owner = account_key(TEST_OTHER_ORGANIZATION);
+        add_org_member(owner, sender);
+        add_organization(new_owner);
+        let now = 42;
+        Timestamp::set_timestamp(now);
+
+        assert_ok!(ProductRegistry::register_product(
+            Origin::signed(sender),
+            id.clone(),
+            owner.clone(),
+            None,
+        ));
+
+        let result = ProductRegistry::transfer_product(
+            Origin::signed(sender),
+            id.clone(),
+            new_owner.clone(),
+        );
+
+        assert_ok!(result);
+
+        assert_eq!(
+            ProductRegistry::product_by_id(&id),
+            Some(Product {
+                id: id.clone(),
+                owner: new_owner,
+                registered: now,
+                props: None,
+                status: ProductStatus::Active
+            })
+        );
+
+        assert_eq!(<ProductsOfOrganization<Test>>::get(owner), Vec::<ProductId>::new());
+        assert_eq!(<ProductsOfOrganization<Test>>::get(new_owner), vec![id.clone()]);
+
+        assert_eq!(ProductRegistry::owner_of(&id), Some(new_owner));
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_registry(RawEvent::ProductOwnershipTransferred(
+                sender,
+                id.clone(),
+                owner,
+                new_owner
+            ))));
+    });
+}
+
+#[test]
+fn transfer_product_with_invalid_sender() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            ProductRegistry::transfer_product(
+                Origin::none(),
+                TEST_PRODUCT_ID.as_bytes().to_owned(),
+                account_key(TEST_OTHER_ORGANIZATION)
+            ),
+            dispatch::DispatchError::BadOrigin
+        );
+    });
+}
+
+#[test]
+fn transfer_product_with_unknown_id() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            ProductRegistry::transfer_product(
+                Origin::signed(account_key(TEST_SENDER)),
+                TEST_PRODUCT_ID.as_bytes().to_owned(),
+                account_key(TEST_OTHER_ORGANIZATION)
+            ),
+            Error::<Test>::ProductIdUnknown
+        );
+    });
+}
+
+#[test]
+fn transfer_product_by_non_member() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+        store_test_product::<Test>(id.clone(), owner, 42);
+
+        // Sender doesn't belong to the owning organization
+        assert_noop!(
+            ProductRegistry::transfer_product(
+                Origin::signed(account_key(TEST_SENDER)),
+                id.clone(),
+                account_key(TEST_OTHER_ORGANIZATION)
+            ),
+            Error::<Test>::NotProductOwner
+        );
+
+        assert_eq!(ProductRegistry::product_by_id(&id).map(|p| p.owner), Some(owner));
+    });
+}
+
+#[test]
+fn transfer_product_to_same_owner() {
+    new_test_ext().execute_with(|| {
+        let existing_product = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let now = 42;
+        add_org_member(account_key(TEST_ORGANIZATION), account_key(TEST_SENDER));
+
+        store_test_product::<Test>(
+            existing_product.clone(),
+            account_key(TEST_ORGANIZATION),
+            now,
+        );
+
+        assert_noop!(
+            ProductRegistry::transfer_product(
+                Origin::signed(account_key(TEST_SENDER)),
+                existing_product,
+                account_key(TEST_ORGANIZATION)
+            ),
+            Error::<Test>::ProductOwnerUnchanged
+        );
+    });
+}
+
+#[test]
+fn transfer_product_to_non_organization() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+        add_org_member(owner, account_key(TEST_SENDER));
+        store_test_product::<Test>(id.clone(), owner, 42);
+
+        // New owner isn't a registered organization
+        assert_noop!(
+            ProductRegistry::transfer_product(
+                Origin::signed(account_key(TEST_SENDER)),
+                id.clone(),
+                account_key(TEST_OTHER_ORGANIZATION)
+            ),
+            Error::<Test>::ProductOwnerNotOrganization
+        );
+
+        assert_eq!(ProductRegistry::product_by_id(&id).map(|p| p.owner), Some(owner));
+        assert_eq!(ProductRegistry::owner_of(&id), Some(owner));
+    });
+}
+
+#[test]
+fn update_product_props() {
+    new_test_ext().execute_with(|| {
+        let sender = account_key(TEST_SENDER);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+        add_org_member(owner, sender);
+
+        assert_ok!(ProductRegistry::register_product(
+            Origin::signed(sender),
+            id.clone(),
+            owner.clone(),
+            Some(vec![text_prop(b"prop1", b"val1")]),
+        ));
+
+        System::set_block_number(2);
+
+        let result = ProductRegistry::set_product_props(
+            Origin::signed(sender),
+            id.clone(),
+            vec![
+                text_prop(b"prop1", b"val1b"),
+                text_prop(b"prop2", b"val2"),
+            ],
+        );
+
+        assert_ok!(result);
+
+        assert_eq!(
+            ProductRegistry::product_by_id(&id).and_then(|p| p.props),
+            Some(vec![
+                text_prop(b"prop1", b"val1b"),
+                text_prop(b"prop2", b"val2"),
+            ])
+        );
+
+        assert_eq!(
+            ProductRegistry::revisions_of(&id),
+            vec![
+                ProductRevision {
+                    block_number: 2,
+                    author: sender,
+                    name: b"prop1".to_vec(),
+                    old_value: Some(PropValue::Text(b"val1".to_vec())),
+                    new_value: Some(PropValue::Text(b"val1b".to_vec())),
+                },
+                ProductRevision {
+                    block_number: 2,
+                    author: sender,
+                    name: b"prop2".to_vec(),
+                    old_value: None,
+                    new_value: Some(PropValue::Text(b"val2".to_vec())),
+                },
+            ]
+        );
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_registry(RawEvent::ProductPropsUpdated(sender, id.clone()))));
+    });
+}
+
+#[test]
+fn update_product_props_by_non_member() {
+    new_test_ext().execute_with(|| {
+        let existing_product = TEST_PRODUCT_ID.as_bytes().to_owned();
+
+        store_test_product::<Test>(existing_product.clone(), account_key(TEST_ORGANIZATION), 42);
+
+        assert_noop!(
+            ProductRegistry::set_product_props(
+                Origin::signed(account_key(TEST_SENDER)),
+                existing_product,
+                vec![text_prop(b"prop1", b"val1")]
+            ),
+            Error::<Test>::NotProductOwner
+        );
+    });
+}
+
+#[test]
+fn update_product_props_with_too_many_props() {
+    new_test_ext().execute_with(|| {
+        let sender = account_key(TEST_SENDER);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+        add_org_member(owner, sender);
+
+        assert_ok!(ProductRegistry::register_product(
+            Origin::signed(sender),
+            id.clone(),
+            owner,
+            Some(vec![
+                text_prop(b"prop1", b"val1"),
+                text_prop(b"prop2", b"val2"),
+            ]),
+        ));
+
+        assert_noop!(
+            ProductRegistry::set_product_props(
+                Origin::signed(sender),
+                id,
+                vec![
+                    text_prop(b"prop3", b"val3"),
+                    text_prop(b"prop4", b"val4"),
+                ]
+            ),
+            Error::<Test>::ProductTooManyProps
+        );
+    });
+}
+
+#[test]
+fn remove_product_prop() {
+    new_test_ext().execute_with(|| {
+        let sender = account_key(TEST_SENDER);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+        add_org_member(owner, sender);
+
+        assert_ok!(ProductRegistry::register_product(
+            Origin::signed(sender),
+            id.clone(),
+            owner,
+            Some(vec![
+                text_prop(b"prop1", b"val1"),
+                text_prop(b"prop2", b"val2"),
+            ]),
+        ));
+
+        assert_ok!(ProductRegistry::remove_product_prop(
+            Origin::signed(sender),
+            id.clone(),
+            b"prop1".to_vec(),
+        ));
+
+        assert_eq!(
+            ProductRegistry::product_by_id(&id).and_then(|p| p.props),
+            Some(vec![text_prop(b"prop2", b"val2")])
+        );
+
+        assert_eq!(
+            ProductRegistry::revisions_of(&id),
+            vec![ProductRevision {
+                block_number: 1,
+                author: sender,
+                name: b"prop1".to_vec(),
+                old_value: Some(PropValue::Text(b"val1".to_vec())),
+                new_value: None,
+            }]
+        );
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_registry(RawEvent::ProductPropRemoved(
+                sender,
+                id.clone(),
+                b"prop1".to_vec()
+            ))));
+    });
+}
+
+#[test]
+fn remove_unknown_product_prop() {
+    new_test_ext().execute_with(|| {
+        let existing_product = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+
+        store_test_product::<Test>(existing_product.clone(), owner, 42);
+
+        assert_noop!(
+            ProductRegistry::remove_product_prop(
+                Origin::signed(owner),
+                existing_product,
+                b"prop1".to_vec()
+            ),
+            Error::<Test>::ProductPropUnknown
+        );
+    });
+}
+
+#[test]
+fn create_product_with_invalid_gtin() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            ProductRegistry::register_product(
+                Origin::signed(account_key(TEST_SENDER)),
+                b"00012345600013".to_vec(),
+                account_key(TEST_ORGANIZATION),
+                None
+            ),
+            Error::<Test>::ProductIdInvalidGtin
+        );
+    })
+}
+
+#[test]
+fn create_product_with_matching_company_prefix() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        set_company_prefix(owner, b"0012345");
+
+        // GTIN-14 & equivalent GTIN-13 w/ same company prefix
+        assert_ok!(ProductRegistry::register_product(
+            Origin::signed(account_key(TEST_SENDER)),
+            TEST_PRODUCT_ID.as_bytes().to_owned(),
+            owner,
+            None
+        ));
+        assert_ok!(ProductRegistry::register_product(
+            Origin::signed(account_key(TEST_SENDER)),
+            b"0012345600029".to_vec(),
+            owner,
+            None
+        ));
+    })
+}
+
+#[test]
+fn create_product_with_mismatched_company_prefix() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        set_company_prefix(owner, b"0054321");
+
+        assert_noop!(
+            ProductRegistry::register_product(
+                Origin::signed(account_key(TEST_SENDER)),
+                TEST_PRODUCT_ID.as_bytes().to_owned(),
+                owner,
+                None
+            ),
+            Error::<Test>::ProductIdPrefixMismatch
+        );
+    })
+}
+
+#[test]
+fn create_product_under_other_organization_company_prefix() {
+    new_test_ext().execute_with(|| {
+        set_company_prefix(account_key(TEST_ORGANIZATION), b"0012345");
+
+        // Owner holds no prefix, but the GTIN falls under another organization's
+        assert_noop!(
+            ProductRegistry::register_product(
+                Origin::signed(account_key(TEST_OTHER_ORGANIZATION)),
+                TEST_PRODUCT_ID.as_bytes().to_owned(),
+                account_key(TEST_OTHER_ORGANIZATION),
+                None
+            ),
+            Error::<Test>::ProductIdPrefixMismatch
+        );
+    })
+}
+
+#[test]
+fn create_product_with_non_gtin_id_for_prefixed_organization() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        set_company_prefix(owner, b"0012345");
+
+        assert_noop!(
+            ProductRegistry::register_product(
+                Origin::signed(account_key(TEST_SENDER)),
+                b"SKU-1234".to_vec(),
+                owner,
+                None
+            ),
+            Error::<Test>::ProductIdPrefixMismatch
+        );
+    })
+}
+
+#[test]
+fn update_product_status() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+
+        store_test_product::<Test>(id.clone(), owner, 42);
+
+        assert_ok!(ProductRegistry::set_product_status(
+            Origin::signed(owner),
+            id.clone(),
+            ProductStatus::Discontinued,
+        ));
+
+        assert_eq!(
+            <ProductRegistry as ProductInfo>::status_of(&id),
+            Some(ProductStatus::Discontinued)
+        );
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_registry(RawEvent::ProductStatusUpdated(
+                owner,
+                id.clone(),
+                ProductStatus::Discontinued
+            ))));
+    });
+}
+
+#[test]
+fn update_product_status_by_non_member() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+
+        store_test_product::<Test>(id.clone(), account_key(TEST_ORGANIZATION), 42);
+
+        assert_noop!(
+            ProductRegistry::set_product_status(
+                Origin::signed(account_key(TEST_SENDER)),
+                id,
+                ProductStatus::Discontinued
+            ),
+            Error::<Test>::NotProductOwner
+        );
+    });
+}
+
+#[test]
+fn update_product_status_unchanged() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+
+        store_test_product::<Test>(id.clone(), owner, 42);
+
+        assert_noop!(
+            ProductRegistry::set_product_status(Origin::signed(owner), id, ProductStatus::Active),
+            Error::<Test>::ProductStatusUnchanged
+        );
+    });
+}
+
+#[test]
+fn recall_product() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+        let now = 42;
+        Timestamp::set_timestamp(now);
+
+        store_test_product::<Test>(id.clone(), owner, now);
+
+        assert_ok!(ProductRegistry::recall_product(
+            Origin::signed(owner),
+            id.clone(),
+            b"contamination".to_vec(),
+            0
+        ));
+
+        assert_eq!(
+            <ProductRegistry as ProductInfo>::status_of(&id),
+            Some(ProductStatus::Recalled)
+        );
+
+        assert_eq!(
+            ProductRegistry::recall_of(&id),
+            Some(RecallNotice {
+                reason: b"contamination".to_vec(),
+                issuer: owner,
+                timestamp: now,
+            })
+        );
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_registry(RawEvent::ProductRecalled(owner, id.clone()))));
+    });
+}
+
+#[test]
+fn recall_product_twice() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+
+        store_test_product::<Test>(id.clone(), owner, 42);
+
+        assert_ok!(ProductRegistry::recall_product(
+            Origin::signed(owner),
+            id.clone(),
+            b"contamination".to_vec(),
+            0
+        ));
+
+        assert_noop!(
+            ProductRegistry::recall_product(Origin::signed(owner), id, b"mislabeled".to_vec(), 0),
+            Error::<Test>::ProductAlreadyRecalled
+        );
+    });
+}
+
+#[test]
+fn recall_product_with_long_reason() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+
+        store_test_product::<Test>(id.clone(), owner, 42);
+
+        assert_noop!(
+            ProductRegistry::recall_product(
+                Origin::signed(owner),
+                id,
+                LONG_VALUE.as_bytes().to_owned(),
+                0
+            ),
+            Error::<Test>::ProductRecallReasonTooLong
+        );
+    });
+}
+
+#[test]
+fn update_product_status_to_recalled() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+
+        store_test_product::<Test>(id.clone(), owner, 42);
+
+        assert_noop!(
+            ProductRegistry::set_product_status(Origin::signed(owner), id, ProductStatus::Recalled),
+            Error::<Test>::ProductRecallRequiresNotice
+        );
+    });
+}
+
+#[test]
+fn update_product_status_after_recall() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+
+        store_test_product::<Test>(id.clone(), owner, 42);
+
+        assert_ok!(ProductRegistry::recall_product(
+            Origin::signed(owner),
+            id.clone(),
+            b"contamination".to_vec(),
+            0
+        ));
+
+        for status in vec![ProductStatus::Active, ProductStatus::Discontinued] {
+            assert_noop!(
+                ProductRegistry::set_product_status(Origin::signed(owner), id.clone(), status),
+                Error::<Test>::ProductAlreadyRecalled
+            );
+        }
+    });
+}
+
+#[test]
+fn recall_product_with_low_witness() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+
+        store_test_product::<Test>(id.clone(), owner, 42);
+        add_recall_affected(&id, 3);
+
+        assert_noop!(
+            ProductRegistry::recall_product(
+                Origin::signed(owner),
+                id.clone(),
+                b"contamination".to_vec(),
+                2
+            ),
+            Error::<Test>::ProductRecallWitnessTooLow
+        );
+
+        // Weight is refunded down to the actual count
+        let result = ProductRegistry::recall_product(
+            Origin::signed(owner),
+            id,
+            b"contamination".to_vec(),
+            10,
+        );
+        assert_eq!(
+            result.map(|info| info.actual_weight),
+            Ok(Some(<() as WeightInfo>::recall_product(3)))
+        );
+    });
+}
+
+#[test]
+fn create_products_in_batch() {
+    new_test_ext().execute_with(|| {
+        let sender = account_key(TEST_SENDER);
+        let owner = account_key(TEST_ORGANIZATION);
+        let other_owner = account_key(TEST_OTHER_ORGANIZATION);
+        let now = 42;
+        Timestamp::set_timestamp(now);
+
+        let result = ProductRegistry::register_products(
+            Origin::signed(sender),
+            vec![
+                (b"00012345600012".to_vec(), owner, None),
+                (
+                    b"00012345600029".to_vec(),
+                    owner,
+                    Some(vec![text_prop(b"prop1", b"val1")]),
+                ),
+                (b"SKU-1234".to_vec(), other_owner, None),
+            ],
+        );
+
+        assert_ok!(result);
+
+        assert_eq!(
+            <ProductsOfOrganization<Test>>::get(owner),
+            vec![b"00012345600012".to_vec(), b"00012345600029".to_vec()]
+        );
+        assert_eq!(
+            <ProductsOfOrganization<Test>>::get(other_owner),
+            vec![b"SKU-1234".to_vec()]
+        );
+        assert_eq!(ProductRegistry::owner_of(b"SKU-1234".to_vec()), Some(other_owner));
+
+        // Events are raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_registry(RawEvent::ProductRegistered(
+                sender,
+                b"SKU-1234".to_vec(),
+                other_owner
+            ))));
+    });
+}
+
+#[test]
+fn create_products_in_batch_with_invalid_product() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+
+        assert_noop!(
+            ProductRegistry::register_products(
+                Origin::signed(account_key(TEST_SENDER)),
+                vec![
+                    (b"00012345600012".to_vec(), owner, None),
+                    (LONG_VALUE.as_bytes().to_owned(), owner, None),
+                ]
+            ),
+            Error::<Test>::ProductIdTooLong
+        );
+
+        // No product of the batch is registered
+        assert_eq!(ProductRegistry::product_by_id(b"00012345600012".to_vec()), None);
+    });
+}
+
+#[test]
+fn create_products_in_batch_with_duplicate_ids() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+
+        assert_noop!(
+            ProductRegistry::register_products(
+                Origin::signed(account_key(TEST_SENDER)),
+                vec![
+                    (b"00012345600012".to_vec(), owner, None),
+                    (b"00012345600012".to_vec(), owner, None),
+                ]
+            ),
+            Error::<Test>::ProductIdExists
+        );
+    });
+}
+
+#[test]
+fn create_products_in_too_large_batch() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+
+        assert_noop!(
+            ProductRegistry::register_products(
+                Origin::signed(account_key(TEST_SENDER)),
+                vec![
+                    (b"SKU-1".to_vec(), owner, None),
+                    (b"SKU-2".to_vec(), owner, None),
+                    (b"SKU-3".to_vec(), owner, None),
+                    (b"SKU-4".to_vec(), owner, None),
+                ]
+            ),
+            Error::<Test>::ProductBatchTooLarge
+        );
+    });
+}
+
+#[test]
+fn register_product_lot() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+        let sender = account_key(TEST_SENDER);
+        add_org_member(owner, sender);
+        let lot_number = b"L2020-10-42".to_vec();
+
+        store_test_product::<Test>(id.clone(), owner, 42);
+
+        assert_ok!(ProductRegistry::register_lot(
+            Origin::signed(sender),
+            id.clone(),
+            lot_number.clone(),
+            500,
+            1_000,
+            Some(2_000),
+        ));
+
+        assert_eq!(
+            ProductRegistry::lot_by_id(&id, &lot_number),
+            Some(ProductLot {
+                lot_number: lot_number.clone(),
+                product_id: id.clone(),
+                quantity: 500,
+                produced: 1_000,
+                expires: Some(2_000),
+            })
+        );
+        assert!(<ProductRegistry as ProductInfo>::lot_exists(&id, &lot_number));
+
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_registry(RawEvent::ProductLotRegistered(
+                sender, id.clone(), lot_number.clone()
+            ))));
+
+        // Lot numbers are unique per product
+        assert_noop!(
+            ProductRegistry::register_lot(Origin::signed(sender), id, lot_number, 100, 1_000, None),
+            Error::<Test>::ProductLotExists
+        );
+    });
+}
+
+#[test]
+fn register_product_lot_of_unknown_product() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            ProductRegistry::register_lot(
+                Origin::signed(account_key(TEST_ORGANIZATION)),
+                TEST_PRODUCT_ID.as_bytes().to_owned(),
+                b"L1".to_vec(),
+                500,
+                1_000,
+                None
+            ),
+            Error::<Test>::ProductIdUnknown
+        );
+    });
+}
+
+#[test]
+fn register_product_lot_by_non_owner() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+
+        store_test_product::<Test>(id.clone(), account_key(TEST_ORGANIZATION), 42);
+
+        assert_noop!(
+            ProductRegistry::register_lot(
+                Origin::signed(account_key(TEST_OTHER_ORGANIZATION)),
+                id,
+                b"L1".to_vec(),
+                500,
+                1_000,
+                None
+            ),
+            Error::<Test>::NotProductOwner
+        );
+    });
+}
+
+#[test]
+fn register_product_lot_with_invalid_data() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+
+        store_test_product::<Test>(id.clone(), owner, 42);
+
+        assert_noop!(
+            ProductRegistry::register_lot(Origin::signed(owner), id.clone(), vec![], 500, 1_000, None),
+            Error::<Test>::ProductLotNumberMissing
+        );
+        assert_noop!(
+            ProductRegistry::register_lot(
+                Origin::signed(owner),
+                id.clone(),
+                LONG_VALUE.as_bytes().to_owned(),
+                500,
+                1_000,
+                None
+            ),
+            Error::<Test>::ProductLotNumberTooLong
+        );
+        assert_noop!(
+            ProductRegistry::register_lot(Origin::signed(owner), id.clone(), b"L1".to_vec(), 0, 1_000, None),
+            Error::<Test>::ProductLotInvalidQuantity
+        );
+        assert_noop!(
+            ProductRegistry::register_lot(Origin::signed(owner), id, b"L1".to_vec(), 500, 1_000, Some(1_000)),
+            Error::<Test>::ProductLotInvalidDates
+        );
+    });
+}
+
+#[test]
+fn migrate_raw_prop_values_to_text() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+        let author = account_key(TEST_SENDER);
+        StorageVersion::put(Releases::V1);
+
+        // Product & revision log as stored before typed property values
+        let raw_props = Some(vec![(b"desc".to_vec(), b"beef burger".to_vec())]);
+        let raw_product = (id.clone(), owner, raw_props, 42u64, ProductStatus::Active);
+        unhashed::put(&<Products<Test>>::hashed_key_for(&id), &raw_product);
+        let raw_revision = (
+            1u64,
+            author,
+            b"desc".to_vec(),
+            None::<Vec<u8>>,
+            Some(b"beef burger".to_vec()),
+        );
+        unhashed::put(&<ProductRevisions<Test>>::hashed_key_for(&id), &vec![raw_revision]);
+
+        migration::migrate::<Test>();
+
+        assert_eq!(
+            ProductRegistry::product_by_id(&id),
+            Some(Product {
+                id: id.clone(),
+                owner,
+                registered: 42,
+                props: Some(vec![text_prop(b"desc", b"beef burger")]),
+                status: ProductStatus::Active
+            })
+        );
+        assert_eq!(
+            ProductRegistry::revisions_of(&id),
+            vec![ProductRevision {
+                block_number: 1,
+                author,
+                name: b"desc".to_vec(),
+                old_value: None,
+                new_value: Some(PropValue::Text(b"beef burger".to_vec())),
+            }]
+        );
+        assert_eq!(StorageVersion::get(), Releases::V3);
+    })
+}
+
+#[test]
+fn migrate_products_without_status() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        StorageVersion::put(Releases::V1);
+
+        // Products as stored before lifecycle statuses, and after them
+        let raw_props = Some(vec![(b"desc".to_vec(), b"beef burger".to_vec())]);
+        let legacy_id = b"00012345600012".to_vec();
+        let legacy_product = (legacy_id.clone(), owner, raw_props.clone(), 42u64);
+        unhashed::put(&<Products<Test>>::hashed_key_for(&legacy_id), &legacy_product);
+        let id = b"00012345600029".to_vec();
+        let raw_product = (id.clone(), owner, raw_props, 43u64, ProductStatus::Discontinued);
+        unhashed::put(&<Products<Test>>::hashed_key_for(&id), &raw_product);
+
+        <ProductRegistry as OnRuntimeUpgrade>::on_runtime_upgrade();
+
+        assert_eq!(
+            ProductRegistry::product_by_id(&legacy_id),
+            Some(Product {
+                id: legacy_id.clone(),
+                owner,
+                registered: 42,
+                props: Some(vec![text_prop(b"desc", b"beef burger")]),
+                status: ProductStatus::Active
+            })
+        );
+        assert_eq!(
+            ProductRegistry::product_by_id(&id),
+            Some(Product {
+                id: id.clone(),
+                owner,
+                registered: 43,
+                props: Some(vec![text_prop(b"desc", b"beef burger")]),
+                status: ProductStatus::Discontinued
+            })
+        );
+        assert_eq!(StorageVersion::get(), Releases::V3);
+    })
+}
+
+#[test]
+fn migrate_products_to_props_index() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        StorageVersion::put(Releases::V2);
+
+        // Product registered before the index, & product already indexed
+        let meat = PropValue::Text(b"meat".to_vec());
+        let category = vec![ProductProperty::new(b"category", meat.clone())];
+        let legacy_id = b"00012345600012".to_vec();
+        <Products<Test>>::insert(
+            &legacy_id,
+            Product {
+                id: legacy_id.clone(),
+                owner,
+                registered: 42,
+                props: Some(category.clone()),
+                status: ProductStatus::Active,
+            },
+        );
+        let id = b"00012345600029".to_vec();
+        ProductRegistry::insert_product(id.clone(), owner, Some(category));
+
+        <ProductRegistry as OnRuntimeUpgrade>::on_runtime_upgrade();
+
+        assert_eq!(
+            ProductRegistry::products_by_prop(owner, b"category".to_vec(), &meat),
+            vec![id, legacy_id]
+        );
+        assert_eq!(StorageVersion::get(), Releases::V3);
+    })
+}
+
+#[test]
+fn migrate_latest_storage_version() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        StorageVersion::put(Releases::V3);
+        store_test_product::<Test>(id.clone(), account_key(TEST_ORGANIZATION), 42);
+        let key = <Products<Test>>::hashed_key_for(&id);
+        let blob = unhashed::get_raw(&key);
+
+        <ProductRegistry as OnRuntimeUpgrade>::on_runtime_upgrade();
+
+        // Storage is left untouched
+        assert_eq!(unhashed::get_raw(&key), blob);
+        assert_eq!(StorageVersion::get(), Releases::V3);
+    })
+}
+
+#[test]
+fn new_chain_starts_with_latest_storage_version() {
+    let mut storage = frame_system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+    GenesisConfig::default()
+        .assimilate_storage::<Test>(&mut storage)
+        .unwrap();
+
+    sp_io::TestExternalities::from(storage).execute_with(|| {
+        assert_eq!(StorageVersion::get(), Releases::V3);
+    });
+}
+
+fn store_test_certification(product_id: &[u8], id: &[u8], certifier: <Test as system::Trait>::AccountId, expires: Option<u64>) {
+    assert_ok!(ProductRegistry::issue_certification(
+        Origin::signed(certifier),
+        product_id.to_vec(),
+        id.to_vec(),
+        b"organic".to_vec(),
+        expires,
+    ));
+}
+
+#[test]
+fn issue_certification() {
+    new_test_ext().execute_with(|| {
+        let certifier = account_key(TEST_CERTIFIER);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        store_test_product::<Test>(id.clone(), account_key(TEST_ORGANIZATION), 10);
+        add_certifier(certifier);
+        Timestamp::set_timestamp(42);
+
+        let result = ProductRegistry::issue_certification(
+            Origin::signed(certifier),
+            id.clone(),
+            b"CERT-001".to_vec(),
+            b"organic".to_vec(),
+            Some(100),
+        );
+
+        assert_ok!(result);
+
+        assert_eq!(
+            ProductRegistry::certifications_of(&id),
+            vec![Certification {
+                id: b"CERT-001".to_vec(),
+                certifier,
+                scheme: b"organic".to_vec(),
+                issued: 42,
+                expires: Some(100),
+                revoked: None,
+            }]
+        );
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_registry(RawEvent::CertificationIssued(
+                certifier,
+                id,
+                b"CERT-001".to_vec()
+            ))));
+    });
+}
+
+#[test]
+fn issue_certification_by_non_certifier() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        store_test_product::<Test>(id.clone(), account_key(TEST_ORGANIZATION), 10);
+
+        assert_noop!(
+            ProductRegistry::issue_certification(
+                Origin::signed(account_key(TEST_SENDER)),
+                id,
+                b"CERT-001".to_vec(),
+                b"organic".to_vec(),
+                None,
+            ),
+            Error::<Test>::NotCertifier
+        );
+    });
+}
+
+#[test]
+fn issue_certification_with_invalid_data() {
+    new_test_ext().execute_with(|| {
+        let certifier = account_key(TEST_CERTIFIER);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        store_test_product::<Test>(id.clone(), account_key(TEST_ORGANIZATION), 10);
+        add_certifier(certifier);
+        Timestamp::set_timestamp(42);
+
+        let issue = |product_id: &[u8], cert_id: &[u8], scheme: &[u8], expires| {
+            ProductRegistry::issue_certification(
+                Origin::signed(certifier),
+                product_id.to_vec(),
+                cert_id.to_vec(),
+                scheme.to_vec(),
+                expires,
+            )
+        };
+
+        assert_noop!(issue(&id, b"", b"organic", None), Error::<Test>::CertificationInvalidId);
+        assert_noop!(
+            issue(&id, LONG_VALUE.as_bytes(), b"organic", None),
+            Error::<Test>::CertificationInvalidId
+        );
+        assert_noop!(issue(&id, b"CERT-001", b"", None), Error::<Test>::CertificationInvalidScheme);
+        assert_noop!(
+            issue(&id, b"CERT-001", b"organic", Some(42)),
+            Error::<Test>::CertificationInvalidExpiry
+        );
+        assert_noop!(
+            issue(b"00012345600029", b"CERT-001", b"organic", None),
+            Error::<Test>::ProductIdUnknown
+        );
+
+        assert_ok!(issue(&id, b"CERT-001", b"organic", None));
+        assert_noop!(issue(&id, b"CERT-001", b"halal", None), Error::<Test>::CertificationExists);
+
+        assert_ok!(issue(&id, b"CERT-002", b"halal", None));
+        assert_noop!(
+            issue(&id, b"CERT-003", b"kosher", None),
+            Error::<Test>::ProductTooManyCertifications
+        );
+    });
+}
+
+#[test]
+fn expire_certification() {
+    new_test_ext().execute_with(|| {
+        let certifier = account_key(TEST_CERTIFIER);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        store_test_product::<Test>(id.clone(), account_key(TEST_ORGANIZATION), 10);
+        add_certifier(certifier);
+        Timestamp::set_timestamp(42);
+        store_test_certification(&id, b"CERT-001", certifier, Some(100));
+        Timestamp::set_timestamp(50);
+
+        let result = ProductRegistry::expire_certification(
+            Origin::signed(certifier),
+            id.clone(),
+            b"CERT-001".to_vec(),
+        );
+
+        assert_ok!(result);
+
+        assert_eq!(ProductRegistry::certifications_of(&id)[0].expires, Some(50));
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_registry(RawEvent::CertificationExpired(
+                certifier,
+                id.clone(),
+                b"CERT-001".to_vec()
+            ))));
+
+        assert_noop!(
+            ProductRegistry::expire_certification(
+                Origin::signed(certifier),
+                id,
+                b"CERT-001".to_vec()
+            ),
+            Error::<Test>::CertificationEnded
+        );
+    });
+}
+
+#[test]
+fn revoke_certification() {
+    new_test_ext().execute_with(|| {
+        let certifier = account_key(TEST_CERTIFIER);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        store_test_product::<Test>(id.clone(), account_key(TEST_ORGANIZATION), 10);
+        add_certifier(certifier);
+        Timestamp::set_timestamp(42);
+        store_test_certification(&id, b"CERT-001", certifier, None);
+        Timestamp::set_timestamp(50);
+
+        let result = ProductRegistry::revoke_certification(
+            Origin::signed(certifier),
+            id.clone(),
+            b"CERT-001".to_vec(),
+        );
+
+        assert_ok!(result);
+
+        assert_eq!(ProductRegistry::certifications_of(&id)[0].revoked, Some(50));
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_registry(RawEvent::CertificationRevoked(
+                certifier,
+                id.clone(),
+                b"CERT-001".to_vec()
+            ))));
+
+        assert_noop!(
+            ProductRegistry::revoke_certification(
+                Origin::signed(certifier),
+                id,
+                b"CERT-001".to_vec()
+            ),
+            Error::<Test>::CertificationEnded
+        );
+    });
+}
+
+#[test]
+fn revoke_certification_by_other_certifier() {
+    new_test_ext().execute_with(|| {
+        let certifier = account_key(TEST_CERTIFIER);
+        let other = account_key(TEST_SENDER);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        store_test_product::<Test>(id.clone(), account_key(TEST_ORGANIZATION), 10);
+        add_certifier(certifier);
+        add_certifier(other);
+        store_test_certification(&id, b"CERT-001", certifier, None);
+
+        assert_noop!(
+            ProductRegistry::revoke_certification(
+                Origin::signed(other),
+                id.clone(),
+                b"CERT-001".to_vec()
+            ),
+            Error::<Test>::NotCertificationIssuer
+        );
+        assert_noop!(
+            ProductRegistry::revoke_certification(
+                Origin::signed(certifier),
+                id,
+                b"CERT-002".to_vec()
+            ),
+            Error::<Test>::CertificationUnknown
+        );
+    });
+}
+
+#[test]
+fn query_valid_certifications() {
+    new_test_ext().execute_with(|| {
+        let certifier = account_key(TEST_CERTIFIER);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        store_test_product::<Test>(id.clone(), account_key(TEST_ORGANIZATION), 10);
+        add_certifier(certifier);
+        Timestamp::set_timestamp(42);
+        store_test_certification(&id, b"CERT-001", certifier, Some(100));
+        store_test_certification(&id, b"CERT-002", certifier, None);
+        assert_ok!(ProductRegistry::revoke_certification(
+            Origin::signed(certifier),
+            id.clone(),
+            b"CERT-002".to_vec()
+        ));
+
+        let ids_at = |now| {
+            ProductRegistry::valid_certifications(&id, now)
+                .into_iter()
+                .map(|c| c.id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids_at(41), Vec::<Vec<u8>>::new());
+        assert_eq!(ids_at(42), vec![b"CERT-001".to_vec()]);
+        assert_eq!(ids_at(99), vec![b"CERT-001".to_vec()]);
+        assert_eq!(ids_at(100), Vec::<Vec<u8>>::new());
+    });
+}
+
+fn component(product_id: &[u8], quantity: u32) -> ProductComponent {
+    ProductComponent {
+        product_id: product_id.to_vec(),
+        quantity,
+    }
+}
+
+#[test]
+fn set_product_components() {
+    new_test_ext().execute_with(|| {
+        let sender = account_key(TEST_SENDER);
+        let owner = account_key(TEST_ORGANIZATION);
+        let supplier = account_key(TEST_OTHER_ORGANIZATION);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        store_test_product::<Test>(id.clone(), owner, 10);
+        store_test_product::<Test>(b"00012345600029".to_vec(), supplier, 10);
+        store_test_product::<Test>(b"00012345600036".to_vec(), supplier, 10);
+        add_org_member(owner, sender);
+
+        let components = vec![
+            component(b"00012345600029", 2),
+            component(b"00012345600036", 1),
+        ];
+        let result = ProductRegistry::set_product_components(
+            Origin::signed(sender),
+            id.clone(),
+            components.clone(),
+        );
+
+        assert_ok!(result);
+
+        assert_eq!(ProductRegistry::components_of(&id), components);
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_registry(RawEvent::ProductComponentsUpdated(
+                sender,
+                id.clone()
+            ))));
+
+        // Components can be removed altogether
+        assert_ok!(ProductRegistry::set_product_components(
+            Origin::signed(sender),
+            id.clone(),
+            vec![]
+        ));
+        assert_eq!(ProductRegistry::components_of(&id), vec![]);
+    });
+}
+
+#[test]
+fn set_product_components_by_non_owner() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        store_test_product::<Test>(id.clone(), account_key(TEST_ORGANIZATION), 10);
+        store_test_product::<Test>(b"00012345600029".to_vec(), account_key(TEST_OTHER_ORGANIZATION), 10);
+
+        assert_noop!(
+            ProductRegistry::set_product_components(
+                Origin::signed(account_key(TEST_OTHER_ORGANIZATION)),
+                id,
+                vec![component(b"00012345600029", 1)]
+            ),
+            Error::<Test>::NotProductOwner
+        );
+    });
+}
+
+#[test]
+fn set_product_components_with_invalid_data() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        store_test_product::<Test>(id.clone(), owner, 10);
+        store_test_product::<Test>(b"00012345600029".to_vec(), owner, 10);
+
+        let set = |product_id: &[u8], components| {
+            ProductRegistry::set_product_components(Origin::signed(owner), product_id.to_vec(), components)
+        };
+
+        assert_noop!(
+            set(b"00012345600036", vec![component(b"00012345600029", 1)]),
+            Error::<Test>::ProductIdUnknown
+        );
+        assert_noop!(
+            set(&id, vec![component(b"00012345600036", 1)]),
+            Error::<Test>::ProductComponentUnknown
+        );
+        assert_noop!(
+            set(&id, vec![component(b"00012345600029", 0)]),
+            Error::<Test>::ProductComponentInvalidQuantity
+        );
+        assert_noop!(
+            set(
+                &id,
+                vec![component(b"00012345600029", 1), component(b"00012345600029", 2)]
+            ),
+            Error::<Test>::ProductComponentDuplicate
+        );
+        assert_noop!(
+            set(&id, vec![component(b"00012345600029", 1); 4]),
+            Error::<Test>::ProductTooManyComponents
+        );
+    });
+}
+
+#[test]
+fn set_product_components_with_cycle() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        store_test_product::<Test>(b"00012345600012".to_vec(), owner, 10);
+        store_test_product::<Test>(b"00012345600029".to_vec(), owner, 10);
+        store_test_product::<Test>(b"00012345600036".to_vec(), owner, 10);
+
+        let set = |product_id: &[u8], components| {
+            ProductRegistry::set_product_components(Origin::signed(owner), product_id.to_vec(), components)
+        };
+
+        // A product cannot be made of itself
+        assert_noop!(
+            set(b"00012345600012", vec![component(b"00012345600012", 1)]),
+            Error::<Test>::ProductComponentCycle
+        );
+
+        // ...even indirectly
+        assert_ok!(set(b"00012345600012", vec![component(b"00012345600029", 1)]));
+        assert_ok!(set(b"00012345600029", vec![component(b"00012345600036", 1)]));
+        assert_noop!(
+            set(b"00012345600036", vec![component(b"00012345600012", 1)]),
+            Error::<Test>::ProductComponentCycle
+        );
+
+        // Shared components (diamonds) are fine
+        assert_ok!(set(
+            b"00012345600012",
+            vec![component(b"00012345600029", 1), component(b"00012345600036", 1)]
+        ));
+    });
+}
+
+#[test]
+fn query_product_provenance() {
+    new_test_ext().execute_with(|| {
+        let manufacturer = account_key(TEST_ORGANIZATION);
+        let supplier = account_key(TEST_OTHER_ORGANIZATION);
+        store_test_product::<Test>(b"00012345600012".to_vec(), manufacturer, 10);
+        store_test_product::<Test>(b"00012345600029".to_vec(), manufacturer, 10);
+        store_test_product::<Test>(b"00012345600036".to_vec(), supplier, 10);
+        <ProductComponents>::insert(
+            b"00012345600012".to_vec(),
+            vec![component(b"00012345600029", 2)],
+        );
+        <ProductComponents>::insert(
+            b"00012345600029".to_vec(),
+            vec![component(b"00012345600036", 3)],
+        );
+
+        assert_eq!(ProductRegistry::provenance(b"00012345600043").unwrap(), None);
+        assert_eq!(
+            ProductRegistry::provenance(b"00012345600012").unwrap(),
+            Some(ProvenanceNode {
+                product_id: b"00012345600012".to_vec(),
+                supplier: manufacturer,
+                quantity: 1,
+                components: vec![ProvenanceNode {
+                    product_id: b"00012345600029".to_vec(),
+                    supplier: manufacturer,
+                    quantity: 2,
+                    components: vec![ProvenanceNode {
+                        product_id: b"00012345600036".to_vec(),
+                        supplier,
+                        quantity: 3,
+                        components: vec![],
+                    }],
+                }],
+            })
+        );
+    });
+}
+
+#[test]
+fn product_components_tree_too_large() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let sku = |i: u8| vec![b'S', b'K', b'U', b'-', b'0' + i];
+        // SKU-1 is made of SKU-2, itself made of SKU-3... down to SKU-6
+        for i in 0..=6 {
+            store_test_product::<Test>(sku(i), owner, 10);
+        }
+        for i in 1..6 {
+            <ProductComponents>::insert(sku(i), vec![component(&sku(i + 1), 1)]);
+        }
+
+        // 6 products lie below SKU-0, past the bound of 5
+        assert_noop!(
+            ProductRegistry::set_product_components(
+                Origin::signed(owner),
+                sku(0),
+                vec![component(&sku(1), 1)]
+            ),
+            Error::<Test>::ProductComponentTreeTooLarge
+        );
+        assert_ok!(ProductRegistry::set_product_components(
+            Origin::signed(owner),
+            sku(0),
+            vec![component(&sku(2), 1)]
+        ));
+
+        assert!(matches!(
+            ProductRegistry::provenance(&sku(1)),
+            Err(Error::<Test>::ProductComponentTreeTooLarge)
+        ));
+        assert!(ProductRegistry::provenance(&sku(2)).is_ok());
+    });
+}
+
+fn content_hash(content: &[u8]) -> <Test as system::Trait>::Hash {
+    <Test as system::Trait>::Hashing::hash(content)
+}
+
+#[test]
+fn add_product_document() {
+    new_test_ext().execute_with(|| {
+        let sender = account_key(TEST_SENDER);
+        let owner = account_key(TEST_ORGANIZATION);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        store_test_product::<Test>(id.clone(), owner, 10);
+        add_org_member(owner, sender);
+        Timestamp::set_timestamp(42);
+
+        let result = ProductRegistry::add_product_document(
+            Origin::signed(sender),
+            id.clone(),
+            b"spec".to_vec(),
+            content_hash(b"spec sheet v1"),
+            b"ipfs://spec-v1".to_vec(),
+        );
+
+        assert_ok!(result);
+
+        assert_eq!(
+            ProductRegistry::documents_of(&id),
+            vec![ProductDocument {
+                doc_type: b"spec".to_vec(),
+                hash: content_hash(b"spec sheet v1"),
+                uri: b"ipfs://spec-v1".to_vec(),
+                added: 42,
+                superseded: None,
+            }]
+        );
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_registry(RawEvent::ProductDocumentAdded(
+                sender,
+                id.clone(),
+                b"spec".to_vec()
+            ))));
+
+        // Documents of a given type can only be superseded once added
+        assert_noop!(
+            ProductRegistry::add_product_document(
+                Origin::signed(sender),
+                id,
+                b"spec".to_vec(),
+                content_hash(b"spec sheet v2"),
+                b"ipfs://spec-v2".to_vec(),
+            ),
+            Error::<Test>::ProductDocumentExists
+        );
+    });
+}
+
+#[test]
+fn add_product_document_with_invalid_data() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        store_test_product::<Test>(id.clone(), owner, 10);
+
+        let add = |product_id: &[u8], doc_type: &[u8], uri: &[u8]| {
+            ProductRegistry::add_product_document(
+                Origin::signed(owner),
+                product_id.to_vec(),
+                doc_type.to_vec(),
+                content_hash(b"content"),
+                uri.to_vec(),
+            )
+        };
+
+        assert_noop!(add(&id, b"", b"ipfs://doc"), Error::<Test>::ProductDocumentInvalidType);
+        assert_noop!(
+            add(&id, b"specification", b"ipfs://doc"),
+            Error::<Test>::ProductDocumentInvalidType
+        );
+        assert_noop!(add(&id, b"spec", b""), Error::<Test>::ProductDocumentInvalidUri);
+        assert_noop!(
+            add(&id, b"spec", LONG_VALUE.as_bytes()),
+            Error::<Test>::ProductDocumentInvalidUri
+        );
+        assert_noop!(
+            add(b"00012345600029", b"spec", b"ipfs://doc"),
+            Error::<Test>::ProductIdUnknown
+        );
+        assert_noop!(
+            ProductRegistry::add_product_document(
+                Origin::signed(account_key(TEST_OTHER_ORGANIZATION)),
+                id.clone(),
+                b"spec".to_vec(),
+                content_hash(b"content"),
+                b"ipfs://doc".to_vec(),
+            ),
+            Error::<Test>::NotProductOwner
+        );
+
+        assert_ok!(add(&id, b"spec", b"ipfs://doc"));
+        assert_ok!(add(&id, b"sds", b"ipfs://doc"));
+        assert_ok!(add(&id, b"coa", b"ipfs://doc"));
+        assert_noop!(add(&id, b"msds", b"ipfs://doc"), Error::<Test>::ProductTooManyDocuments);
+    });
+}
+
+#[test]
+fn supersede_product_document() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        store_test_product::<Test>(id.clone(), owner, 10);
+        Timestamp::set_timestamp(42);
+
+        assert_noop!(
+            ProductRegistry::supersede_product_document(
+                Origin::signed(owner),
+                id.clone(),
+                b"spec".to_vec(),
+                content_hash(b"spec sheet v2"),
+                b"ipfs://spec-v2".to_vec(),
+            ),
+            Error::<Test>::ProductDocumentUnknown
+        );
+
+        assert_ok!(ProductRegistry::add_product_document(
+            Origin::signed(owner),
+            id.clone(),
+            b"spec".to_vec(),
+            content_hash(b"spec sheet v1"),
+            b"ipfs://spec-v1".to_vec(),
+        ));
+        Timestamp::set_timestamp(50);
+
+        let result = ProductRegistry::supersede_product_document(
+            Origin::signed(owner),
+            id.clone(),
+            b"spec".to_vec(),
+            content_hash(b"spec sheet v2"),
+            b"ipfs://spec-v2".to_vec(),
+        );
+
+        assert_ok!(result);
+
+        assert_eq!(
+            ProductRegistry::documents_of(&id),
+            vec![
+                ProductDocument {
+                    doc_type: b"spec".to_vec(),
+                    hash: content_hash(b"spec sheet v1"),
+                    uri: b"ipfs://spec-v1".to_vec(),
+                    added: 42,
+                    superseded: Some(50),
+                },
+                ProductDocument {
+                    doc_type: b"spec".to_vec(),
+                    hash: content_hash(b"spec sheet v2"),
+                    uri: b"ipfs://spec-v2".to_vec(),
+                    added: 50,
+                    superseded: None,
+                }
+            ]
+        );
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_registry(RawEvent::ProductDocumentSuperseded(
+                owner,
+                id.clone(),
+                b"spec".to_vec()
+            ))));
+    });
+}
+
+#[test]
+fn supersede_product_document_past_limit() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        store_test_product::<Test>(id.clone(), owner, 10);
+
+        let add = |doc_type: &[u8], content: &[u8]| {
+            ProductRegistry::add_product_document(
+                Origin::signed(owner),
+                id.clone(),
+                doc_type.to_vec(),
+                content_hash(content),
+                b"ipfs://doc".to_vec(),
+            )
+        };
+        let supersede = |doc_type: &[u8], content: &[u8]| {
+            ProductRegistry::supersede_product_document(
+                Origin::signed(owner),
+                id.clone(),
+                doc_type.to_vec(),
+                content_hash(content),
+                b"ipfs://doc".to_vec(),
+            )
+        };
+
+        assert_ok!(add(b"spec", b"spec v1"));
+        assert_ok!(add(b"sds", b"sds v1"));
+        assert_ok!(add(b"coa", b"coa v1"));
+
+        // Superseding a full list prunes the oldest superseded version
+        for version in &[b"spec v2", b"spec v3", b"spec v4"] {
+            assert_ok!(supersede(b"spec", *version));
+        }
+
+        let documents = ProductRegistry::documents_of(&id);
+        assert_eq!(documents.len(), 3);
+        assert!(ProductRegistry::verify_document(&id, b"spec", b"spec v4"));
+        assert!(ProductRegistry::verify_document(&id, b"sds", b"sds v1"));
+        assert!(ProductRegistry::verify_document(&id, b"coa", b"coa v1"));
+
+        // Current documents are never pruned
+        assert_noop!(add(b"msds", b"msds v1"), Error::<Test>::ProductTooManyDocuments);
+    });
+}
+
+#[test]
+fn verify_product_document() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        store_test_product::<Test>(id.clone(), owner, 10);
+
+        assert!(!ProductRegistry::verify_document(&id, b"spec", b"spec sheet v1"));
+
+        assert_ok!(ProductRegistry::add_product_document(
+            Origin::signed(owner),
+            id.clone(),
+            b"spec".to_vec(),
+            content_hash(b"spec sheet v1"),
+            b"ipfs://spec-v1".to_vec(),
+        ));
+
+        assert!(ProductRegistry::verify_document(&id, b"spec", b"spec sheet v1"));
+        assert!(!ProductRegistry::verify_document(&id, b"spec", b"tampered spec sheet"));
+        assert!(!ProductRegistry::verify_document(&id, b"sds", b"spec sheet v1"));
+
+        // Only the current version of a document is valid
+        assert_ok!(ProductRegistry::supersede_product_document(
+            Origin::signed(owner),
+            id.clone(),
+            b"spec".to_vec(),
+            content_hash(b"spec sheet v2"),
+            b"ipfs://spec-v2".to_vec(),
+        ));
+
+        assert!(!ProductRegistry::verify_document(&id, b"spec", b"spec sheet v1"));
+        assert!(ProductRegistry::verify_document(&id, b"spec", b"spec sheet v2"));
+    });
+}
+
+fn products_by_category(org: <Test as system::Trait>::AccountId, category: &[u8]) -> Vec<ProductId> {
+    ProductRegistry::products_by_prop(org, b"category".to_vec(), &PropValue::Text(category.to_vec()))
+}
+
+#[test]
+fn index_products_by_prop() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let other_owner = account_key(TEST_OTHER_ORGANIZATION);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let other_id = b"00012345600029".to_vec();
+
+        assert_ok!(ProductRegistry::register_product(
+            Origin::signed(owner),
+            id.clone(),
+            owner,
+            Some(vec![text_prop(b"category", b"meat"), text_prop(b"desc", b"burger")])
+        ));
+        assert_ok!(ProductRegistry::register_product(
+            Origin::signed(owner),
+            other_id.clone(),
+            owner,
+            Some(vec![text_prop(b"category", b"meat")])
+        ));
+
+        assert_eq!(products_by_category(owner, b"meat"), vec![id.clone(), other_id.clone()]);
+        assert_eq!(products_by_category(owner, b"dairy"), Vec::<ProductId>::new());
+        assert_eq!(products_by_category(other_owner, b"meat"), Vec::<ProductId>::new());
+
+        // Only indexed props can be looked up
+        assert_eq!(
+            ProductRegistry::products_by_prop(owner, b"desc".to_vec(), &PropValue::Text(b"burger".to_vec())),
+            Vec::<ProductId>::new()
+        );
+
+        // Index follows prop updates...
+        assert_ok!(ProductRegistry::set_product_props(
+            Origin::signed(owner),
+            id.clone(),
+            vec![text_prop(b"category", b"dairy")]
+        ));
+        assert_eq!(products_by_category(owner, b"meat"), vec![other_id.clone()]);
+        assert_eq!(products_by_category(owner, b"dairy"), vec![id.clone()]);
+
+        // ...removals...
+        assert_ok!(ProductRegistry::remove_product_prop(
+            Origin::signed(owner),
+            id.clone(),
+            b"category".to_vec()
+        ));
+        assert_eq!(products_by_category(owner, b"dairy"), Vec::<ProductId>::new());
+
+        // ...and ownership transfers
+        add_organization(other_owner);
+        assert_ok!(ProductRegistry::transfer_product(
+            Origin::signed(owner),
+            other_id.clone(),
+            other_owner
+        ));
+        assert_eq!(products_by_category(owner, b"meat"), Vec::<ProductId>::new());
+        assert_eq!(products_by_category(other_owner, b"meat"), vec![other_id]);
+    });
+}
+
+#[test]
+fn index_products_by_typed_prop() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let id = TEST_PRODUCT_ID.as_bytes().to_owned();
+
+        assert_ok!(ProductRegistry::register_product(
+            Origin::signed(owner),
+            id.clone(),
+            owner,
+            Some(vec![ProductProperty::new(b"category", PropValue::Integer(42))])
+        ));
+
+        // Values of different types don't match
+        assert_eq!(
+            ProductRegistry::products_by_prop(owner, b"category".to_vec(), &PropValue::Integer(42)),
+            vec![id]
+        );
+        assert_eq!(products_by_category(owner, b"42"), Vec::<ProductId>::new());
+    });
+}
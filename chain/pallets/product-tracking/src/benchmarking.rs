@@ -0,0 +1,335 @@
+//! Benchmarking setup for the product tracking pallet
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+
+use frame_benchmarking::{account, benchmarks};
+use frame_support::traits::OnFinalize;
+use sp_core::crypto::KeyTypeId;
+
+// Upper bound used to benchmark the number of sensor readings per shipping event
+const MAX_READINGS: u32 = 100;
+
+const SEED: u32 = 0;
+
+// Key type of the devices' keys, generated in the keystore to sign readings
+const DEVICE_KEY_TYPE: KeyTypeId = KeyTypeId(*b"devc");
+
+// Origin passing the pallet's CreateRoleOrigin check, along with its account
+fn org_origin<T: Trait>() -> (T::Origin, T::AccountId) {
+    let origin = T::CreateRoleOrigin::successful_origin();
+    let who = ensure_signed(origin.clone()).expect("successful origin is signed; qed");
+    (origin, who)
+}
+
+// Identifier of maximal length, unique for each index
+fn identifier(prefix: u8, i: u32) -> Vec<u8> {
+    let mut id = vec![prefix; IDENTIFIER_MAX_LENGTH - 4];
+    id.extend_from_slice(&i.to_le_bytes());
+    id
+}
+
+fn device<T: Trait>(id: DeviceId, owner: &T::AccountId) -> Device<T::AccountId> {
+    Device {
+        id,
+        owner: owner.clone(),
+        public_key: sp_io::crypto::sr25519_generate(DEVICE_KEY_TYPE, None),
+        reading_types: vec![ReadingType::Temperature],
+    }
+}
+
+// Readings of the shipment, each signed by a device of its own
+fn readings<T: Trait>(shipment_id: &ShipmentId, owner: &T::AccountId, count: u32) -> Vec<SignedReading<T::Moment>> {
+    (0..count)
+        .map(|i| {
+            let device = device::<T>(identifier(b'd', i), owner);
+            let reading = Reading {
+                device_id: device.id.clone(),
+                reading_type: ReadingType::Temperature,
+                timestamp: i.into(),
+                value: Decimal::from_num(20),
+            };
+            let signature = sp_io::crypto::sr25519_sign(
+                DEVICE_KEY_TYPE,
+                &device.public_key,
+                &reading.signing_payload(shipment_id),
+            )
+            .expect("key was just generated; qed");
+            <Devices<T>>::insert(&device.id, device);
+            SignedReading { reading, signature }
+        })
+        .collect()
+}
+
+// Polygon of maximal size, around the given point
+fn polygon(latitude: i32, longitude: i32) -> Geofence {
+    let vertices = (0..GEOFENCE_MAX_VERTICES as i32)
+        .map(|i| ReadPoint {
+            latitude: Decimal::from_num(latitude) + Decimal::from_num(i % 2),
+            longitude: Decimal::from_num(longitude) + Decimal::from_num(i) / Decimal::from_num(10),
+        })
+        .collect();
+    Geofence::Polygon(vertices)
+}
+
+// Shipments consolidated into the given container
+fn load_container<T: Trait>(container: &ShipmentId, owner: &T::AccountId, count: u32) -> Vec<ShipmentId> {
+    let ids: Vec<ShipmentId> = (0..count).map(|i| identifier(b'c', i)).collect();
+    for id in ids.iter() {
+        let shipment = Module::<T>::new_shipment()
+            .identified_by(id.clone())
+            .owned_by(owner.clone())
+            .build();
+        <Shipments<T>>::insert(id, shipment);
+        <ShipmentContainer>::insert(id, container);
+    }
+    <ContainerShipments>::insert(container, &ids);
+    ids
+}
+
+benchmarks! {
+    _ { }
+
+    register_shipment {
+        let p in 0 .. SHIPMENT_MAX_PRODUCTS as u32;
+        let (origin, caller) = org_origin::<T>();
+        let id = identifier(b's', 0);
+        let products: Vec<ProductId> = (0..p).map(|i| identifier(b'p', i)).collect();
+        products.iter().for_each(|id| T::Products::add_product(id));
+        let waypoints: Vec<ReadPoint> = (0..SHIPMENT_MAX_WAYPOINTS as i32)
+            .map(|i| ReadPoint {
+                latitude: Decimal::from_num(48 + i / 5),
+                longitude: Decimal::from_num(2 + i / 2),
+            })
+            .collect();
+        let expected_delivery: Option<T::Moment> = Some((DEADLINE_PERIOD as u32).into());
+    }: _(origin, id.clone(), caller, products, vec![], waypoints, expected_delivery)
+    verify {
+        assert!(Module::<T>::shipment_by_id(&id).is_some());
+    }
+
+    track_shipment {
+        let r in 0 .. MAX_READINGS;
+        let c in 0 .. CONTAINER_MAX_SHIPMENTS as u32;
+        let (origin, caller) = org_origin::<T>();
+        let id = identifier(b's', 0);
+        let products: Vec<ProductId> = (0..SHIPMENT_MAX_PRODUCTS as u32)
+            .map(|i| identifier(b'p', i))
+            .collect();
+        products.iter().for_each(|id| T::Products::add_product(id));
+        Module::<T>::register_shipment(origin.clone(), id.clone(), caller.clone(), products, vec![], vec![], None)?;
+        load_container::<T>(&id, &caller, c);
+        // Every reading goes past the temperature threshold of the container & of
+        // each of its shipments, which are all checked against their own route
+        for shipment_id in core::iter::once(id.clone()).chain(<ContainerShipments>::get(&id)) {
+            <ConditionThresholds>::insert(&shipment_id, vec![ConditionThreshold {
+                reading_type: ReadingType::Temperature,
+                min: None,
+                max: Some(Decimal::from_num(8)),
+            }]);
+            <ShipmentRoutes>::insert(&shipment_id, ShipmentRoute {
+                origin: Some(polygon(52, 13)),
+                destination: Some(polygon(48, 2)),
+                corridor: (0..ROUTE_MAX_CORRIDOR_FENCES as i32).map(|i| polygon(50, 3 + i)).collect(),
+            });
+        }
+        let location = Some(ReadPoint {
+            latitude: Decimal::from_num(52.4941),
+            longitude: Decimal::from_num(13.4410),
+        });
+        let readings = Some(readings::<T>(&id, &caller, r));
+    }: _(origin, id.clone(), ShippingOperation::Pickup, 1u32.into(), location, readings)
+    verify {
+        assert_eq!(
+            Module::<T>::shipment_by_id(&id).map(|s| s.status),
+            Some(ShipmentStatus::InTransit)
+        );
+    }
+
+    flag_overdue_shipments {
+        // Checking the period itself counts as one check
+        let s in 0 .. OVERDUE_MAX_CHECKS_PER_BLOCK as u32 - 1;
+        let owner: T::AccountId = account("shipper", 0, SEED);
+        let ids: Vec<ShipmentId> = (0..s).map(|i| identifier(b's', i)).collect();
+        for id in ids.iter() {
+            let shipment = Module::<T>::new_shipment()
+                .identified_by(id.clone())
+                .owned_by(owner.clone())
+                .expected_by(Some(1u32.into()))
+                .build();
+            <Shipments<T>>::insert(id, shipment);
+        }
+        <ShipmentDeadlines>::insert(0, ids);
+        NextDeadlinePeriod::put(0);
+        timestamp::Module::<T>::set_timestamp((DEADLINE_PERIOD as u32).into());
+    }: {
+        Module::<T>::on_finalize(1u32.into());
+    }
+    verify {
+        assert_eq!(Module::<T>::next_deadline_period(), Some(1));
+    }
+
+    consolidate {
+        let c in 1 .. CONTAINER_MAX_SHIPMENTS as u32;
+        let (origin, caller) = org_origin::<T>();
+        let container = identifier(b's', 0);
+        Module::<T>::register_shipment(origin.clone(), container.clone(), caller.clone(), vec![], vec![], vec![], None)?;
+        let shipments: Vec<ShipmentId> = (0..c).map(|i| identifier(b'c', i)).collect();
+        for id in shipments.iter() {
+            Module::<T>::register_shipment(origin.clone(), id.clone(), caller.clone(), vec![], vec![], vec![], None)?;
+        }
+    }: _(origin, container.clone(), shipments)
+    verify {
+        assert_eq!(Module::<T>::shipments_in_container(&container).len(), c as usize);
+    }
+
+    deconsolidate {
+        let c in 1 .. CONTAINER_MAX_SHIPMENTS as u32;
+        let (origin, caller) = org_origin::<T>();
+        let container = identifier(b's', 0);
+        Module::<T>::register_shipment(origin.clone(), container.clone(), caller.clone(), vec![], vec![], vec![], None)?;
+        load_container::<T>(&container, &caller, c);
+    }: _(origin, container.clone())
+    verify {
+        assert!(Module::<T>::shipments_in_container(&container).is_empty());
+    }
+
+    set_route {
+        let c in 0 .. ROUTE_MAX_CORRIDOR_FENCES as u32;
+        let (origin, caller) = org_origin::<T>();
+        let id = identifier(b's', 0);
+        Module::<T>::register_shipment(origin.clone(), id.clone(), caller, vec![], vec![], vec![], None)?;
+        let corridor: Vec<Geofence> = (0..c as i32).map(|i| polygon(50, 3 + i)).collect();
+    }: _(origin, id.clone(), Some(polygon(52, 13)), Some(polygon(48, 2)), corridor)
+    verify {
+        assert!(Module::<T>::route_of(&id).is_some());
+    }
+
+    set_condition_threshold {
+        let (origin, caller) = org_origin::<T>();
+        let id = identifier(b's', 0);
+        Module::<T>::register_shipment(origin.clone(), id.clone(), caller, vec![], vec![], vec![], None)?;
+        let min = Some(Decimal::from_num(2));
+        let max = Some(Decimal::from_num(8));
+    }: _(origin, id.clone(), ReadingType::Temperature, min, max)
+    verify {
+        assert_eq!(Module::<T>::thresholds_of(&id).len(), 1);
+    }
+
+    offer_custody {
+        let (origin, caller) = org_origin::<T>();
+        let id = identifier(b's', 0);
+        Module::<T>::register_shipment(origin.clone(), id.clone(), caller, vec![], vec![], vec![], None)?;
+        let carrier: T::AccountId = account("carrier", 0, SEED);
+        <ShipmentCarriers<T>>::insert(&id, vec![carrier.clone()]);
+    }: _(origin, id.clone(), carrier.clone())
+    verify {
+        assert_eq!(Module::<T>::custody_offer_of(&id), Some(carrier));
+    }
+
+    authorize_carrier {
+        let (origin, caller) = org_origin::<T>();
+        let id = identifier(b's', 0);
+        Module::<T>::register_shipment(origin.clone(), id.clone(), caller, vec![], vec![], vec![], None)?;
+        let carriers: Vec<T::AccountId> = (1..SHIPMENT_MAX_CARRIERS as u32)
+            .map(|i| account("carrier", i, SEED))
+            .collect();
+        <ShipmentCarriers<T>>::insert(&id, carriers);
+        let carrier: T::AccountId = account("carrier", 0, SEED);
+    }: _(origin, id.clone(), carrier.clone())
+    verify {
+        assert!(Module::<T>::carriers_of(&id).contains(&carrier));
+    }
+
+    revoke_carrier {
+        let (origin, caller) = org_origin::<T>();
+        let id = identifier(b's', 0);
+        Module::<T>::register_shipment(origin.clone(), id.clone(), caller, vec![], vec![], vec![], None)?;
+        let carriers: Vec<T::AccountId> = (0..SHIPMENT_MAX_CARRIERS as u32)
+            .map(|i| account("carrier", i, SEED))
+            .collect();
+        <ShipmentCarriers<T>>::insert(&id, carriers.clone());
+        // Last carrier has a pending custody offer, to be withdrawn
+        let carrier = carriers[SHIPMENT_MAX_CARRIERS - 1].clone();
+        <CustodyOffers<T>>::insert(&id, &carrier);
+    }: _(origin, id.clone(), carrier.clone())
+    verify {
+        assert!(!Module::<T>::carriers_of(&id).contains(&carrier));
+    }
+
+    accept_custody {
+        let c in 0 .. CONTAINER_MAX_SHIPMENTS as u32;
+        let (origin, caller) = org_origin::<T>();
+        let id = identifier(b's', 0);
+        let shipper: T::AccountId = account("shipper", 0, SEED);
+        let shipment = Module::<T>::new_shipment()
+            .identified_by(id.clone())
+            .owned_by(shipper.clone())
+            .build();
+        <Shipments<T>>::insert(&id, shipment);
+        load_container::<T>(&id, &shipper, c);
+        <ShipmentCarriers<T>>::insert(&id, vec![caller.clone()]);
+        <CustodyOffers<T>>::insert(&id, &caller);
+        let location = Some(ReadPoint {
+            latitude: Decimal::from_num(52.4941),
+            longitude: Decimal::from_num(13.4410),
+        });
+    }: _(origin, id.clone(), 1u32.into(), location)
+    verify {
+        assert_eq!(Module::<T>::shipment_by_id(&id).map(|s| s.custodian), Some(caller));
+    }
+
+    register_device {
+        let (origin, caller) = org_origin::<T>();
+        let id = identifier(b'd', 0);
+        let public_key = sp_io::crypto::sr25519_generate(DEVICE_KEY_TYPE, None);
+        let reading_types = vec![
+            ReadingType::Humidity,
+            ReadingType::Pressure,
+            ReadingType::Shock,
+            ReadingType::Tilt,
+            ReadingType::Temperature,
+            ReadingType::Vibration,
+        ];
+    }: _(origin, id.clone(), caller, public_key, reading_types)
+    verify {
+        assert!(Module::<T>::device_by_id(&id).is_some());
+    }
+
+    deregister_device {
+        let (origin, caller) = org_origin::<T>();
+        let id = identifier(b'd', 0);
+        <Devices<T>>::insert(&id, device::<T>(id.clone(), &caller));
+        <DevicesOfOrganization<T>>::insert(&caller, vec![id.clone()]);
+    }: _(origin, id.clone())
+    verify {
+        assert!(Module::<T>::device_by_id(&id).is_none());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{new_test_ext, Test};
+    use frame_support::assert_ok;
+
+    #[test]
+    fn test_benchmarks() {
+        new_test_ext().execute_with(|| {
+            assert_ok!(test_benchmark_register_shipment::<Test>());
+            assert_ok!(test_benchmark_track_shipment::<Test>());
+            assert_ok!(test_benchmark_flag_overdue_shipments::<Test>());
+            assert_ok!(test_benchmark_consolidate::<Test>());
+            assert_ok!(test_benchmark_deconsolidate::<Test>());
+            assert_ok!(test_benchmark_set_route::<Test>());
+            assert_ok!(test_benchmark_set_condition_threshold::<Test>());
+            assert_ok!(test_benchmark_offer_custody::<Test>());
+            assert_ok!(test_benchmark_authorize_carrier::<Test>());
+            assert_ok!(test_benchmark_revoke_carrier::<Test>());
+            assert_ok!(test_benchmark_accept_custody::<Test>());
+            assert_ok!(test_benchmark_register_device::<Test>());
+            assert_ok!(test_benchmark_deregister_device::<Test>());
+        });
+    }
+}
@@ -1,6 +1,6 @@
 use crate::types::*;
 use frame_support::sp_std::prelude::*;
-use product_registry::ProductId;
+use product_registry::{LotId, ProductId};
 
 // --- ShipmentBuilder ---
 
@@ -13,12 +13,15 @@ where
     id: ShipmentId,
     owner: AccountId,
     products: Vec<ProductId>,
+    lots: Vec<LotId>,
     registered: Moment,
+    waypoints: Vec<ReadPoint>,
+    expected_delivery: Option<Moment>,
 }
 
 impl<AccountId, Moment> ShipmentBuilder<AccountId, Moment>
 where
-    AccountId: Default,
+    AccountId: Default + Clone,
     Moment: Default,
 {
     pub fn identified_by(mut self, id: ShipmentId) -> Self {
@@ -36,19 +39,38 @@ where
         self
     }
 
+    pub fn with_lots(mut self, lots: Vec<LotId>) -> Self {
+        self.lots = lots;
+        self
+    }
+
     pub fn registered_at(mut self, registered: Moment) -> Self {
         self.registered = registered;
         self
     }
 
+    pub fn with_waypoints(mut self, waypoints: Vec<ReadPoint>) -> Self {
+        self.waypoints = waypoints;
+        self
+    }
+
+    pub fn expected_by(mut self, expected_delivery: Option<Moment>) -> Self {
+        self.expected_delivery = expected_delivery;
+        self
+    }
+
     pub fn build(self) -> Shipment<AccountId, Moment> {
         Shipment::<AccountId, Moment> {
             id: self.id,
+            custodian: self.owner.clone(),
             owner: self.owner,
             products: self.products,
+            lots: self.lots,
             registered: self.registered,
             status: ShipmentStatus::Pending,
             delivered: None,
+            waypoints: self.waypoints,
+            expected_delivery: self.expected_delivery,
         }
     }
 }
@@ -0,0 +1,257 @@
+//! Export of shipping events as GS1 EPCIS 2.0 JSON-LD, for partners consuming EPCIS
+//! rather than SCALE-encoded events.
+
+#![cfg(feature = "std")]
+
+use super::*;
+use serde_json::{json, Map, Value};
+use frame_support::sp_runtime::traits::UniqueSaturatedInto;
+
+const EPCIS_CONTEXT: &str = "https://ref.gs1.org/standards/epcis/2.0.0/epcis-context.jsonld";
+// Products identified by a GTIN are referenced by their GS1 Digital Link URIs
+const GS1_DIGITAL_LINK: &str = "https://id.gs1.org";
+// Valid lengths of a GTIN, zero-padded to a GTIN-14 in Digital Link URIs
+const GTIN_LENGTHS: [usize; 4] = [8, 12, 13, 14];
+// Other products & lots, shipments & devices have no GS1 identifier, hence URNs of their own
+const PRODUCT_URI_PREFIX: &str = "urn:x-product-tracking:product:";
+const LOT_URI_PREFIX: &str = "urn:x-product-tracking:lot:";
+const SHIPMENT_URI_PREFIX: &str = "urn:x-product-tracking:shipment:";
+const DEVICE_URI_PREFIX: &str = "urn:x-product-tracking:device:";
+
+// EPCIS ObjectEvent of a shipment's shipping event, other than a (de)consolidation
+pub fn object_event<AccountId, Moment>(
+    shipment: &Shipment<AccountId, Moment>,
+    event: &ShippingEvent<Moment>,
+) -> Value
+where
+    Moment: Copy + UniqueSaturatedInto<u64>,
+{
+    let (action, biz_step, disposition) = business_context(&event.event_type);
+    let mut object = json!({
+        "@context": [EPCIS_CONTEXT],
+        "type": "ObjectEvent",
+        "eventTime": date_time(event.timestamp),
+        "eventTimeZoneOffset": "+00:00",
+        "epcList": [uri(SHIPMENT_URI_PREFIX, &shipment.id)],
+        "action": action,
+        "bizStep": biz_step,
+        "disposition": disposition,
+    });
+    let fields = object.as_object_mut().expect("built as an object; qed");
+
+    // Products & lots shipped are classes of objects, in unspecified quantities
+    let classes: Vec<Value> = shipment
+        .products
+        .iter()
+        .map(|product_id| epc_class(product_id, None))
+        .chain(
+            shipment
+                .lots
+                .iter()
+                .map(|(product_id, lot_number)| epc_class(product_id, Some(lot_number))),
+        )
+        .map(|epc_class| json!({ "epcClass": epc_class }))
+        .collect();
+    if !classes.is_empty() {
+        fields.insert("quantityList".into(), classes.into());
+    }
+
+    insert_observations(fields, event);
+    object
+}
+
+// EPCIS AggregationEvent of a (de)consolidation, the container being the parent
+// of the shipments loaded into or unloaded from it
+pub fn aggregation_event<Moment>(
+    container: &ShipmentId,
+    shipments: &[ShipmentId],
+    event: &ShippingEvent<Moment>,
+) -> Value
+where
+    Moment: Copy + UniqueSaturatedInto<u64>,
+{
+    let (action, biz_step, disposition) = business_context(&event.event_type);
+    let child_epcs: Vec<String> = shipments.iter().map(|id| uri(SHIPMENT_URI_PREFIX, id)).collect();
+    let mut aggregation = json!({
+        "@context": [EPCIS_CONTEXT],
+        "type": "AggregationEvent",
+        "eventTime": date_time(event.timestamp),
+        "eventTimeZoneOffset": "+00:00",
+        "parentID": uri(SHIPMENT_URI_PREFIX, container),
+        "childEPCs": child_epcs,
+        "action": action,
+        "bizStep": biz_step,
+        "disposition": disposition,
+    });
+    let fields = aggregation.as_object_mut().expect("built as an object; qed");
+
+    insert_observations(fields, event);
+    aggregation
+}
+
+// EPCIS document holding the given events
+pub fn document<Moment>(events: Vec<Value>, created: Moment) -> Value
+where
+    Moment: UniqueSaturatedInto<u64>,
+{
+    json!({
+        "@context": [EPCIS_CONTEXT],
+        "type": "EPCISDocument",
+        "schemaVersion": "2.0",
+        "creationDate": date_time(created),
+        "epcisBody": { "eventList": events },
+    })
+}
+
+impl<T: Trait> Module<T> {
+    // EPCIS document of a shipment's shipping events, none if the shipment is unknown
+    pub fn epcis_document(id: &ShipmentId, created: T::Moment) -> Option<Value> {
+        let shipment = Self::shipment_by_id(id)?;
+        let events = Self::events_of_shipment(id)
+            .into_iter()
+            .filter_map(|event_idx| {
+                let event = Self::event_by_idx(event_idx)?;
+                Some(match Self::aggregation_of_event(event_idx) {
+                    Some((container, shipments)) => aggregation_event(&container, &shipments, &event),
+                    None => object_event(&shipment, &event),
+                })
+            })
+            .collect();
+        Some(document(events, created))
+    }
+}
+
+// Action, business step & disposition (from the GS1 Core Business Vocabulary) of an event
+fn business_context(event_type: &ShippingEventType) -> (&'static str, &'static str, &'static str) {
+    match event_type {
+        ShippingEventType::ShipmentRegistration => ("ADD", "staging_outbound", "active"),
+        ShippingEventType::ShipmentPickup => ("OBSERVE", "shipping", "in_transit"),
+        ShippingEventType::ShipmentScan => ("OBSERVE", "transporting", "in_transit"),
+        ShippingEventType::ShipmentDeliver => ("OBSERVE", "receiving", "in_progress"),
+        ShippingEventType::CustodyTransfer => ("OBSERVE", "accepting", "in_transit"),
+        ShippingEventType::ShipmentCancel => ("OBSERVE", "void_shipping", "inactive"),
+        ShippingEventType::ShipmentHold => ("OBSERVE", "holding", "unavailable"),
+        ShippingEventType::ShipmentResume => ("OBSERVE", "transporting", "in_transit"),
+        ShippingEventType::ShipmentReturn => ("OBSERVE", "receiving", "returned"),
+        ShippingEventType::ShipmentLoss => ("OBSERVE", "other", "unknown"),
+        ShippingEventType::ShipmentConsolidation => ("ADD", "packing", "container_closed"),
+        ShippingEventType::ShipmentDeconsolidation => ("DELETE", "unpacking", "container_open"),
+    }
+}
+
+// Location & readings of an event, left out when missing
+fn insert_observations<Moment>(fields: &mut Map<String, Value>, event: &ShippingEvent<Moment>)
+where
+    Moment: Copy + UniqueSaturatedInto<u64>,
+{
+    if let Some(location) = &event.location {
+        let geo = format!(
+            "geo:{:.5},{:.5}",
+            location.latitude.to_num::<f64>(),
+            location.longitude.to_num::<f64>()
+        );
+        fields.insert("readPoint".into(), json!({ "id": geo }));
+    }
+
+    if !event.readings.is_empty() {
+        let sensor_elements: Vec<Value> = event.readings.iter().map(sensor_element).collect();
+        fields.insert("sensorElementList".into(), sensor_elements.into());
+    }
+}
+
+fn sensor_element<Moment>(reading: &Reading<Moment>) -> Value
+where
+    Moment: Copy + UniqueSaturatedInto<u64>,
+{
+    // Measurement types & their units, as UN/CEFACT Recommendation 20 codes
+    let (measurement_type, uom) = match reading.reading_type {
+        ReadingType::Humidity => ("gs1:RelativeHumidity", "P1"),
+        ReadingType::Pressure => ("gs1:AbsolutePressure", "KPA"),
+        ReadingType::Shock | ReadingType::Vibration => ("gs1:Acceleration", "MSK"),
+        ReadingType::Tilt => ("gs1:Angle", "DD"),
+        ReadingType::Temperature => ("gs1:Temperature", "CEL"),
+    };
+    // Decimals are precise to about 4 decimal places
+    let value = (reading.value.to_num::<f64>() * 10_000.0).round() / 10_000.0;
+
+    json!({
+        "sensorMetadata": {
+            "time": date_time(reading.timestamp),
+            "deviceID": uri(DEVICE_URI_PREFIX, &reading.device_id),
+        },
+        "sensorReport": [{ "type": measurement_type, "value": value, "uom": uom }],
+    })
+}
+
+// Class of a product, or of a lot of it: a GS1 Digital Link URI for GTINs, a URN otherwise
+fn epc_class(product_id: &[u8], lot_number: Option<&[u8]>) -> String {
+    let is_gtin = GTIN_LENGTHS.contains(&product_id.len()) && product_id.iter().all(u8::is_ascii_digit);
+    match (is_gtin, lot_number) {
+        (true, None) => format!("{}/01/{}", GS1_DIGITAL_LINK, gtin_14(product_id)),
+        (true, Some(lot_number)) => format!(
+            "{}/01/{}/10/{}",
+            GS1_DIGITAL_LINK,
+            gtin_14(product_id),
+            percent_encode(lot_number)
+        ),
+        (false, None) => uri(PRODUCT_URI_PREFIX, product_id),
+        (false, Some(lot_number)) => format!(
+            "{}{}:{}",
+            LOT_URI_PREFIX,
+            percent_encode(product_id),
+            percent_encode(lot_number)
+        ),
+    }
+}
+
+// GTIN left-padded with zeros to 14 digits, as required in Digital Link URIs
+fn gtin_14(gtin: &[u8]) -> String {
+    format!("{:0>14}", String::from_utf8_lossy(gtin))
+}
+
+fn uri(prefix: &str, id: &[u8]) -> String {
+    format!("{}{}", prefix, percent_encode(id))
+}
+
+// Bytes percent-encoded but for unreserved characters (RFC 3986), making up a valid URI segment
+fn percent_encode(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+// UTC date & time of a moment in milliseconds since the UNIX epoch, as per RFC 3339
+fn date_time<Moment: UniqueSaturatedInto<u64>>(moment: Moment) -> String {
+    let millis: u64 = moment.unique_saturated_into();
+    let seconds = millis / 1000;
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        seconds % 86_400 / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+        millis % 1000
+    )
+}
+
+// Gregorian calendar date of a number of days since the UNIX epoch,
+// using Howard Hinnant's `civil_from_days` algorithm
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = (if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 }) as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
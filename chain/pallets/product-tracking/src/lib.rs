@@ -12,11 +12,16 @@ use frame_support::{
         storage_lock::{StorageLock, Time},
     },
     sp_std::prelude::*,
+    sp_std::collections::btree_set::BTreeSet,
+    sp_runtime::traits::{SaturatedConversion, Verify},
     traits::EnsureOrigin,
+    weights::Weight,
 };
 use frame_system::{self as system, ensure_signed, offchain::SendTransactionTypes};
 
-use product_registry::ProductId;
+use product_registry::{
+    LotId, OnProductRecalled, Organizations, ProductId, ProductInfo, ProductStatus,
+};
 
 #[cfg(test)]
 mod mock;
@@ -24,6 +29,11 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
+mod benchmarking;
+mod default_weights;
+mod migration;
+pub mod epcis;
+
 mod types;
 use crate::types::*;
 
@@ -34,12 +44,39 @@ use crate::builders::*;
 // Note: these could also be passed as trait config parameters
 pub const IDENTIFIER_MAX_LENGTH: usize = 36;
 pub const SHIPMENT_MAX_PRODUCTS: usize = 10;
+pub const SHIPMENT_MAX_CARRIERS: usize = 10;
+pub const GEOFENCE_MAX_VERTICES: usize = 20;
+pub const ROUTE_MAX_CORRIDOR_FENCES: usize = 10;
+pub const SHIPMENT_MAX_WAYPOINTS: usize = 20;
+pub const CONTAINER_MAX_SHIPMENTS: usize = 20;
+pub const DEADLINE_PERIOD: u64 = 60_000; // in milli-seconds
+pub const OVERDUE_MAX_CHECKS_PER_BLOCK: usize = 50;
+pub const DEVICE_MAX_READING_TYPES: usize = 6;
 pub const LISTENER_ENDPOINT: &str = "http://localhost:3005";
 pub const LOCK_TIMEOUT_EXPIRATION: u64 = 3000; // in milli-seconds
 
+pub trait WeightInfo {
+    fn register_shipment(p: u32) -> Weight;
+    fn track_shipment(r: u32, c: u32) -> Weight;
+    fn offer_custody() -> Weight;
+    fn accept_custody(c: u32) -> Weight;
+    fn authorize_carrier() -> Weight;
+    fn revoke_carrier() -> Weight;
+    fn set_condition_threshold() -> Weight;
+    fn set_route(c: u32) -> Weight;
+    fn flag_overdue_shipments(s: u32) -> Weight;
+    fn consolidate(c: u32) -> Weight;
+    fn deconsolidate(c: u32) -> Weight;
+    fn register_device() -> Weight;
+    fn deregister_device() -> Weight;
+}
+
 pub trait Trait: system::Trait + timestamp::Trait + SendTransactionTypes<Call<Self>> {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
     type CreateRoleOrigin: EnsureOrigin<Self::Origin>;
+    type Products: ProductInfo;
+    type Organizations: Organizations<Self::AccountId>;
+    type WeightInfo: WeightInfo;
 }
 
 decl_storage! {
@@ -47,14 +84,44 @@ decl_storage! {
         // Shipments
         pub Shipments get(fn shipment_by_id): map hasher(blake2_128_concat) ShipmentId => Option<Shipment<T::AccountId, T::Moment>>;
         pub ShipmentsOfOrganization get(fn shipments_of_org): map hasher(blake2_128_concat) T::AccountId => Vec<ShipmentId>;
+        pub ShipmentsOfProduct get(fn shipments_of_product): map hasher(blake2_128_concat) ProductId => Vec<ShipmentId>;
+        // Carrier organizations authorized by a shipment's owner to handle it
+        pub ShipmentCarriers get(fn carriers_of): map hasher(blake2_128_concat) ShipmentId => Vec<T::AccountId>;
+        // Organization a shipment's custody has been offered to, pending its acceptance
+        pub CustodyOffers get(fn custody_offer_of): map hasher(blake2_128_concat) ShipmentId => Option<T::AccountId>;
+        // Shipments consolidated into a container (itself a shipment), and the other way around
+        pub ContainerShipments get(fn shipments_in_container): map hasher(blake2_128_concat) ShipmentId => Vec<ShipmentId>;
+        pub ShipmentContainer get(fn container_of): map hasher(blake2_128_concat) ShipmentId => Option<ShipmentId>;
+        // Acceptable ranges of a shipment's readings, at most one per reading type
+        pub ConditionThresholds get(fn thresholds_of): map hasher(blake2_128_concat) ShipmentId => Vec<ConditionThreshold>;
+        // Reading types whose threshold a shipment's readings went past, flagging the shipment
+        pub ConditionViolations get(fn condition_violations_of): map hasher(blake2_128_concat) ShipmentId => Vec<ReadingType>;
+        // Geofences a shipment's scans & delivery are checked against
+        pub ShipmentRoutes get(fn route_of): map hasher(blake2_128_concat) ShipmentId => Option<ShipmentRoute>;
+        // Shipments expected to be delivered during a period (see DEADLINE_PERIOD),
+        // and the next period to check for overdue shipments, from the first block on
+        pub ShipmentDeadlines get(fn shipments_due_in): map hasher(twox_64_concat) u64 => Vec<ShipmentId>;
+        pub NextDeadlinePeriod get(fn next_deadline_period): Option<u64>;
+
+        // Sensor devices
+        pub Devices get(fn device_by_id): map hasher(blake2_128_concat) DeviceId => Option<Device<T::AccountId>>;
+        pub DevicesOfOrganization get(fn devices_of_org): map hasher(blake2_128_concat) T::AccountId => Vec<DeviceId>;
 
         // Shipping events
         pub EventCount get(fn event_count): u128 = 0;
         pub AllEvents get(fn event_by_idx): map hasher(blake2_128_concat) ShippingEventIndex => Option<ShippingEvent<T::Moment>>;
         pub EventsOfShipment get(fn events_of_shipment): map hasher(blake2_128_concat) ShipmentId => Vec<ShippingEventIndex>;
+        // Container & shipments of (de)consolidation events: all of its shipments for the container's event,
+        // only the shipment itself for each shipment's event. Shipping events don't name the container,
+        // and container storage only holds its current shipments (cleared on deconsolidation), so this is
+        // the only record left in state of who took part in past aggregations, e.g. for EPCIS export
+        pub EventAggregations get(fn aggregation_of_event): map hasher(blake2_128_concat) ShippingEventIndex => Option<(ShipmentId, Vec<ShipmentId>)>;
 
         // Off-chain Worker notifications
         pub OcwNotifications get (fn ocw_notifications): map hasher(identity) T::BlockNumber => Vec<ShippingEventIndex>;
+
+        // Storage layout version, new chains start with the latest one
+        StorageVersion build(|_| Releases::V3): Releases;
     }
 }
 
@@ -62,9 +129,24 @@ decl_event!(
     pub enum Event<T>
     where
         AccountId = <T as system::Trait>::AccountId,
+        Moment = <T as timestamp::Trait>::Moment,
     {
         ShipmentRegistered(AccountId, ShipmentId, AccountId),
         ShipmentStatusUpdated(AccountId, ShipmentId, ShippingEventIndex, ShipmentStatus),
+        ShipmentAffectedByRecall(ShipmentId, ProductId),
+        ShipmentCustodyOffered(AccountId, ShipmentId, AccountId),
+        ShipmentCustodyTransferred(AccountId, ShipmentId, ShippingEventIndex, AccountId),
+        ShipmentCarrierAuthorized(AccountId, ShipmentId, AccountId),
+        ShipmentCarrierRevoked(AccountId, ShipmentId, AccountId),
+        ShipmentConditionThresholdSet(AccountId, ShipmentId, ReadingType),
+        ShipmentConditionViolated(ShipmentId, ReadingType, Decimal, DeviceId),
+        ShipmentRouteSet(AccountId, ShipmentId),
+        ShipmentRouteDeviated(ShipmentId, ShippingEventIndex, ReadPoint),
+        ShipmentOverdue(ShipmentId, Moment),
+        ShipmentsConsolidated(AccountId, ShipmentId, Vec<ShipmentId>),
+        ShipmentsDeconsolidated(AccountId, ShipmentId, Vec<ShipmentId>),
+        DeviceRegistered(AccountId, DeviceId, AccountId),
+        DeviceDeregistered(AccountId, DeviceId),
     }
 );
 
@@ -72,12 +154,45 @@ decl_error! {
     pub enum Error for Module<T: Trait> {
         InvalidOrMissingIdentifier,
         ShipmentAlreadyExists,
-        ShipmentHasBeenDelivered,
-        ShipmentIsInTransit,
+        ShipmentIsClosed,
         ShipmentIsUnknown,
         ShipmentHasTooManyProducts,
+        ShipmentHasInactiveProduct,
+        ShipmentHasUnknownProduct,
+        ShipmentHasUnknownLot,
         ShippingEventAlreadyExists,
         ShippingEventMaxExceeded,
+        ShipmentCustodyUnchanged,
+        ShipmentHasNoCustodyOffer,
+        NotShipmentCustodian,
+        NotShipmentCustodyRecipient,
+        ShipmentHasTooManyCarriers,
+        ShipmentCarrierAlreadyAuthorized,
+        ShipmentCarrierNotAuthorized,
+        ShipmentCarrierHasCustody,
+        NotShipmentOwner,
+        NotAuthorizedForShipment,
+        InvalidStatusTransition,
+        InvalidConditionThreshold,
+        InvalidGeofence,
+        ShipmentRouteHasTooManyFences,
+        ShipmentLocationIsMissing,
+        ShipmentIsOutsideDestination,
+        ShipmentHasTooManyWaypoints,
+        InvalidWaypoint,
+        InvalidExpectedDelivery,
+        ShipmentIsConsolidated,
+        ShipmentIsContainer,
+        ContainerHasNoShipments,
+        ShipmentStatusMismatch,
+        ContainerHasTooManyShipments,
+        DeviceAlreadyExists,
+        DeviceIsUnknown,
+        NotDeviceOwner,
+        InvalidDeviceReadingTypes,
+        DeviceNotAuthorizedForShipment,
+        DeviceReadingTypeNotAllowed,
+        InvalidReadingSignature,
         OffchainWorkerAlreadyBusy
     }
 }
@@ -87,16 +202,41 @@ decl_module! {
         type Error = Error<T>;
         fn deposit_event() = default;
 
-        #[weight = 10_000]
-        pub fn register_shipment(origin, id: ShipmentId, owner: T::AccountId, products: Vec<ProductId>) -> dispatch::DispatchResult {
+        fn on_runtime_upgrade() -> Weight {
+            migration::migrate::<T>()
+        }
+
+        fn on_initialize(_block_number: T::BlockNumber) -> Weight {
+            // Reserve the weight of the overdue shipments check, done once the block's timestamp is set
+            T::WeightInfo::flag_overdue_shipments(OVERDUE_MAX_CHECKS_PER_BLOCK as u32)
+        }
+
+        fn on_finalize(_block_number: T::BlockNumber) {
+            Self::flag_overdue_shipments(<timestamp::Module<T>>::now());
+        }
+
+        #[weight = T::WeightInfo::register_shipment((products.len() + lots.len()) as u32)]
+        pub fn register_shipment(
+            origin,
+            id: ShipmentId,
+            owner: T::AccountId,
+            products: Vec<ProductId>,
+            lots: Vec<LotId>,
+            waypoints: Vec<ReadPoint>,
+            expected_delivery: Option<T::Moment>
+        ) -> dispatch::DispatchResult {
             T::CreateRoleOrigin::ensure_origin(origin.clone())?;
             let who = ensure_signed(origin)?;
+            let now = <timestamp::Module<T>>::now();
 
             // Validate format of shipment ID
             Self::validate_identifier(&id)?;
 
-            // Validate shipment products
-            Self::validate_shipment_products(&products)?;
+            // Validate shipment products & lots (1 DB read per product, 2 DB reads per lot)
+            Self::validate_shipment_products(&products, &lots)?;
+
+            // Validate planned route & delivery deadline
+            Self::validate_shipment_plan(&waypoints, expected_delivery, now)?;
 
             // Check shipment doesn't exist yet (1 DB read)
             Self::validate_new_shipment(&id)?;
@@ -105,8 +245,11 @@ decl_module! {
             let shipment = Self::new_shipment()
                 .identified_by(id.clone())
                 .owned_by(owner.clone())
-                .registered_at(<timestamp::Module<T>>::now())
-                .with_products(products)
+                .registered_at(now)
+                .with_products(products.clone())
+                .with_lots(lots.clone())
+                .with_waypoints(waypoints)
+                .expected_by(expected_delivery)
                 .build();
             let status = shipment.status.clone();
 
@@ -124,6 +267,18 @@ decl_module! {
             // Add shipment (2 DB write)
             <Shipments<T>>::insert(&id, shipment);
             <ShipmentsOfOrganization<T>>::append(&owner, &id);
+            // Index shipment by product, including products of lots (1 DB write per product)
+            let product_ids: BTreeSet<_> = products
+                .iter()
+                .chain(lots.iter().map(|(product_id, _)| product_id))
+                .collect();
+            for product_id in product_ids {
+                ShipmentsOfProduct::append(product_id, &id);
+            }
+            // Schedule the overdue check (1 DB write)
+            if let Some(deadline) = expected_delivery {
+                <ShipmentDeadlines>::append(Self::deadline_period(deadline), &id);
+            }
             // Store shipping event (1 DB read, 3 DB writes)
             let event_idx = Self::store_event(event)?;
             // Update offchain notifications (1 DB write)
@@ -136,14 +291,17 @@ decl_module! {
             Ok(())
         }
 
-        #[weight = 10_000]
+        #[weight = T::WeightInfo::track_shipment(
+            readings.as_ref().map_or(0, |r| r.len()) as u32,
+            CONTAINER_MAX_SHIPMENTS as u32
+        )]
         pub fn track_shipment(
             origin,
             id: ShipmentId,
             operation: ShippingOperation,
             #[compact] timestamp: T::Moment,
             location: Option<ReadPoint>,
-            readings: Option<Vec<Reading<T::Moment>>>
+            readings: Option<Vec<SignedReading<T::Moment>>>
         ) -> dispatch::DispatchResult {
             T::CreateRoleOrigin::ensure_origin(origin.clone())?;
             let who = ensure_signed(origin)?;
@@ -151,31 +309,46 @@ decl_module! {
             // Validate format of shipment ID
             Self::validate_identifier(&id)?;
 
-            // Check shipment is known (1 DB read) & do transition checks
-            let mut shipment = match <Shipments<T>>::get(&id) {
-                Some(shipment) => match shipment.status {
-                    ShipmentStatus::Delivered => Err(<Error<T>>::ShipmentHasBeenDelivered),
-                    ShipmentStatus::InTransit if operation == ShippingOperation::Pickup =>
-                        Err(<Error<T>>::ShipmentIsInTransit),
-                    _ => Ok(shipment)
-                }
-                None => Err(<Error<T>>::ShipmentIsUnknown)
-            }?;
+            // Check shipment is known (1 DB read) & operation is allowed in its current status
+            let shipment = <Shipments<T>>::get(&id).ok_or(Error::<T>::ShipmentIsUnknown)?;
+            let status = shipment.status
+                .transition(&operation)
+                .ok_or(Error::<T>::InvalidStatusTransition)?;
+
+            // Only the owner organization's or authorized carriers' members may
+            // track the shipment (1 DB read), while in their custody
+            Self::validate_authorized(&shipment, &who)?;
+            Self::validate_custodian(&shipment, &who)?;
+
+            // Consolidated shipments are tracked through their container (1 DB read),
+            // whose operations must be allowed for its shipments (1 DB read per shipment)
+            Self::validate_not_consolidated(&id)?;
+            let contents = Self::container_transitions(&id, &operation)?;
+
+            // Check location against the shipment's route, if any (1 DB read),
+            // and against the own routes of the container's shipments (1 DB read per shipment)
+            let deviation = Self::route_deviation(&id, &operation, &location)?;
+            let content_deviations = contents
+                .iter()
+                .map(|(content, _)| Self::route_deviation(&content.id, &operation, &location))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // Check readings were signed by devices allowed to take them for the shipment
+            // (1 DB read, 1 DB read per reading)
+            let readings = Self::verify_readings(&shipment, readings.unwrap_or_default())?;
 
             // Update shipment status
-            shipment = match operation {
-                ShippingOperation::Pickup => shipment.pickup(),
-                ShippingOperation::Deliver => shipment.deliver(timestamp),
-                _ => shipment,
-            };
-            let status = shipment.status.clone();
+            let shipment = shipment.update_status(status.clone(), timestamp);
+
+            // Check readings against the shipment's thresholds (1 DB read)
+            let violations = Self::condition_violations(&id, &readings);
 
             // Create shipping event
             let event = Self::new_shipping_event()
                 .of_type(operation.clone().into())
                 .for_shipment(id.clone())
-                .at_location(location)
-                .with_readings(readings.unwrap_or_default())
+                .at_location(location.clone())
+                .with_readings(readings.clone())
                 .at_time(timestamp)
                 .build();
 
@@ -186,6 +359,57 @@ decl_module! {
             // Update offchain notifications (1 DB write)
             <OcwNotifications<T>>::append(<system::Module<T>>::block_number(), event_idx);
 
+            // Cascade the operation to the container's shipments
+            // (3 DB reads, 6 DB writes per shipment)
+            for ((content, content_status), content_deviation) in contents.into_iter().zip(content_deviations) {
+                let content_id = content.id.clone();
+                // Readings were signed for the container, by devices of its owner or carriers:
+                // they are only stored along with the container's event
+                let content_event = Self::new_shipping_event()
+                    .of_type(operation.clone().into())
+                    .for_shipment(content_id.clone())
+                    .at_location(location.clone())
+                    .with_readings(vec![])
+                    .at_time(timestamp)
+                    .build();
+                let content_event_idx = Self::record_event(content_event)?;
+
+                // The shipment shares its container's conditions: check the container's readings
+                // against the shipment's own thresholds (1 DB read), flagging it if need be
+                // (1 DB read, 1 DB write)
+                let content_violations = Self::condition_violations(&content_id, &readings);
+                if !content_violations.is_empty() {
+                    Self::flag_violations(&content_id, content_violations);
+                }
+
+                if let Some(point) = content_deviation {
+                    Self::deposit_event(RawEvent::ShipmentRouteDeviated(
+                        content_id.clone(),
+                        content_event_idx,
+                        point,
+                    ));
+                }
+
+                if operation != ShippingOperation::Scan {
+                    <Shipments<T>>::insert(&content_id, content.update_status(content_status.clone(), timestamp));
+                    Self::deposit_event(RawEvent::ShipmentStatusUpdated(
+                        who.clone(),
+                        content_id,
+                        content_event_idx,
+                        content_status,
+                    ));
+                }
+            }
+
+            if !violations.is_empty() {
+                // Flag the shipment (1 DB read, 1 DB write)
+                Self::flag_violations(&id, violations);
+            }
+
+            if let Some(point) = deviation {
+                Self::deposit_event(RawEvent::ShipmentRouteDeviated(id.clone(), event_idx, point));
+            }
+
             if operation != ShippingOperation::Scan {
                 // Update shipment (1 DB write)
                 <Shipments<T>>::insert(&id, shipment);
@@ -196,6 +420,378 @@ decl_module! {
             Ok(())
         }
 
+        #[weight = T::WeightInfo::set_condition_threshold()]
+        pub fn set_condition_threshold(
+            origin,
+            id: ShipmentId,
+            reading_type: ReadingType,
+            min: Option<Decimal>,
+            max: Option<Decimal>
+        ) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            // Check shipment is known & still open (1 DB read)
+            let shipment = <Shipments<T>>::get(&id).ok_or(Error::<T>::ShipmentIsUnknown)?;
+            ensure!(!shipment.status.is_closed(), Error::<T>::ShipmentIsClosed);
+
+            // Only the owner organization's members may set thresholds
+            Self::validate_shipment_owner(&shipment, &who)?;
+
+            if let (Some(min), Some(max)) = (min, max) {
+                ensure!(min <= max, Error::<T>::InvalidConditionThreshold);
+            }
+
+            // Replace the reading type's threshold, or clear it when unbounded (1 DB read, 1 DB write)
+            <ConditionThresholds>::mutate(&id, |thresholds| {
+                thresholds.retain(|t| t.reading_type != reading_type);
+                if min.is_some() || max.is_some() {
+                    thresholds.push(ConditionThreshold { reading_type: reading_type.clone(), min, max });
+                }
+            });
+
+            Self::deposit_event(RawEvent::ShipmentConditionThresholdSet(who, id, reading_type));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::set_route(corridor.len() as u32)]
+        pub fn set_route(
+            origin,
+            id: ShipmentId,
+            origin_fence: Option<Geofence>,
+            destination_fence: Option<Geofence>,
+            corridor: Vec<Geofence>
+        ) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            // Check shipment is known & still open (1 DB read)
+            let shipment = <Shipments<T>>::get(&id).ok_or(Error::<T>::ShipmentIsUnknown)?;
+            ensure!(!shipment.status.is_closed(), Error::<T>::ShipmentIsClosed);
+
+            // Only the owner organization's members may set the route
+            Self::validate_shipment_owner(&shipment, &who)?;
+
+            // Validate geofences
+            ensure!(
+                corridor.len() <= ROUTE_MAX_CORRIDOR_FENCES,
+                Error::<T>::ShipmentRouteHasTooManyFences
+            );
+            let route = ShipmentRoute {
+                origin: origin_fence,
+                destination: destination_fence,
+                corridor,
+            };
+            route.fences().try_for_each(Self::validate_geofence)?;
+
+            // Store the route, replacing any previous one (1 DB write)
+            <ShipmentRoutes>::insert(&id, route);
+
+            Self::deposit_event(RawEvent::ShipmentRouteSet(who, id));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::offer_custody()]
+        pub fn offer_custody(origin, id: ShipmentId, custodian: T::AccountId) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            // Check shipment is known & still open (1 DB read)
+            let shipment = <Shipments<T>>::get(&id).ok_or(Error::<T>::ShipmentIsUnknown)?;
+            ensure!(!shipment.status.is_closed(), Error::<T>::ShipmentIsClosed);
+
+            // Only the current custodian may hand the shipment over, to another organization,
+            // along with its container if consolidated (1 DB read)
+            Self::validate_custodian(&shipment, &who)?;
+            Self::validate_not_consolidated(&id)?;
+            ensure!(shipment.custodian != custodian, Error::<T>::ShipmentCustodyUnchanged);
+
+            // Receiving organization must be the owner or an authorized carrier (1 DB read)
+            ensure!(
+                custodian == shipment.owner || <ShipmentCarriers<T>>::get(&id).contains(&custodian),
+                Error::<T>::ShipmentCarrierNotAuthorized
+            );
+
+            // Store the offer, replacing any previous one (1 DB write)
+            <CustodyOffers<T>>::insert(&id, &custodian);
+
+            Self::deposit_event(RawEvent::ShipmentCustodyOffered(who, id, custodian));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::accept_custody(CONTAINER_MAX_SHIPMENTS as u32)]
+        pub fn accept_custody(
+            origin,
+            id: ShipmentId,
+            #[compact] timestamp: T::Moment,
+            location: Option<ReadPoint>
+        ) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            // Check shipment is known & still open (1 DB read)
+            let shipment = <Shipments<T>>::get(&id).ok_or(Error::<T>::ShipmentIsUnknown)?;
+            ensure!(!shipment.status.is_closed(), Error::<T>::ShipmentIsClosed);
+
+            // Consolidated shipments are handed over along with their container (1 DB read)
+            Self::validate_not_consolidated(&id)?;
+
+            // Check custody was offered to the sender's organization (1 DB read)
+            let custodian = <CustodyOffers<T>>::get(&id).ok_or(Error::<T>::ShipmentHasNoCustodyOffer)?;
+            ensure!(
+                T::Organizations::is_member(&custodian, &who),
+                Error::<T>::NotShipmentCustodyRecipient
+            );
+
+            // Create shipping event
+            let event = Self::new_shipping_event()
+                .of_type(ShippingEventType::CustodyTransfer)
+                .for_shipment(id.clone())
+                .at_location(location.clone())
+                .with_readings(vec![])
+                .at_time(timestamp)
+                .build();
+
+            // Storage writes
+            // --------------
+            // Store shipping event (1 DB read, 3 DB writes)
+            let event_idx = Self::store_event(event)?;
+            // Update offchain notifications (1 DB write)
+            <OcwNotifications<T>>::append(<system::Module<T>>::block_number(), event_idx);
+            // Update shipment & remove offer (2 DB writes)
+            <Shipments<T>>::insert(&id, shipment.hand_over(custodian.clone()));
+            <CustodyOffers<T>>::remove(&id);
+
+            // Container's shipments are handed over along with it
+            // (1 DB read, then 1 DB read & 5 DB writes per shipment)
+            for content_id in <ContainerShipments>::get(&id) {
+                if let Some(content) = <Shipments<T>>::get(&content_id) {
+                    let content_event = Self::new_shipping_event()
+                        .of_type(ShippingEventType::CustodyTransfer)
+                        .for_shipment(content_id.clone())
+                        .at_location(location.clone())
+                        .with_readings(vec![])
+                        .at_time(timestamp)
+                        .build();
+                    Self::record_event(content_event)?;
+                    <Shipments<T>>::insert(&content_id, content.hand_over(custodian.clone()));
+                }
+            }
+
+            Self::deposit_event(RawEvent::ShipmentCustodyTransferred(who, id, event_idx, custodian));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::consolidate(shipments.len() as u32)]
+        pub fn consolidate(origin, container: ShipmentId, shipments: Vec<ShipmentId>) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            // Check container is known & still open (1 DB read)
+            let parent = <Shipments<T>>::get(&container).ok_or(Error::<T>::ShipmentIsUnknown)?;
+            ensure!(!parent.status.is_closed(), Error::<T>::ShipmentIsClosed);
+
+            // Only members of the organization in custody of the container may load it (1 DB read)
+            Self::validate_authorized(&parent, &who)?;
+            Self::validate_custodian(&parent, &who)?;
+
+            // Containers can't be nested (1 DB read)
+            Self::validate_not_consolidated(&container)?;
+
+            // Check container has room left for the shipments (1 DB read)
+            let mut contents = <ContainerShipments>::get(&container);
+            ensure!(!shipments.is_empty(), Error::<T>::ContainerHasNoShipments);
+            ensure!(
+                contents.len() + shipments.len() <= CONTAINER_MAX_SHIPMENTS,
+                Error::<T>::ContainerHasTooManyShipments
+            );
+
+            // Validate shipments (3 DB reads per shipment)
+            for id in shipments.iter() {
+                ensure!(id != &container, Error::<T>::ShipmentIsContainer);
+                ensure!(!contents.contains(id), Error::<T>::ShipmentIsConsolidated);
+                let shipment = <Shipments<T>>::get(id).ok_or(Error::<T>::ShipmentIsUnknown)?;
+                Self::validate_not_consolidated(id)?;
+                ensure!(
+                    <ContainerShipments>::get(id).is_empty(),
+                    Error::<T>::ShipmentIsContainer
+                );
+                // Shipments move along with their container from then on
+                ensure!(shipment.custodian == parent.custodian, Error::<T>::NotShipmentCustodian);
+                ensure!(shipment.status == parent.status, Error::<T>::ShipmentStatusMismatch);
+                contents.push(id.clone());
+            }
+
+            // Storage writes
+            // --------------
+            // Update container's shipments (1 DB write, 1 DB write per shipment)
+            <ContainerShipments>::insert(&container, contents);
+            for id in shipments.iter() {
+                <ShipmentContainer>::insert(id, &container);
+            }
+            // Store aggregation events (5 DB writes, 5 DB writes per shipment)
+            Self::record_aggregation(&container, &shipments, ShippingEventType::ShipmentConsolidation)?;
+
+            Self::deposit_event(RawEvent::ShipmentsConsolidated(who, container, shipments));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::deconsolidate(CONTAINER_MAX_SHIPMENTS as u32)]
+        pub fn deconsolidate(origin, container: ShipmentId) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            // Check container is known (1 DB read)
+            let parent = <Shipments<T>>::get(&container).ok_or(Error::<T>::ShipmentIsUnknown)?;
+
+            // Only members of the organization in custody of the container may unload it (1 DB read)
+            Self::validate_authorized(&parent, &who)?;
+            Self::validate_custodian(&parent, &who)?;
+
+            // Check container has shipments (1 DB read)
+            let shipments = <ContainerShipments>::get(&container);
+            ensure!(!shipments.is_empty(), Error::<T>::ContainerHasNoShipments);
+
+            // Storage writes
+            // --------------
+            // Empty the container (1 DB write, 1 DB write per shipment)
+            <ContainerShipments>::remove(&container);
+            for id in shipments.iter() {
+                <ShipmentContainer>::remove(id);
+            }
+            // Store aggregation events (5 DB writes, 5 DB writes per shipment)
+            Self::record_aggregation(&container, &shipments, ShippingEventType::ShipmentDeconsolidation)?;
+
+            Self::deposit_event(RawEvent::ShipmentsDeconsolidated(who, container, shipments));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::authorize_carrier()]
+        pub fn authorize_carrier(origin, id: ShipmentId, carrier: T::AccountId) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            // Check shipment is known & sender acts for its owner (1 DB read)
+            let shipment = <Shipments<T>>::get(&id).ok_or(Error::<T>::ShipmentIsUnknown)?;
+            Self::validate_shipment_owner(&shipment, &who)?;
+
+            // Check carrier isn't authorized yet (1 DB read)
+            let mut carriers = <ShipmentCarriers<T>>::get(&id);
+            ensure!(
+                carrier != shipment.owner && !carriers.contains(&carrier),
+                Error::<T>::ShipmentCarrierAlreadyAuthorized
+            );
+            ensure!(
+                carriers.len() < SHIPMENT_MAX_CARRIERS,
+                Error::<T>::ShipmentHasTooManyCarriers
+            );
+            carriers.push(carrier.clone());
+
+            // Update carriers (1 DB write)
+            <ShipmentCarriers<T>>::insert(&id, carriers);
+
+            Self::deposit_event(RawEvent::ShipmentCarrierAuthorized(who, id, carrier));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::revoke_carrier()]
+        pub fn revoke_carrier(origin, id: ShipmentId, carrier: T::AccountId) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            // Check shipment is known & sender acts for its owner (1 DB read)
+            let shipment = <Shipments<T>>::get(&id).ok_or(Error::<T>::ShipmentIsUnknown)?;
+            Self::validate_shipment_owner(&shipment, &who)?;
+
+            // Carrier in custody of the shipment must hand it over first
+            ensure!(shipment.custodian != carrier, Error::<T>::ShipmentCarrierHasCustody);
+
+            // Check carrier is authorized (1 DB read)
+            let mut carriers = <ShipmentCarriers<T>>::get(&id);
+            let pos = carriers
+                .iter()
+                .position(|c| c == &carrier)
+                .ok_or(Error::<T>::ShipmentCarrierNotAuthorized)?;
+            carriers.remove(pos);
+
+            // Update carriers, withdrawing any custody offer to the carrier (1 DB read, 2 DB writes)
+            <ShipmentCarriers<T>>::insert(&id, carriers);
+            if <CustodyOffers<T>>::get(&id).as_ref() == Some(&carrier) {
+                <CustodyOffers<T>>::remove(&id);
+            }
+
+            Self::deposit_event(RawEvent::ShipmentCarrierRevoked(who, id, carrier));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::register_device()]
+        pub fn register_device(
+            origin,
+            id: DeviceId,
+            owner: T::AccountId,
+            public_key: DevicePublicKey,
+            reading_types: Vec<ReadingType>
+        ) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            // Validate format of device ID & reading types, each taken once
+            Self::validate_identifier(&id)?;
+            ensure!(
+                !reading_types.is_empty()
+                    && reading_types.len() <= DEVICE_MAX_READING_TYPES
+                    && reading_types.iter().enumerate().all(|(i, t)| !reading_types[..i].contains(t)),
+                Error::<T>::InvalidDeviceReadingTypes
+            );
+
+            // Only the owner organization's members may register its devices (1 DB read)
+            ensure!(T::Organizations::is_member(&owner, &who), Error::<T>::NotDeviceOwner);
+
+            // Check device doesn't exist yet (1 DB read)
+            ensure!(!<Devices<T>>::contains_key(&id), Error::<T>::DeviceAlreadyExists);
+
+            let device = Device {
+                id: id.clone(),
+                owner: owner.clone(),
+                public_key,
+                reading_types,
+            };
+
+            // Store device & add it to its organization's devices (2 DB writes)
+            <Devices<T>>::insert(&id, device);
+            <DevicesOfOrganization<T>>::append(&owner, &id);
+
+            Self::deposit_event(RawEvent::DeviceRegistered(who, id, owner));
+
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::deregister_device()]
+        pub fn deregister_device(origin, id: DeviceId) -> dispatch::DispatchResult {
+            T::CreateRoleOrigin::ensure_origin(origin.clone())?;
+            let who = ensure_signed(origin)?;
+
+            // Check device is known & sender acts for its owner (2 DB reads)
+            let device = <Devices<T>>::get(&id).ok_or(Error::<T>::DeviceIsUnknown)?;
+            ensure!(T::Organizations::is_member(&device.owner, &who), Error::<T>::NotDeviceOwner);
+
+            // Remove device, its further readings being rejected (1 DB read, 2 DB writes)
+            <Devices<T>>::remove(&id);
+            <DevicesOfOrganization<T>>::mutate(&device.owner, |devices| devices.retain(|d| d != &id));
+
+            Self::deposit_event(RawEvent::DeviceDeregistered(who, id));
+
+            Ok(())
+        }
+
         fn offchain_worker(block_number: T::BlockNumber) {
             // Acquiring the lock
             let mut lock = StorageLock::<Time>::with_deadline(
@@ -211,6 +807,39 @@ decl_module! {
     }
 }
 
+impl<T: Trait> OnProductRecalled for Module<T> {
+    fn affected_count(id: &ProductId) -> u32 {
+        Self::shipments_of_product(id).len() as u32
+    }
+
+    fn on_product_recalled(id: &ProductId) {
+        // Raise an event for each open shipment carrying the recalled product
+        for shipment_id in Self::shipments_of_product(id) {
+            if let Some(shipment) = Self::shipment_by_id(&shipment_id) {
+                if !shipment.status.is_closed() {
+                    Self::deposit_event(RawEvent::ShipmentAffectedByRecall(shipment_id, id.clone()));
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn add_affected(id: &ProductId, count: u32) {
+        // Open shipments carrying the product
+        for i in 0..count {
+            let mut shipment_id = b"Recall-".to_vec();
+            shipment_id.extend_from_slice(&i.to_le_bytes());
+            let shipment = Self::new_shipment()
+                .identified_by(shipment_id.clone())
+                .owned_by(Default::default())
+                .with_products(vec![id.clone()])
+                .build();
+            <Shipments<T>>::insert(&shipment_id, shipment);
+            ShipmentsOfProduct::append(id, &shipment_id);
+        }
+    }
+}
+
 impl<T: Trait> Module<T> {
     // Helper methods
     fn new_shipment() -> ShipmentBuilder<T::AccountId, T::Moment> {
@@ -221,6 +850,96 @@ impl<T: Trait> Module<T> {
         ShippingEventBuilder::<T::Moment>::default()
     }
 
+    // Stores a shipping event & notifies the off-chain worker of it (1 DB read, 4 DB writes)
+    fn record_event(event: ShippingEvent<T::Moment>) -> Result<ShippingEventIndex, Error<T>> {
+        let event_idx = Self::store_event(event)?;
+        <OcwNotifications<T>>::append(<system::Module<T>>::block_number(), event_idx);
+        Ok(event_idx)
+    }
+
+    // Records a (de)consolidation in the history of the container & of each of its shipments
+    fn record_aggregation(
+        container: &ShipmentId,
+        shipments: &[ShipmentId],
+        event_type: ShippingEventType,
+    ) -> Result<(), Error<T>> {
+        let now = <timestamp::Module<T>>::now();
+        for id in core::iter::once(container).chain(shipments.iter()) {
+            let event = Self::new_shipping_event()
+                .of_type(event_type.clone())
+                .for_shipment(id.clone())
+                .at_location(None)
+                .with_readings(vec![])
+                .at_time(now)
+                .build();
+            let event_idx = Self::record_event(event)?;
+            let children = if id == container { shipments.to_vec() } else { vec![id.clone()] };
+            <EventAggregations>::insert(event_idx, (container.clone(), children));
+        }
+        Ok(())
+    }
+
+    // Shipments of a container along with the status the operation moves them to
+    fn container_transitions(
+        container: &ShipmentId,
+        operation: &ShippingOperation,
+    ) -> Result<Vec<(Shipment<T::AccountId, T::Moment>, ShipmentStatus)>, Error<T>> {
+        <ContainerShipments>::get(container)
+            .iter()
+            .map(|id| {
+                let shipment = <Shipments<T>>::get(id).ok_or(Error::<T>::ShipmentIsUnknown)?;
+                let status = shipment
+                    .status
+                    .transition(operation)
+                    .ok_or(Error::<T>::InvalidStatusTransition)?;
+                Ok((shipment, status))
+            })
+            .collect()
+    }
+
+    fn deadline_period(moment: T::Moment) -> u64 {
+        moment.saturated_into::<u64>() / DEADLINE_PERIOD
+    }
+
+    // Raises an event for each shipment still open after its expected delivery.
+    // Periods are checked once elapsed, in order, so shipments get flagged at most
+    // one period late; checks are spread over several blocks when too many are due.
+    fn flag_overdue_shipments(now: T::Moment) {
+        let current_period = Self::deadline_period(now);
+        let mut period = match NextDeadlinePeriod::get() {
+            Some(period) => period,
+            // No shipment is due before the first check
+            None => current_period,
+        };
+
+        let mut checks = 0;
+        while period < current_period && checks < OVERDUE_MAX_CHECKS_PER_BLOCK {
+            checks += 1;
+            let mut due = <ShipmentDeadlines>::take(period);
+            let postponed = due.split_off(due.len().min(OVERDUE_MAX_CHECKS_PER_BLOCK - checks));
+
+            for id in due {
+                checks += 1;
+                if let Some(shipment) = <Shipments<T>>::get(&id) {
+                    match shipment.expected_delivery {
+                        Some(deadline) if !shipment.status.is_closed() => {
+                            Self::deposit_event(RawEvent::ShipmentOverdue(id, deadline));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            if !postponed.is_empty() {
+                <ShipmentDeadlines>::insert(period, postponed);
+                break;
+            }
+            period += 1;
+        }
+
+        NextDeadlinePeriod::put(period);
+    }
+
     fn store_event(event: ShippingEvent<T::Moment>) -> Result<ShippingEventIndex, Error<T>> {
         let event_idx = EventCount::get()
             .checked_add(1)
@@ -253,11 +972,195 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
-    pub fn validate_shipment_products(props: &[ProductId]) -> Result<(), Error<T>> {
+    pub fn validate_shipment_plan(
+        waypoints: &[ReadPoint],
+        expected_delivery: Option<T::Moment>,
+        now: T::Moment,
+    ) -> Result<(), Error<T>> {
+        ensure!(
+            waypoints.len() <= SHIPMENT_MAX_WAYPOINTS,
+            Error::<T>::ShipmentHasTooManyWaypoints
+        );
+        ensure!(
+            waypoints.iter().all(ReadPoint::is_valid),
+            Error::<T>::InvalidWaypoint
+        );
+        ensure!(
+            expected_delivery.map_or(true, |deadline| deadline > now),
+            Error::<T>::InvalidExpectedDelivery
+        );
+        Ok(())
+    }
+
+    pub fn validate_not_consolidated(id: &ShipmentId) -> Result<(), Error<T>> {
+        ensure!(
+            !<ShipmentContainer>::contains_key(id),
+            Error::<T>::ShipmentIsConsolidated
+        );
+        Ok(())
+    }
+
+    pub fn validate_shipment_owner(
+        shipment: &Shipment<T::AccountId, T::Moment>,
+        who: &T::AccountId,
+    ) -> Result<(), Error<T>> {
         ensure!(
-            props.len() <= SHIPMENT_MAX_PRODUCTS,
+            T::Organizations::is_member(&shipment.owner, who),
+            Error::<T>::NotShipmentOwner
+        );
+        Ok(())
+    }
+
+    // Readings of signed readings, provided each was signed with the key of its device,
+    // which must be allowed to take such readings & belong to the shipment's owner or carriers
+    fn verify_readings(
+        shipment: &Shipment<T::AccountId, T::Moment>,
+        readings: Vec<SignedReading<T::Moment>>,
+    ) -> Result<Vec<Reading<T::Moment>>, Error<T>> {
+        if readings.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let carriers = <ShipmentCarriers<T>>::get(&shipment.id);
+        readings
+            .into_iter()
+            .map(|SignedReading { reading, signature }| -> Result<_, Error<T>> {
+                let device = <Devices<T>>::get(&reading.device_id).ok_or(Error::<T>::DeviceIsUnknown)?;
+                ensure!(
+                    device.owner == shipment.owner || carriers.contains(&device.owner),
+                    Error::<T>::DeviceNotAuthorizedForShipment
+                );
+                ensure!(
+                    device.reading_types.contains(&reading.reading_type),
+                    Error::<T>::DeviceReadingTypeNotAllowed
+                );
+                ensure!(
+                    signature.verify(&reading.signing_payload(&shipment.id)[..], &device.public_key),
+                    Error::<T>::InvalidReadingSignature
+                );
+                Ok(reading)
+            })
+            .collect()
+    }
+
+    // Checks the location against the shipment's route, if any, returning
+    // the location when a scan deviates from the route.
+    fn route_deviation(
+        id: &ShipmentId,
+        operation: &ShippingOperation,
+        location: &Option<ReadPoint>,
+    ) -> Result<Option<ReadPoint>, Error<T>> {
+        match (<ShipmentRoutes>::get(id), location) {
+            (Some(route), Some(point)) => {
+                ensure!(
+                    *operation != ShippingOperation::Deliver || route.is_at_destination(point),
+                    Error::<T>::ShipmentIsOutsideDestination
+                );
+                if *operation == ShippingOperation::Scan && !route.is_on_route(point) {
+                    Ok(Some(point.clone()))
+                } else {
+                    Ok(None)
+                }
+            }
+            (Some(route), None) => {
+                ensure!(
+                    *operation != ShippingOperation::Deliver || route.destination.is_none(),
+                    Error::<T>::ShipmentLocationIsMissing
+                );
+                Ok(None)
+            }
+            (None, _) => Ok(None),
+        }
+    }
+
+    // Readings going past the shipment's threshold for their type
+    fn condition_violations(id: &ShipmentId, readings: &[Reading<T::Moment>]) -> Vec<Reading<T::Moment>> {
+        let thresholds = <ConditionThresholds>::get(id);
+        if thresholds.is_empty() {
+            return vec![];
+        }
+
+        readings
+            .iter()
+            .filter(|reading| {
+                thresholds.iter().any(|t| {
+                    t.reading_type == reading.reading_type && t.is_violated_by(reading.value)
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn flag_violations(id: &ShipmentId, violations: Vec<Reading<T::Moment>>) {
+        <ConditionViolations>::mutate(id, |flagged| {
+            for reading in violations {
+                if !flagged.contains(&reading.reading_type) {
+                    flagged.push(reading.reading_type.clone());
+                }
+                Self::deposit_event(RawEvent::ShipmentConditionViolated(
+                    id.clone(),
+                    reading.reading_type,
+                    reading.value,
+                    reading.device_id,
+                ));
+            }
+        });
+    }
+
+    pub fn validate_geofence(fence: &Geofence) -> Result<(), Error<T>> {
+        let valid = match fence {
+            Geofence::Circle(center, radius) => center.is_valid() && *radius > 0,
+            Geofence::Polygon(vertices) => {
+                vertices.len() >= 3
+                    && vertices.len() <= GEOFENCE_MAX_VERTICES
+                    && vertices.iter().all(ReadPoint::is_valid)
+            }
+        };
+        ensure!(valid, Error::<T>::InvalidGeofence);
+        Ok(())
+    }
+
+    pub fn validate_authorized(
+        shipment: &Shipment<T::AccountId, T::Moment>,
+        who: &T::AccountId,
+    ) -> Result<(), Error<T>> {
+        let authorized = T::Organizations::is_member(&shipment.owner, who)
+            || <ShipmentCarriers<T>>::get(&shipment.id)
+                .iter()
+                .any(|carrier| T::Organizations::is_member(carrier, who));
+        ensure!(authorized, Error::<T>::NotAuthorizedForShipment);
+        Ok(())
+    }
+
+    pub fn validate_custodian(
+        shipment: &Shipment<T::AccountId, T::Moment>,
+        who: &T::AccountId,
+    ) -> Result<(), Error<T>> {
+        ensure!(
+            T::Organizations::is_member(&shipment.custodian, who),
+            Error::<T>::NotShipmentCustodian
+        );
+        Ok(())
+    }
+
+    pub fn validate_shipment_products(products: &[ProductId], lots: &[LotId]) -> Result<(), Error<T>> {
+        ensure!(
+            products.len() + lots.len() <= SHIPMENT_MAX_PRODUCTS,
             Error::<T>::ShipmentHasTooManyProducts,
         );
+        // Lots must have been registered for their product
+        for (product_id, lot_number) in lots {
+            ensure!(
+                T::Products::lot_exists(product_id, lot_number),
+                Error::<T>::ShipmentHasUnknownLot,
+            );
+        }
+        // Products must have been registered, and be active to be shipped
+        let product_ids = products.iter().chain(lots.iter().map(|(product_id, _)| product_id));
+        for id in product_ids {
+            let status = T::Products::status_of(id).ok_or(Error::<T>::ShipmentHasUnknownProduct)?;
+            ensure!(status == ProductStatus::Active, Error::<T>::ShipmentHasInactiveProduct);
+        }
         Ok(())
     }
 
@@ -0,0 +1,101 @@
+// Storage migrations of the product tracking pallet, run on runtime upgrades.
+//
+// Each version of the storage (see Releases) has its own shipment layout: `migrate`
+// decodes the existing shipments with the layout of the version currently in
+// storage, and upgrades them to the current one, so that chains can skip
+// runtime versions.
+
+use super::*;
+use codec::Decode;
+use core::cell::Cell;
+use frame_support::storage::{IterableStorageMap, StorageValue};
+
+// Shipment as stored before custodians & lots (storage V1)
+#[derive(Decode)]
+struct ShipmentV1<AccountId, Moment> {
+    id: ShipmentId,
+    owner: AccountId,
+    status: ShipmentStatus,
+    products: Vec<ProductId>,
+    registered: Moment,
+    delivered: Option<Moment>,
+}
+
+// Existing shipments are in the custody of their owner, and have no lots
+impl<AccountId: Clone, Moment> From<ShipmentV1<AccountId, Moment>> for Shipment<AccountId, Moment> {
+    fn from(shipment: ShipmentV1<AccountId, Moment>) -> Self {
+        Shipment {
+            id: shipment.id,
+            custodian: shipment.owner.clone(),
+            owner: shipment.owner,
+            status: shipment.status,
+            products: shipment.products,
+            lots: vec![],
+            registered: shipment.registered,
+            delivered: shipment.delivered,
+            waypoints: vec![],
+            expected_delivery: None,
+        }
+    }
+}
+
+// Shipment as stored before planned routes (storage V2)
+#[derive(Decode)]
+struct ShipmentV2<AccountId, Moment> {
+    id: ShipmentId,
+    owner: AccountId,
+    custodian: AccountId,
+    status: ShipmentStatus,
+    products: Vec<ProductId>,
+    lots: Vec<LotId>,
+    registered: Moment,
+    delivered: Option<Moment>,
+}
+
+// Existing shipments have no planned route nor expected delivery
+impl<AccountId, Moment> From<ShipmentV2<AccountId, Moment>> for Shipment<AccountId, Moment> {
+    fn from(shipment: ShipmentV2<AccountId, Moment>) -> Self {
+        Shipment {
+            id: shipment.id,
+            owner: shipment.owner,
+            custodian: shipment.custodian,
+            status: shipment.status,
+            products: shipment.products,
+            lots: shipment.lots,
+            registered: shipment.registered,
+            delivered: shipment.delivered,
+            waypoints: vec![],
+            expected_delivery: None,
+        }
+    }
+}
+
+pub fn migrate<T: Trait>() -> Weight {
+    // Storage version (1 DB read)
+    let weight = T::DbWeight::get().reads(1);
+
+    match StorageVersion::get() {
+        Releases::V1 => weight.saturating_add(migrate_shipments::<T, ShipmentV1<T::AccountId, T::Moment>>()),
+        Releases::V2 => weight.saturating_add(migrate_shipments::<T, ShipmentV2<T::AccountId, T::Moment>>()),
+        Releases::V3 => weight,
+    }
+}
+
+// Existing shipments, stored with the given layout, are upgraded to the current one
+fn migrate_shipments<T, S>() -> Weight
+where
+    T: Trait,
+    S: Decode + Into<Shipment<T::AccountId, T::Moment>>,
+{
+    let count = Cell::new(0 as Weight);
+    <Shipments<T>>::translate::<S, _>(|_, shipment| {
+        count.set(count.get() + 1);
+        Some(shipment.into())
+    });
+
+    StorageVersion::put(Releases::V3);
+
+    // Each migrated shipment is read & written, as well as the storage version
+    let count = count.get();
+    T::DbWeight::get().reads_writes(count, count.saturating_add(1))
+}
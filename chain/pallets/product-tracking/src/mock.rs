@@ -2,6 +2,8 @@
 
 use crate::{Call, Module, Trait};
 use core::marker::PhantomData;
+use product_registry::{Organizations, ProductInfo, ProductStatus};
+use std::cell::RefCell;
 use frame_support::{
     impl_outer_event, impl_outer_origin, parameter_types, traits::EnsureOrigin, weights::Weight,
 };
@@ -13,6 +15,8 @@ use sp_core::{
     //     OffchainExt, TransactionPoolExt,
     // },
     sr25519,
+    testing::KeyStore,
+    traits::KeystoreExt,
     Pair,
     H256,
 };
@@ -86,6 +90,9 @@ impl timestamp::Trait for Test {
 impl Trait for Test {
     type Event = TestEvent;
     type CreateRoleOrigin = MockOrigin<Test>;
+    type Products = MockProducts;
+    type Organizations = MockOrganizations;
+    type WeightInfo = ();
 }
 
 pub type ProductTracking = Module<Test>;
@@ -102,6 +109,79 @@ impl<T: Trait> EnsureOrigin<T::Origin> for MockOrigin<T> {
             r => Err(T::Origin::from(r)),
         })
     }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn successful_origin() -> T::Origin {
+        T::Origin::from(RawOrigin::Signed(Default::default()))
+    }
+}
+
+thread_local! {
+    static PRODUCT_STATUSES: RefCell<Vec<(Vec<u8>, ProductStatus)>> = RefCell::new(vec![]);
+    static PRODUCT_LOTS: RefCell<Vec<(Vec<u8>, Vec<u8>)>> = RefCell::new(vec![]);
+    static ORG_MEMBERS: RefCell<Vec<(sr25519::Public, sr25519::Public)>> = RefCell::new(vec![]);
+}
+
+pub struct MockProducts;
+
+impl ProductInfo for MockProducts {
+    fn status_of(id: &[u8]) -> Option<ProductStatus> {
+        PRODUCT_STATUSES.with(|p| {
+            p.borrow()
+                .iter()
+                .rev()
+                .find(|(pid, _)| pid.as_slice() == id)
+                .map(|(_, status)| status.clone())
+        })
+    }
+
+    fn lot_exists(id: &[u8], lot_number: &[u8]) -> bool {
+        PRODUCT_LOTS.with(|l| {
+            l.borrow()
+                .iter()
+                .any(|(pid, lot)| pid.as_slice() == id && lot.as_slice() == lot_number)
+        })
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn add_product(id: &[u8]) {
+        set_product_status(id, ProductStatus::Active);
+    }
+}
+
+pub fn set_product_status(id: &[u8], status: ProductStatus) {
+    PRODUCT_STATUSES.with(|p| p.borrow_mut().push((id.to_vec(), status)));
+}
+
+pub fn add_product_lot(id: &[u8], lot_number: &[u8]) {
+    PRODUCT_LOTS.with(|l| l.borrow_mut().push((id.to_vec(), lot_number.to_vec())));
+}
+
+pub struct MockOrganizations;
+
+impl Organizations<sr25519::Public> for MockOrganizations {
+    fn is_member(org: &sr25519::Public, account: &sr25519::Public) -> bool {
+        org == account || ORG_MEMBERS.with(|m| m.borrow().contains(&(*org, *account)))
+    }
+
+    fn is_organization(_account: &sr25519::Public) -> bool {
+        true
+    }
+
+    fn company_prefix(_org: &sr25519::Public) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn company_prefix_holder(_prefix: &[u8]) -> Option<sr25519::Public> {
+        None
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn add_organization(_account: &sr25519::Public) {}
+}
+
+pub fn add_org_member(org: sr25519::Public, account: sr25519::Public) {
+    ORG_MEMBERS.with(|m| m.borrow_mut().push((org, account)));
 }
 
 // This function basically just builds a genesis storage key/value store according to
@@ -111,6 +191,8 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
         .build_storage::<Test>()
         .unwrap();
     let mut ext = sp_io::TestExternalities::from(storage);
+    // Devices' keys are generated & used through the keystore when benchmarking
+    ext.register_extension(KeystoreExt(KeyStore::new()));
     // Events are not emitted on block 0 -> advance to block 1.
     // Any dispatchable calls made during genesis block will have no events emitted.
     ext.execute_with(|| System::set_block_number(1));
@@ -1,7 +1,13 @@
 use super::*;
 use crate::{mock::*, types::*, Error};
+use codec::{Decode, Encode};
 use fixed::types::I16F16;
-use frame_support::{assert_noop, assert_ok, dispatch};
+use sp_core::{sr25519, Pair};
+use frame_support::{
+    assert_noop, assert_ok, dispatch,
+    storage::unhashed,
+    traits::{OnFinalize, OnRuntimeUpgrade},
+};
 
 pub fn store_test_shipment<T: Trait>(
     id: ShipmentId,
@@ -14,11 +20,15 @@ pub fn store_test_shipment<T: Trait>(
         id.clone(),
         Shipment {
             id,
+            custodian: owner.clone(),
             owner,
             status,
             products,
+            lots: vec![],
             registered,
             delivered: None,
+            waypoints: vec![],
+            expected_delivery: None,
         },
     );
 }
@@ -37,9 +47,40 @@ pub fn store_test_event<T: Trait>(shipment_id: ShipmentId, event_type: ShippingE
     EventsOfShipment::append(shipment_id, event_idx);
 }
 
+pub fn store_test_carrier<T: Trait>(shipment_id: ShipmentId, carrier: T::AccountId) {
+    ShipmentCarriers::<T>::append(shipment_id, carrier);
+}
+
+pub fn store_test_device(id: &str, owner: sr25519::Public, reading_types: Vec<ReadingType>) {
+    let device = Device {
+        id: id.as_bytes().to_owned(),
+        owner,
+        public_key: device_key(id).public(),
+        reading_types,
+    };
+    Devices::<Test>::insert(id.as_bytes(), device);
+}
+
+fn device_key(id: &str) -> sr25519::Pair {
+    sr25519::Pair::from_string(&format!("//{}", id), None).expect("static values are valid; qed")
+}
+
+// Readings signed with the keys of their devices
+fn sign_readings(shipment_id: &ShipmentId, readings: &[Reading<u64>]) -> Vec<SignedReading<u64>> {
+    readings
+        .iter()
+        .map(|reading| SignedReading {
+            reading: reading.clone(),
+            signature: device_key(core::str::from_utf8(&reading.device_id).unwrap())
+                .sign(&reading.signing_payload(shipment_id)),
+        })
+        .collect()
+}
+
 const TEST_PRODUCT_ID: &str = "00012345678905";
 const TEST_SHIPMENT_ID: &str = "0001";
 const TEST_ORGANIZATION: &str = "Northwind";
+const TEST_CARRIER: &str = "Fabrikam";
 const TEST_SENDER: &str = "Alice";
 const LONG_VALUE : &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. Donec aliquam ut tortor nec congue. Pellente";
 
@@ -57,6 +98,9 @@ fn register_shipment_without_products() {
             id.clone(),
             owner.clone(),
             vec![],
+            vec![],
+            vec![],
+            None,
         );
 
         assert_ok!(result);
@@ -66,10 +110,14 @@ fn register_shipment_without_products() {
             Some(Shipment {
                 id: id.clone(),
                 owner: owner,
+                custodian: owner,
                 status: ShipmentStatus::Pending,
                 products: vec![],
+                lots: vec![],
                 registered: now,
-                delivered: None
+                delivered: None,
+                waypoints: vec![],
+                expected_delivery: None
             })
         );
 
@@ -103,6 +151,9 @@ fn register_shipment_with_valid_products() {
         let owner = account_key(TEST_ORGANIZATION);
         let now = 42;
         Timestamp::set_timestamp(now);
+        set_product_status(b"00012345600001", ProductStatus::Active);
+        set_product_status(b"00012345600002", ProductStatus::Active);
+        set_product_status(b"00012345600003", ProductStatus::Active);
 
         let result = ProductTracking::register_shipment(
             Origin::signed(sender),
@@ -113,6 +164,9 @@ fn register_shipment_with_valid_products() {
                 b"00012345600002".to_vec(),
                 b"00012345600003".to_vec(),
             ],
+            vec![],
+            vec![],
+            None,
         );
 
         assert_ok!(result);
@@ -122,14 +176,18 @@ fn register_shipment_with_valid_products() {
             Some(Shipment {
                 id: id.clone(),
                 owner: owner,
+                custodian: owner,
                 status: ShipmentStatus::Pending,
                 products: vec![
                     b"00012345600001".to_vec(),
                     b"00012345600002".to_vec(),
                     b"00012345600003".to_vec(),
                 ],
+                lots: vec![],
                 registered: now,
-                delivered: None
+                delivered: None,
+                waypoints: vec![],
+                expected_delivery: None
             })
         );
 
@@ -138,6 +196,11 @@ fn register_shipment_with_valid_products() {
             vec![id.clone()]
         );
 
+        assert_eq!(
+            ProductTracking::shipments_of_product(b"00012345600001".to_vec()),
+            vec![id.clone()]
+        );
+
         assert!(System::events().iter().any(|er| er.event
             == TestEvent::product_tracking(RawEvent::ShipmentRegistered(
                 sender,
@@ -163,7 +226,10 @@ fn register_shipment_with_invalid_sender() {
                 Origin::none(),
                 TEST_SHIPMENT_ID.as_bytes().to_owned(),
                 account_key(TEST_ORGANIZATION),
-                vec!()
+                vec!(),
+                vec!(),
+                vec![],
+                None
             ),
             dispatch::DispatchError::BadOrigin
         );
@@ -178,7 +244,10 @@ fn register_shipment_with_missing_id() {
                 Origin::signed(account_key(TEST_SENDER)),
                 vec!(),
                 account_key(TEST_ORGANIZATION),
-                vec!()
+                vec!(),
+                vec!(),
+                vec![],
+                None
             ),
             Error::<Test>::InvalidOrMissingIdentifier
         );
@@ -193,7 +262,10 @@ fn register_shipment_with_long_id() {
                 Origin::signed(account_key(TEST_SENDER)),
                 LONG_VALUE.as_bytes().to_owned(),
                 account_key(TEST_ORGANIZATION),
-                vec!()
+                vec!(),
+                vec!(),
+                vec![],
+                None
             ),
             Error::<Test>::InvalidOrMissingIdentifier
         );
@@ -219,7 +291,10 @@ fn register_shipment_with_existing_id() {
                 Origin::signed(account_key(TEST_SENDER)),
                 existing_shipment,
                 account_key(TEST_ORGANIZATION),
-                vec![]
+                vec![],
+                vec![],
+                vec![],
+                None
             ),
             Error::<Test>::ShipmentAlreadyExists
         );
@@ -246,13 +321,111 @@ fn register_shipment_with_too_many_products() {
                     b"00012345600009".to_vec(),
                     b"00012345600010".to_vec(),
                     b"00012345600011".to_vec(),
-                ]
+                ],
+                vec![],
+                vec![],
+                None
             ),
             Error::<Test>::ShipmentHasTooManyProducts
         );
     })
 }
 
+#[test]
+fn register_shipment_with_inactive_product() {
+    new_test_ext().execute_with(|| {
+        set_product_status(b"00012345600001", ProductStatus::Active);
+        set_product_status(b"00012345600002", ProductStatus::Recalled);
+
+        assert_noop!(
+            ProductTracking::register_shipment(
+                Origin::signed(account_key(TEST_SENDER)),
+                TEST_SHIPMENT_ID.as_bytes().to_owned(),
+                account_key(TEST_ORGANIZATION),
+                vec![
+                    b"00012345600001".to_vec(),
+                    b"00012345600002".to_vec(),
+                ],
+                vec![],
+                vec![],
+                None
+            ),
+            Error::<Test>::ShipmentHasInactiveProduct
+        );
+    })
+}
+
+#[test]
+fn register_shipment_with_unknown_product() {
+    new_test_ext().execute_with(|| {
+        set_product_status(b"00012345600001", ProductStatus::Active);
+
+        // Products must have been registered to be shipped
+        assert_noop!(
+            ProductTracking::register_shipment(
+                Origin::signed(account_key(TEST_SENDER)),
+                TEST_SHIPMENT_ID.as_bytes().to_owned(),
+                account_key(TEST_ORGANIZATION),
+                vec![
+                    b"00012345600001".to_vec(),
+                    b"00012345600002".to_vec(),
+                ],
+                vec![],
+                vec![],
+                None
+            ),
+            Error::<Test>::ShipmentHasUnknownProduct
+        );
+    })
+}
+
+#[test]
+fn register_shipment_with_lots() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+        let lot = (b"00012345600002".to_vec(), b"L42".to_vec());
+        set_product_status(b"00012345600001", ProductStatus::Active);
+        set_product_status(&lot.0, ProductStatus::Active);
+        add_product_lot(&lot.0, &lot.1);
+
+        assert_ok!(ProductTracking::register_shipment(
+            Origin::signed(account_key(TEST_SENDER)),
+            id.clone(),
+            owner,
+            vec![b"00012345600001".to_vec()],
+            vec![lot.clone()],
+            vec![],
+            None,
+        ));
+
+        assert_eq!(
+            ProductTracking::shipment_by_id(&id).map(|s| s.lots),
+            Some(vec![lot.clone()])
+        );
+        // Shipment is indexed by the product of the lot
+        assert_eq!(ProductTracking::shipments_of_product(&lot.0), vec![id.clone()]);
+    })
+}
+
+#[test]
+fn register_shipment_with_unknown_lot() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            ProductTracking::register_shipment(
+                Origin::signed(account_key(TEST_SENDER)),
+                TEST_SHIPMENT_ID.as_bytes().to_owned(),
+                account_key(TEST_ORGANIZATION),
+                vec![],
+                vec![(b"00012345600002".to_vec(), b"L42".to_vec())],
+                vec![],
+                None
+            ),
+            Error::<Test>::ShipmentHasUnknownLot
+        );
+    })
+}
+
 #[test]
 fn track_shipment_with_invalid_sender() {
     new_test_ext().execute_with(|| {
@@ -379,10 +552,14 @@ fn track_shipment_pickup() {
             Some(Shipment {
                 id: shipment_id.clone(),
                 owner: owner,
+                custodian: owner,
                 status: ShipmentStatus::InTransit,
                 products: vec![TEST_PRODUCT_ID.as_bytes().to_owned()],
+                lots: vec![],
                 registered: now,
-                delivered: None
+                delivered: None,
+                waypoints: vec![],
+                expected_delivery: None
             })
         );
 
@@ -449,10 +626,14 @@ fn track_shipment_delivery() {
             Some(Shipment {
                 id: shipment_id.clone(),
                 owner: owner,
+                custodian: owner,
                 status: ShipmentStatus::Delivered,
                 products: vec![TEST_PRODUCT_ID.as_bytes().to_owned()],
+                lots: vec![],
                 registered: now,
-                delivered: Some(now)
+                delivered: Some(now),
+                waypoints: vec![],
+                expected_delivery: None
             })
         );
 
@@ -492,7 +673,7 @@ fn track_shipment_for_delivered_shipment() {
                 None,
                 None
             ),
-            Error::<Test>::ShipmentHasBeenDelivered
+            Error::<Test>::InvalidStatusTransition
         );
     })
 }
@@ -522,8 +703,193 @@ fn track_shipment_for_intransit_shipment() {
                 None,
                 None
             ),
-            Error::<Test>::ShipmentIsInTransit
+            Error::<Test>::InvalidStatusTransition
+        );
+    })
+}
+
+#[test]
+fn track_shipment_status_transitions() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        let now = 42;
+
+        let statuses = vec![
+            ShipmentStatus::Pending,
+            ShipmentStatus::InTransit,
+            ShipmentStatus::Delivered,
+            ShipmentStatus::Cancelled,
+            ShipmentStatus::OnHold,
+            ShipmentStatus::Returned,
+            ShipmentStatus::Lost,
+        ];
+        let operations = vec![
+            ShippingOperation::Pickup,
+            ShippingOperation::Scan,
+            ShippingOperation::Deliver,
+            ShippingOperation::Cancel,
+            ShippingOperation::Hold,
+            ShippingOperation::Resume,
+            ShippingOperation::Return,
+            ShippingOperation::ReportLoss,
+        ];
+        let allowed = vec![
+            (ShipmentStatus::Pending, ShippingOperation::Pickup, ShipmentStatus::InTransit),
+            (ShipmentStatus::Pending, ShippingOperation::Scan, ShipmentStatus::Pending),
+            (ShipmentStatus::Pending, ShippingOperation::Cancel, ShipmentStatus::Cancelled),
+            (ShipmentStatus::InTransit, ShippingOperation::Scan, ShipmentStatus::InTransit),
+            (ShipmentStatus::InTransit, ShippingOperation::Deliver, ShipmentStatus::Delivered),
+            (ShipmentStatus::InTransit, ShippingOperation::Hold, ShipmentStatus::OnHold),
+            (ShipmentStatus::InTransit, ShippingOperation::Return, ShipmentStatus::Returned),
+            (ShipmentStatus::InTransit, ShippingOperation::ReportLoss, ShipmentStatus::Lost),
+            (ShipmentStatus::OnHold, ShippingOperation::Scan, ShipmentStatus::OnHold),
+            (ShipmentStatus::OnHold, ShippingOperation::Resume, ShipmentStatus::InTransit),
+            (ShipmentStatus::OnHold, ShippingOperation::Cancel, ShipmentStatus::Cancelled),
+            (ShipmentStatus::OnHold, ShippingOperation::Return, ShipmentStatus::Returned),
+            (ShipmentStatus::OnHold, ShippingOperation::ReportLoss, ShipmentStatus::Lost),
+        ];
+
+        for status in statuses.iter() {
+            for operation in operations.iter() {
+                store_test_shipment::<Test>(shipment_id.clone(), owner, status.clone(), vec![], now);
+
+                let result = ProductTracking::track_shipment(
+                    Origin::signed(owner),
+                    shipment_id.clone(),
+                    operation.clone(),
+                    now,
+                    None,
+                    None,
+                );
+
+                match allowed.iter().find(|(s, o, _)| s == status && o == operation) {
+                    Some((_, _, next)) => {
+                        assert_ok!(result);
+                        assert_eq!(
+                            ProductTracking::shipment_by_id(&shipment_id).map(|s| s.status),
+                            Some(next.clone())
+                        );
+                    }
+                    None => assert_eq!(
+                        result,
+                        Err(Error::<Test>::InvalidStatusTransition.into()),
+                        "{:?} shouldn't be allowed on a {:?} shipment",
+                        operation,
+                        status
+                    ),
+                }
+            }
+        }
+    })
+}
+
+#[test]
+fn track_shipment_hold_and_resume() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        let now = 42;
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::InTransit, vec![], now);
+
+        assert_ok!(ProductTracking::track_shipment(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            ShippingOperation::Hold,
+            now,
+            None,
+            None
+        ));
+
+        // Shipment cannot be delivered while on hold
+        assert_noop!(
+            ProductTracking::track_shipment(
+                Origin::signed(owner),
+                shipment_id.clone(),
+                ShippingOperation::Deliver,
+                now,
+                None,
+                None
+            ),
+            Error::<Test>::InvalidStatusTransition
+        );
+
+        assert_ok!(ProductTracking::track_shipment(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            ShippingOperation::Resume,
+            now + 1,
+            None,
+            None
+        ));
+
+        // Shipping events are recorded & status updates raised
+        assert_eq!(
+            ProductTracking::events_of_shipment(&shipment_id)
+                .into_iter()
+                .filter_map(|idx| ProductTracking::event_by_idx(idx).map(|e| e.event_type))
+                .collect::<Vec<_>>(),
+            vec![ShippingEventType::ShipmentHold, ShippingEventType::ShipmentResume]
+        );
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_tracking(RawEvent::ShipmentStatusUpdated(
+                owner,
+                shipment_id.clone(),
+                1,
+                ShipmentStatus::OnHold
+            ))));
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_tracking(RawEvent::ShipmentStatusUpdated(
+                owner,
+                shipment_id.clone(),
+                2,
+                ShipmentStatus::InTransit
+            ))));
+    })
+}
+
+#[test]
+fn cancelled_shipment_is_closed() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let carrier = account_key(TEST_CARRIER);
+        let product_id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        let now = 42;
+        store_test_shipment::<Test>(
+            shipment_id.clone(),
+            owner,
+            ShipmentStatus::Pending,
+            vec![product_id.clone()],
+            now,
+        );
+        ShipmentsOfProduct::append(&product_id, &shipment_id);
+        store_test_carrier::<Test>(shipment_id.clone(), carrier);
+
+        assert_ok!(ProductTracking::track_shipment(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            ShippingOperation::Cancel,
+            now,
+            None,
+            None
+        ));
+
+        // Shipment is neither delivered nor can be handed over anymore
+        let shipment = ProductTracking::shipment_by_id(&shipment_id).unwrap();
+        assert_eq!(shipment.status, ShipmentStatus::Cancelled);
+        assert_eq!(shipment.delivered, None);
+        assert_noop!(
+            ProductTracking::offer_custody(Origin::signed(owner), shipment_id, carrier),
+            Error::<Test>::ShipmentIsClosed
         );
+
+        // ...nor is it affected by recalls
+        <ProductTracking as OnProductRecalled>::on_product_recalled(&product_id);
+        assert!(!System::events().iter().any(|er| matches!(
+            er.event,
+            TestEvent::product_tracking(RawEvent::ShipmentAffectedByRecall(..))
+        )));
     })
 }
 
@@ -553,6 +919,7 @@ fn monitor_shipment() {
             longitude: I16F16::from_num(13.4355606),
         };
 
+        store_test_device("14d453ea4bdf46bc8042", owner, vec![ReadingType::Temperature]);
         let readings = vec![Reading {
             device_id: "14d453ea4bdf46bc8042".as_bytes().to_owned(),
             reading_type: ReadingType::Temperature,
@@ -567,7 +934,7 @@ fn monitor_shipment() {
             ShippingOperation::Scan,
             now,
             Some(location.clone()),
-            Some(readings.clone())
+            Some(sign_readings(&shipment_id, &readings))
         ));
 
         // Storage is correctly updated
@@ -590,10 +957,14 @@ fn monitor_shipment() {
             Some(Shipment {
                 id: shipment_id.clone(),
                 owner: owner,
+                custodian: owner,
                 status: ShipmentStatus::InTransit,
                 products: vec![TEST_PRODUCT_ID.as_bytes().to_owned()],
+                lots: vec![],
                 registered: now,
-                delivered: None
+                delivered: None,
+                waypoints: vec![],
+                expected_delivery: None
             })
         );
     })
@@ -626,6 +997,7 @@ fn monitor_shipment_with_negative_latlon() {
             longitude: I16F16::from_num(-43.233472),
         };
 
+        store_test_device("14d453ea4bdf46bc8042", owner, vec![ReadingType::Temperature]);
         let readings = vec![Reading {
             device_id: "14d453ea4bdf46bc8042".as_bytes().to_owned(),
             reading_type: ReadingType::Temperature,
@@ -640,7 +1012,7 @@ fn monitor_shipment_with_negative_latlon() {
             ShippingOperation::Scan,
             now,
             Some(location.clone()),
-            Some(readings.clone())
+            Some(sign_readings(&shipment_id, &readings))
         ));
 
         // Storage is correctly updated
@@ -663,11 +1035,2074 @@ fn monitor_shipment_with_negative_latlon() {
             Some(Shipment {
                 id: shipment_id.clone(),
                 owner: owner,
+                custodian: owner,
                 status: ShipmentStatus::InTransit,
                 products: vec![TEST_PRODUCT_ID.as_bytes().to_owned()],
+                lots: vec![],
                 registered: now,
-                delivered: None
+                delivered: None,
+                waypoints: vec![],
+                expected_delivery: None
+            })
+        );
+    })
+}
+
+#[test]
+fn recall_product_affects_open_shipments() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let product_id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        let now = 42;
+        Timestamp::set_timestamp(now);
+        set_product_status(&product_id, ProductStatus::Active);
+
+        // Register an open & a delivered shipment carrying the product
+        assert_ok!(ProductTracking::register_shipment(
+            Origin::signed(owner),
+            b"0001".to_vec(),
+            owner,
+            vec![product_id.clone()],
+            vec![],
+            vec![],
+            None,
+        ));
+        assert_ok!(ProductTracking::register_shipment(
+            Origin::signed(owner),
+            b"0002".to_vec(),
+            owner,
+            vec![product_id.clone()],
+            vec![],
+            vec![],
+            None,
+        ));
+        assert_ok!(ProductTracking::track_shipment(
+            Origin::signed(owner),
+            b"0002".to_vec(),
+            ShippingOperation::Pickup,
+            now,
+            None,
+            None
+        ));
+        assert_ok!(ProductTracking::track_shipment(
+            Origin::signed(owner),
+            b"0002".to_vec(),
+            ShippingOperation::Deliver,
+            now,
+            None,
+            None
+        ));
+
+        <ProductTracking as OnProductRecalled>::on_product_recalled(&product_id);
+
+        // Event is raised for the open shipment only
+        let affected: Vec<_> = System::events()
+            .into_iter()
+            .filter_map(|er| match er.event {
+                TestEvent::product_tracking(RawEvent::ShipmentAffectedByRecall(id, _)) => Some(id),
+                _ => None,
             })
+            .collect();
+        assert_eq!(affected, vec![b"0001".to_vec()]);
+    })
+}
+
+#[test]
+fn offer_shipment_custody() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let sender = account_key(TEST_SENDER);
+        let carrier = account_key(TEST_CARRIER);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        add_org_member(owner, sender);
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::Pending, vec![], 42);
+        store_test_carrier::<Test>(shipment_id.clone(), carrier);
+
+        assert_ok!(ProductTracking::offer_custody(
+            Origin::signed(sender),
+            shipment_id.clone(),
+            carrier
+        ));
+
+        assert_eq!(ProductTracking::custody_offer_of(&shipment_id), Some(carrier));
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_tracking(RawEvent::ShipmentCustodyOffered(
+                sender,
+                shipment_id.clone(),
+                carrier
+            ))));
+
+        // Custody can only be offered to another organization
+        assert_noop!(
+            ProductTracking::offer_custody(Origin::signed(sender), shipment_id, owner),
+            Error::<Test>::ShipmentCustodyUnchanged
+        );
+    })
+}
+
+#[test]
+fn offer_shipment_custody_by_non_custodian() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let carrier = account_key(TEST_CARRIER);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::Pending, vec![], 42);
+
+        assert_noop!(
+            ProductTracking::offer_custody(Origin::signed(carrier), shipment_id.clone(), carrier),
+            Error::<Test>::NotShipmentCustodian
+        );
+        assert_noop!(
+            ProductTracking::offer_custody(Origin::signed(owner), b"0002".to_vec(), carrier),
+            Error::<Test>::ShipmentIsUnknown
+        );
+
+        // Custody can only be offered to authorized carriers
+        assert_noop!(
+            ProductTracking::offer_custody(Origin::signed(owner), shipment_id, carrier),
+            Error::<Test>::ShipmentCarrierNotAuthorized
+        );
+    })
+}
+
+#[test]
+fn accept_shipment_custody() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let carrier = account_key(TEST_CARRIER);
+        let driver = account_key(TEST_SENDER);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        let now = 42;
+        add_org_member(carrier, driver);
+        store_test_shipment::<Test>(
+            shipment_id.clone(),
+            owner,
+            ShipmentStatus::Pending,
+            vec![TEST_PRODUCT_ID.as_bytes().to_owned()],
+            now,
+        );
+        store_test_event::<Test>(shipment_id.clone(), ShippingEventType::ShipmentRegistration);
+        store_test_carrier::<Test>(shipment_id.clone(), carrier);
+
+        // Custody must have been offered first
+        assert_noop!(
+            ProductTracking::accept_custody(Origin::signed(driver), shipment_id.clone(), now, None),
+            Error::<Test>::ShipmentHasNoCustodyOffer
+        );
+        assert_ok!(ProductTracking::offer_custody(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            carrier
+        ));
+
+        // ...to the accepting organization
+        assert_noop!(
+            ProductTracking::accept_custody(
+                Origin::signed(account_key("Contoso")),
+                shipment_id.clone(),
+                now,
+                None
+            ),
+            Error::<Test>::NotShipmentCustodyRecipient
+        );
+
+        assert_ok!(ProductTracking::accept_custody(
+            Origin::signed(driver),
+            shipment_id.clone(),
+            now,
+            None
+        ));
+
+        // Handoff is recorded as a shipping event
+        assert_eq!(EventCount::get(), 2);
+        assert_eq!(
+            AllEvents::<Test>::get(2),
+            Some(ShippingEvent {
+                event_type: ShippingEventType::CustodyTransfer,
+                shipment_id: shipment_id.clone(),
+                location: None,
+                readings: vec![],
+                timestamp: now,
+            })
+        );
+        assert_eq!(EventsOfShipment::get(&shipment_id), vec![1, 2]);
+
+        // Shipment is now in the custody of the carrier
+        assert_eq!(
+            ProductTracking::shipment_by_id(&shipment_id),
+            Some(Shipment {
+                id: shipment_id.clone(),
+                owner: owner,
+                custodian: carrier,
+                status: ShipmentStatus::Pending,
+                products: vec![TEST_PRODUCT_ID.as_bytes().to_owned()],
+                lots: vec![],
+                registered: now,
+                delivered: None,
+                waypoints: vec![],
+                expected_delivery: None
+            })
+        );
+        assert_eq!(ProductTracking::custody_offer_of(&shipment_id), None);
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_tracking(RawEvent::ShipmentCustodyTransferred(
+                driver,
+                shipment_id.clone(),
+                2,
+                carrier
+            ))));
+    })
+}
+
+#[test]
+fn track_shipment_by_custodian_only() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let carrier = account_key(TEST_CARRIER);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        let now = 42;
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::Pending, vec![], now);
+        store_test_carrier::<Test>(shipment_id.clone(), carrier);
+
+        // Carrier cannot handle the shipment before taking custody of it
+        assert_noop!(
+            ProductTracking::track_shipment(
+                Origin::signed(carrier),
+                shipment_id.clone(),
+                ShippingOperation::Pickup,
+                now,
+                None,
+                None
+            ),
+            Error::<Test>::NotShipmentCustodian
+        );
+
+        assert_ok!(ProductTracking::offer_custody(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            carrier
+        ));
+        assert_ok!(ProductTracking::accept_custody(
+            Origin::signed(carrier),
+            shipment_id.clone(),
+            now,
+            None
+        ));
+
+        // ...and the owner no longer can once custody was handed over
+        assert_noop!(
+            ProductTracking::track_shipment(
+                Origin::signed(owner),
+                shipment_id.clone(),
+                ShippingOperation::Pickup,
+                now,
+                None,
+                None
+            ),
+            Error::<Test>::NotShipmentCustodian
+        );
+        assert_ok!(ProductTracking::track_shipment(
+            Origin::signed(carrier),
+            shipment_id,
+            ShippingOperation::Pickup,
+            now,
+            None,
+            None
+        ));
+    })
+}
+
+#[test]
+fn migrate_shipments_without_custodian() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let product_id = TEST_PRODUCT_ID.as_bytes().to_owned();
+        StorageVersion::put(Releases::V1);
+
+        // Shipments as stored before custodians & lots
+        let legacy_shipment = (
+            b"0001".to_vec(),
+            owner,
+            ShipmentStatus::Delivered,
+            vec![product_id.clone()],
+            42u64,
+            Some(43u64),
+        );
+        unhashed::put(&<Shipments<Test>>::hashed_key_for(b"0001".to_vec()), &legacy_shipment);
+        // Its registration time encodes a lot (numbered L0042, of an empty product ID)
+        // so that the shipment also decodes as one with lots, which isn't delivered
+        let registered = u64::from_le_bytes([4, 0, 20, b'L', b'0', b'0', b'4', b'2']);
+        let ambiguous_shipment = (
+            b"0002".to_vec(),
+            owner,
+            ShipmentStatus::Delivered,
+            Vec::<ProductId>::new(),
+            registered,
+            Some(43u64),
+        );
+        type ShipmentWithLots =
+            (ShipmentId, sr25519::Public, ShipmentStatus, Vec<ProductId>, Vec<LotId>, u64, Option<u64>);
+        let encoded = ambiguous_shipment.encode();
+        let input = &mut &encoded[..];
+        assert_eq!(
+            ShipmentWithLots::decode(input).map(|shipment| (shipment.4, shipment.6)),
+            Ok((vec![(vec![], b"L0042".to_vec())], None))
+        );
+        assert!(input.is_empty());
+        unhashed::put(&<Shipments<Test>>::hashed_key_for(b"0002".to_vec()), &ambiguous_shipment);
+
+        <ProductTracking as OnRuntimeUpgrade>::on_runtime_upgrade();
+
+        assert_eq!(
+            ProductTracking::shipment_by_id(b"0001".to_vec()),
+            Some(Shipment {
+                id: b"0001".to_vec(),
+                owner,
+                custodian: owner,
+                status: ShipmentStatus::Delivered,
+                products: vec![product_id],
+                lots: vec![],
+                registered: 42,
+                delivered: Some(43),
+                waypoints: vec![],
+                expected_delivery: None
+            })
+        );
+        // The storage version tells which layout the shipment was stored with
+        assert_eq!(
+            ProductTracking::shipment_by_id(b"0002".to_vec()),
+            Some(Shipment {
+                id: b"0002".to_vec(),
+                owner,
+                custodian: owner,
+                status: ShipmentStatus::Delivered,
+                products: vec![],
+                lots: vec![],
+                registered,
+                delivered: Some(43),
+                waypoints: vec![],
+                expected_delivery: None
+            })
+        );
+        assert_eq!(StorageVersion::get(), Releases::V3);
+    })
+}
+
+#[test]
+fn migrate_shipments_without_planned_route() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let carrier = account_key(TEST_CARRIER);
+        StorageVersion::put(Releases::V2);
+
+        // Shipment as stored before planned routes
+        let shipment = (
+            b"0001".to_vec(),
+            owner,
+            carrier,
+            ShipmentStatus::InTransit,
+            Vec::<ProductId>::new(),
+            Vec::<LotId>::new(),
+            42u64,
+            None::<u64>,
+        );
+        unhashed::put(&<Shipments<Test>>::hashed_key_for(b"0001".to_vec()), &shipment);
+
+        <ProductTracking as OnRuntimeUpgrade>::on_runtime_upgrade();
+
+        assert_eq!(
+            ProductTracking::shipment_by_id(b"0001".to_vec()),
+            Some(Shipment {
+                id: b"0001".to_vec(),
+                owner,
+                custodian: carrier,
+                status: ShipmentStatus::InTransit,
+                products: vec![],
+                lots: vec![],
+                registered: 42,
+                delivered: None,
+                waypoints: vec![],
+                expected_delivery: None
+            })
+        );
+        assert_eq!(StorageVersion::get(), Releases::V3);
+    })
+}
+
+#[test]
+fn track_shipment_by_other_organization() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let carrier = account_key(TEST_CARRIER);
+        let driver = account_key(TEST_SENDER);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        let now = 42;
+        add_org_member(carrier, driver);
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::Pending, vec![], now);
+
+        // Neither the other organization nor its members may track the shipment...
+        for who in [carrier, driver].iter() {
+            assert_noop!(
+                ProductTracking::track_shipment(
+                    Origin::signed(*who),
+                    shipment_id.clone(),
+                    ShippingOperation::Scan,
+                    now,
+                    None,
+                    None
+                ),
+                Error::<Test>::NotAuthorizedForShipment
+            );
+        }
+
+        // ...nor take custody of it
+        assert_noop!(
+            ProductTracking::offer_custody(Origin::signed(owner), shipment_id.clone(), carrier),
+            Error::<Test>::ShipmentCarrierNotAuthorized
+        );
+
+        // ...until it has been authorized by the owner, and took custody of the shipment
+        assert_ok!(ProductTracking::authorize_carrier(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            carrier
+        ));
+        assert_ok!(ProductTracking::offer_custody(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            carrier
+        ));
+        assert_ok!(ProductTracking::accept_custody(
+            Origin::signed(driver),
+            shipment_id.clone(),
+            now,
+            None
+        ));
+        assert_ok!(ProductTracking::track_shipment(
+            Origin::signed(driver),
+            shipment_id,
+            ShippingOperation::Pickup,
+            now,
+            None,
+            None
+        ));
+    })
+}
+
+#[test]
+fn authorize_shipment_carrier() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let sender = account_key(TEST_SENDER);
+        let carrier = account_key(TEST_CARRIER);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        add_org_member(owner, sender);
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::Pending, vec![], 42);
+
+        assert_ok!(ProductTracking::authorize_carrier(
+            Origin::signed(sender),
+            shipment_id.clone(),
+            carrier
+        ));
+
+        assert_eq!(ProductTracking::carriers_of(&shipment_id), vec![carrier]);
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_tracking(RawEvent::ShipmentCarrierAuthorized(
+                sender,
+                shipment_id.clone(),
+                carrier
+            ))));
+
+        assert_noop!(
+            ProductTracking::authorize_carrier(Origin::signed(sender), shipment_id.clone(), carrier),
+            Error::<Test>::ShipmentCarrierAlreadyAuthorized
+        );
+        assert_noop!(
+            ProductTracking::authorize_carrier(Origin::signed(sender), shipment_id, owner),
+            Error::<Test>::ShipmentCarrierAlreadyAuthorized
+        );
+    })
+}
+
+#[test]
+fn authorize_shipment_carrier_by_other_organization() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let carrier = account_key(TEST_CARRIER);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::Pending, vec![], 42);
+        store_test_carrier::<Test>(shipment_id.clone(), carrier);
+
+        // Even authorized carriers cannot authorize other ones
+        assert_noop!(
+            ProductTracking::authorize_carrier(
+                Origin::signed(carrier),
+                shipment_id.clone(),
+                account_key("Contoso")
+            ),
+            Error::<Test>::NotShipmentOwner
+        );
+        assert_noop!(
+            ProductTracking::revoke_carrier(Origin::signed(carrier), shipment_id, carrier),
+            Error::<Test>::NotShipmentOwner
+        );
+    })
+}
+
+#[test]
+fn revoke_shipment_carrier() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let carrier = account_key(TEST_CARRIER);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        let now = 42;
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::Pending, vec![], now);
+        store_test_carrier::<Test>(shipment_id.clone(), carrier);
+        assert_ok!(ProductTracking::offer_custody(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            carrier
+        ));
+
+        assert_ok!(ProductTracking::revoke_carrier(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            carrier
+        ));
+
+        // Pending custody offer is withdrawn along with the authorization
+        assert!(ProductTracking::carriers_of(&shipment_id).is_empty());
+        assert_eq!(ProductTracking::custody_offer_of(&shipment_id), None);
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_tracking(RawEvent::ShipmentCarrierRevoked(
+                owner,
+                shipment_id.clone(),
+                carrier
+            ))));
+
+        assert_noop!(
+            ProductTracking::revoke_carrier(Origin::signed(owner), shipment_id.clone(), carrier),
+            Error::<Test>::ShipmentCarrierNotAuthorized
+        );
+
+        // Carriers in custody of the shipment cannot be revoked
+        store_test_carrier::<Test>(shipment_id.clone(), carrier);
+        assert_ok!(ProductTracking::offer_custody(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            carrier
+        ));
+        assert_ok!(ProductTracking::accept_custody(
+            Origin::signed(carrier),
+            shipment_id.clone(),
+            now,
+            None
+        ));
+        assert_noop!(
+            ProductTracking::revoke_carrier(Origin::signed(owner), shipment_id, carrier),
+            Error::<Test>::ShipmentCarrierHasCustody
+        );
+    })
+}
+
+fn temperature_reading(device: &str, value: f32) -> Reading<u64> {
+    Reading {
+        device_id: device.as_bytes().to_owned(),
+        reading_type: ReadingType::Temperature,
+        timestamp: 42,
+        value: I16F16::from_num(value),
+    }
+}
+
+#[test]
+fn set_shipment_condition_threshold() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let sender = account_key(TEST_SENDER);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        add_org_member(owner, sender);
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::Pending, vec![], 42);
+
+        assert_ok!(ProductTracking::set_condition_threshold(
+            Origin::signed(sender),
+            shipment_id.clone(),
+            ReadingType::Temperature,
+            Some(I16F16::from_num(2)),
+            Some(I16F16::from_num(8))
+        ));
+        assert_ok!(ProductTracking::set_condition_threshold(
+            Origin::signed(sender),
+            shipment_id.clone(),
+            ReadingType::Shock,
+            None,
+            Some(I16F16::from_num(3))
+        ));
+
+        // Setting a threshold again replaces it
+        assert_ok!(ProductTracking::set_condition_threshold(
+            Origin::signed(sender),
+            shipment_id.clone(),
+            ReadingType::Temperature,
+            Some(I16F16::from_num(0)),
+            None
+        ));
+        assert_eq!(
+            ProductTracking::thresholds_of(&shipment_id),
+            vec![
+                ConditionThreshold {
+                    reading_type: ReadingType::Shock,
+                    min: None,
+                    max: Some(I16F16::from_num(3))
+                },
+                ConditionThreshold {
+                    reading_type: ReadingType::Temperature,
+                    min: Some(I16F16::from_num(0)),
+                    max: None
+                },
+            ]
+        );
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_tracking(RawEvent::ShipmentConditionThresholdSet(
+                sender,
+                shipment_id.clone(),
+                ReadingType::Shock
+            ))));
+
+        // ...while an unbounded one clears it
+        assert_ok!(ProductTracking::set_condition_threshold(
+            Origin::signed(sender),
+            shipment_id.clone(),
+            ReadingType::Shock,
+            None,
+            None
+        ));
+        assert_eq!(ProductTracking::thresholds_of(&shipment_id).len(), 1);
+    })
+}
+
+#[test]
+fn set_invalid_shipment_condition_threshold() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let carrier = account_key(TEST_CARRIER);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::Pending, vec![], 42);
+        store_test_carrier::<Test>(shipment_id.clone(), carrier);
+
+        assert_noop!(
+            ProductTracking::set_condition_threshold(
+                Origin::signed(owner),
+                shipment_id.clone(),
+                ReadingType::Temperature,
+                Some(I16F16::from_num(8)),
+                Some(I16F16::from_num(2))
+            ),
+            Error::<Test>::InvalidConditionThreshold
+        );
+
+        // Carriers cannot set thresholds
+        assert_noop!(
+            ProductTracking::set_condition_threshold(
+                Origin::signed(carrier),
+                shipment_id.clone(),
+                ReadingType::Temperature,
+                None,
+                Some(I16F16::from_num(8))
+            ),
+            Error::<Test>::NotShipmentOwner
+        );
+
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::Delivered, vec![], 42);
+        assert_noop!(
+            ProductTracking::set_condition_threshold(
+                Origin::signed(owner),
+                shipment_id,
+                ReadingType::Temperature,
+                None,
+                Some(I16F16::from_num(8))
+            ),
+            Error::<Test>::ShipmentIsClosed
+        );
+    })
+}
+
+#[test]
+fn track_shipment_with_condition_violations() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        let now = 42;
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::Pending, vec![], now);
+        store_test_device("probe-1", owner, vec![ReadingType::Temperature]);
+        store_test_device("probe-2", owner, vec![ReadingType::Temperature]);
+        store_test_device("probe-3", owner, vec![ReadingType::Humidity]);
+        assert_ok!(ProductTracking::set_condition_threshold(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            ReadingType::Temperature,
+            Some(I16F16::from_num(2)),
+            Some(I16F16::from_num(8))
+        ));
+
+        // Readings within range don't flag the shipment
+        assert_ok!(ProductTracking::track_shipment(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            ShippingOperation::Pickup,
+            now,
+            None,
+            Some(sign_readings(
+                &shipment_id,
+                &[temperature_reading("probe-1", 2.0), temperature_reading("probe-2", 8.0)]
+            ))
+        ));
+        assert!(ProductTracking::condition_violations_of(&shipment_id).is_empty());
+
+        // ...while the ones past the threshold do, unlike other types of readings
+        let humidity = Reading {
+            device_id: b"probe-3".to_vec(),
+            reading_type: ReadingType::Humidity,
+            timestamp: now,
+            value: I16F16::from_num(95),
+        };
+        assert_ok!(ProductTracking::track_shipment(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            ShippingOperation::Scan,
+            now,
+            None,
+            Some(sign_readings(
+                &shipment_id,
+                &[
+                    temperature_reading("probe-1", 9.5),
+                    temperature_reading("probe-2", 5.0),
+                    temperature_reading("probe-2", -1.0),
+                    humidity
+                ]
+            ))
+        ));
+        assert_eq!(
+            ProductTracking::condition_violations_of(&shipment_id),
+            vec![ReadingType::Temperature]
+        );
+
+        // An event is raised for each violation
+        let violations: Vec<_> = System::events()
+            .into_iter()
+            .filter_map(|er| match er.event {
+                TestEvent::product_tracking(RawEvent::ShipmentConditionViolated(
+                    id,
+                    reading_type,
+                    value,
+                    device_id,
+                )) => Some((id, reading_type, value, device_id)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                (
+                    shipment_id.clone(),
+                    ReadingType::Temperature,
+                    I16F16::from_num(9.5),
+                    b"probe-1".to_vec()
+                ),
+                (
+                    shipment_id,
+                    ReadingType::Temperature,
+                    I16F16::from_num(-1),
+                    b"probe-2".to_vec()
+                ),
+            ]
+        );
+    })
+}
+
+fn read_point(latitude: f64, longitude: f64) -> ReadPoint {
+    ReadPoint {
+        latitude: I16F16::from_num(latitude),
+        longitude: I16F16::from_num(longitude),
+    }
+}
+
+// Square around Berlin's center
+fn berlin_polygon() -> Geofence {
+    Geofence::Polygon(vec![
+        read_point(52.45, 13.3),
+        read_point(52.45, 13.5),
+        read_point(52.55, 13.5),
+        read_point(52.55, 13.3),
+    ])
+}
+
+fn paris_circle() -> Geofence {
+    Geofence::Circle(read_point(48.8566, 2.3522), 10_000)
+}
+
+#[test]
+fn read_point_distance() {
+    let berlin = read_point(52.52, 13.405);
+    let paris = read_point(48.8566, 2.3522);
+
+    // ~878km, within 1%
+    let distance = berlin.distance_to(&paris);
+    assert!(distance > 869_000 && distance < 887_000, "{}", distance);
+    assert_eq!(berlin.distance_to(&berlin), 0);
+
+    // Distances are measured across the antimeridian
+    assert!(read_point(0.0, 179.9).distance_to(&read_point(0.0, -179.9)) < 23_000);
+}
+
+#[test]
+fn geofence_contains_point() {
+    assert!(paris_circle().contains(&read_point(48.85, 2.35)));
+    assert!(paris_circle().contains(&read_point(48.9, 2.3)));
+    assert!(!paris_circle().contains(&read_point(49.0, 2.35)));
+
+    assert!(berlin_polygon().contains(&read_point(52.52, 13.405)));
+    assert!(!berlin_polygon().contains(&read_point(52.52, 13.6)));
+    assert!(!berlin_polygon().contains(&read_point(52.4, 13.405)));
+}
+
+#[test]
+fn set_shipment_route() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let sender = account_key(TEST_SENDER);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        add_org_member(owner, sender);
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::Pending, vec![], 42);
+
+        assert_ok!(ProductTracking::set_route(
+            Origin::signed(sender),
+            shipment_id.clone(),
+            Some(berlin_polygon()),
+            Some(paris_circle()),
+            vec![]
+        ));
+
+        assert_eq!(
+            ProductTracking::route_of(&shipment_id),
+            Some(ShipmentRoute {
+                origin: Some(berlin_polygon()),
+                destination: Some(paris_circle()),
+                corridor: vec![]
+            })
+        );
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_tracking(RawEvent::ShipmentRouteSet(sender, shipment_id.clone()))));
+    })
+}
+
+#[test]
+fn set_invalid_shipment_route() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let carrier = account_key(TEST_CARRIER);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::Pending, vec![], 42);
+        store_test_carrier::<Test>(shipment_id.clone(), carrier);
+
+        let invalid_fences = vec![
+            Geofence::Circle(read_point(48.8566, 2.3522), 0),
+            Geofence::Circle(read_point(91.0, 2.3522), 10_000),
+            Geofence::Polygon(vec![read_point(52.45, 13.3), read_point(52.55, 13.5)]),
+            Geofence::Polygon(vec![read_point(52.45, 13.3); GEOFENCE_MAX_VERTICES + 1]),
+        ];
+        for fence in invalid_fences {
+            assert_noop!(
+                ProductTracking::set_route(
+                    Origin::signed(owner),
+                    shipment_id.clone(),
+                    None,
+                    None,
+                    vec![fence]
+                ),
+                Error::<Test>::InvalidGeofence
+            );
+        }
+
+        assert_noop!(
+            ProductTracking::set_route(
+                Origin::signed(owner),
+                shipment_id.clone(),
+                None,
+                None,
+                vec![paris_circle(); ROUTE_MAX_CORRIDOR_FENCES + 1]
+            ),
+            Error::<Test>::ShipmentRouteHasTooManyFences
+        );
+
+        // Carriers cannot set the route
+        assert_noop!(
+            ProductTracking::set_route(
+                Origin::signed(carrier),
+                shipment_id,
+                None,
+                Some(paris_circle()),
+                vec![]
+            ),
+            Error::<Test>::NotShipmentOwner
+        );
+    })
+}
+
+#[test]
+fn deliver_shipment_outside_destination() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        let now = 42;
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::InTransit, vec![], now);
+        assert_ok!(ProductTracking::set_route(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            None,
+            Some(paris_circle()),
+            vec![]
+        ));
+
+        assert_noop!(
+            ProductTracking::track_shipment(
+                Origin::signed(owner),
+                shipment_id.clone(),
+                ShippingOperation::Deliver,
+                now,
+                Some(read_point(52.52, 13.405)),
+                None
+            ),
+            Error::<Test>::ShipmentIsOutsideDestination
+        );
+        assert_noop!(
+            ProductTracking::track_shipment(
+                Origin::signed(owner),
+                shipment_id.clone(),
+                ShippingOperation::Deliver,
+                now,
+                None,
+                None
+            ),
+            Error::<Test>::ShipmentLocationIsMissing
+        );
+
+        assert_ok!(ProductTracking::track_shipment(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            ShippingOperation::Deliver,
+            now,
+            Some(read_point(48.86, 2.34)),
+            None
+        ));
+        assert_eq!(
+            ProductTracking::shipment_by_id(&shipment_id).map(|s| s.status),
+            Some(ShipmentStatus::Delivered)
+        );
+    })
+}
+
+#[test]
+fn scan_shipment_outside_route() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        let now = 42;
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::InTransit, vec![], now);
+
+        // Corridor going through Cologne
+        let cologne = Geofence::Circle(read_point(50.9375, 6.9603), 150_000);
+        assert_ok!(ProductTracking::set_route(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            Some(berlin_polygon()),
+            Some(paris_circle()),
+            vec![cologne]
+        ));
+
+        // Scans within the origin, destination or corridor fences are on route
+        for point in vec![read_point(52.5, 13.4), read_point(51.2, 6.8), read_point(48.85, 2.35)] {
+            assert_ok!(ProductTracking::track_shipment(
+                Origin::signed(owner),
+                shipment_id.clone(),
+                ShippingOperation::Scan,
+                now,
+                Some(point),
+                None
+            ));
+        }
+        assert!(!System::events().iter().any(|er| matches!(
+            er.event,
+            TestEvent::product_tracking(RawEvent::ShipmentRouteDeviated(..))
+        )));
+
+        // ...while others deviate from it
+        assert_ok!(ProductTracking::track_shipment(
+            Origin::signed(owner),
+            shipment_id.clone(),
+            ShippingOperation::Scan,
+            now,
+            Some(read_point(48.1351, 11.582)),
+            None
+        ));
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_tracking(RawEvent::ShipmentRouteDeviated(
+                shipment_id.clone(),
+                4,
+                read_point(48.1351, 11.582)
+            ))));
+    })
+}
+
+#[test]
+fn register_shipment_with_planned_route() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        let now = 42;
+        Timestamp::set_timestamp(now);
+        let waypoints = vec![read_point(52.52, 13.405), read_point(50.9375, 6.9603)];
+        let expected_delivery = now + 2 * DEADLINE_PERIOD;
+
+        assert_ok!(ProductTracking::register_shipment(
+            Origin::signed(owner),
+            id.clone(),
+            owner,
+            vec![],
+            vec![],
+            waypoints.clone(),
+            Some(expected_delivery),
+        ));
+
+        let shipment = ProductTracking::shipment_by_id(&id).unwrap();
+        assert_eq!(shipment.waypoints, waypoints);
+        assert_eq!(shipment.expected_delivery, Some(expected_delivery));
+
+        // Shipment is scheduled for the overdue check
+        assert_eq!(ProductTracking::shipments_due_in(2), vec![id]);
+    })
+}
+
+#[test]
+fn register_shipment_with_invalid_plan() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        let now = 42;
+        Timestamp::set_timestamp(now);
+
+        assert_noop!(
+            ProductTracking::register_shipment(
+                Origin::signed(owner),
+                id.clone(),
+                owner,
+                vec![],
+                vec![],
+                vec![read_point(52.52, 13.405); SHIPMENT_MAX_WAYPOINTS + 1],
+                None
+            ),
+            Error::<Test>::ShipmentHasTooManyWaypoints
+        );
+        assert_noop!(
+            ProductTracking::register_shipment(
+                Origin::signed(owner),
+                id.clone(),
+                owner,
+                vec![],
+                vec![],
+                vec![read_point(52.52, 213.405)],
+                None
+            ),
+            Error::<Test>::InvalidWaypoint
+        );
+        assert_noop!(
+            ProductTracking::register_shipment(
+                Origin::signed(owner),
+                id,
+                owner,
+                vec![],
+                vec![],
+                vec![],
+                Some(now)
+            ),
+            Error::<Test>::InvalidExpectedDelivery
+        );
+    })
+}
+
+fn overdue_shipments() -> Vec<(ShipmentId, u64)> {
+    System::events()
+        .into_iter()
+        .filter_map(|er| match er.event {
+            TestEvent::product_tracking(RawEvent::ShipmentOverdue(id, deadline)) => Some((id, deadline)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn flag_overdue_shipments() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let now = 1_000;
+        Timestamp::set_timestamp(now);
+        <ProductTracking as OnFinalize<u64>>::on_finalize(1);
+
+        // Shipments due during the first & second periods, one of which gets delivered on time
+        for (id, deadline) in vec![(b"0001", 30_000), (b"0002", 90_000), (b"0003", 70_000)] {
+            assert_ok!(ProductTracking::register_shipment(
+                Origin::signed(owner),
+                id.to_vec(),
+                owner,
+                vec![],
+                vec![],
+                vec![],
+                Some(deadline),
+            ));
+        }
+        for operation in vec![ShippingOperation::Pickup, ShippingOperation::Deliver] {
+            assert_ok!(ProductTracking::track_shipment(
+                Origin::signed(owner),
+                b"0003".to_vec(),
+                operation,
+                now,
+                None,
+                None
+            ));
+        }
+
+        // Shipments are flagged once the period they're due in has elapsed
+        Timestamp::set_timestamp(50_000);
+        <ProductTracking as OnFinalize<u64>>::on_finalize(2);
+        assert!(overdue_shipments().is_empty());
+
+        Timestamp::set_timestamp(61_000);
+        <ProductTracking as OnFinalize<u64>>::on_finalize(3);
+        assert_eq!(overdue_shipments(), vec![(b"0001".to_vec(), 30_000)]);
+
+        Timestamp::set_timestamp(125_000);
+        <ProductTracking as OnFinalize<u64>>::on_finalize(4);
+        assert_eq!(
+            overdue_shipments(),
+            vec![(b"0001".to_vec(), 30_000), (b"0002".to_vec(), 90_000)]
+        );
+        assert_eq!(ProductTracking::next_deadline_period(), Some(2));
+    })
+}
+
+#[test]
+fn flag_overdue_shipments_over_several_blocks() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        Timestamp::set_timestamp(0);
+        <ProductTracking as OnFinalize<u64>>::on_finalize(1);
+
+        let count = OVERDUE_MAX_CHECKS_PER_BLOCK + 10;
+        for i in 0..count {
+            assert_ok!(ProductTracking::register_shipment(
+                Origin::signed(owner),
+                format!("{:04}", i).into_bytes(),
+                owner,
+                vec![],
+                vec![],
+                vec![],
+                Some(1_000),
+            ));
+        }
+
+        // Checking the period counts as one check
+        Timestamp::set_timestamp(DEADLINE_PERIOD * 5);
+        <ProductTracking as OnFinalize<u64>>::on_finalize(2);
+        assert_eq!(overdue_shipments().len(), OVERDUE_MAX_CHECKS_PER_BLOCK - 1);
+        assert_eq!(ProductTracking::next_deadline_period(), Some(0));
+
+        <ProductTracking as OnFinalize<u64>>::on_finalize(3);
+        assert_eq!(overdue_shipments().len(), count);
+        assert_eq!(ProductTracking::next_deadline_period(), Some(5));
+    })
+}
+
+const TEST_CONTAINER_ID: &str = "C001";
+
+fn event_types_of(shipment_id: &ShipmentId) -> Vec<ShippingEventType> {
+    ProductTracking::events_of_shipment(shipment_id)
+        .into_iter()
+        .filter_map(|idx| ProductTracking::event_by_idx(idx).map(|e| e.event_type))
+        .collect()
+}
+
+// Container & two shipments, all pending in their owner's custody
+fn store_test_container(owner: <Test as system::Trait>::AccountId) -> (ShipmentId, Vec<ShipmentId>) {
+    let container = TEST_CONTAINER_ID.as_bytes().to_owned();
+    let shipments = vec![b"0001".to_vec(), b"0002".to_vec()];
+    for id in core::iter::once(&container).chain(shipments.iter()) {
+        store_test_shipment::<Test>(id.clone(), owner, ShipmentStatus::Pending, vec![], 42);
+    }
+    (container, shipments)
+}
+
+#[test]
+fn consolidate_shipments() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let sender = account_key(TEST_SENDER);
+        add_org_member(owner, sender);
+        let (container, shipments) = store_test_container(owner);
+
+        assert_ok!(ProductTracking::consolidate(
+            Origin::signed(sender),
+            container.clone(),
+            shipments.clone()
+        ));
+
+        assert_eq!(ProductTracking::shipments_in_container(&container), shipments);
+        for id in shipments.iter() {
+            assert_eq!(ProductTracking::container_of(id), Some(container.clone()));
+            assert_eq!(event_types_of(id), vec![ShippingEventType::ShipmentConsolidation]);
+        }
+        assert_eq!(event_types_of(&container), vec![ShippingEventType::ShipmentConsolidation]);
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_tracking(RawEvent::ShipmentsConsolidated(
+                sender,
+                container.clone(),
+                shipments.clone()
+            ))));
+    })
+}
+
+#[test]
+fn consolidate_invalid_shipments() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let carrier = account_key(TEST_CARRIER);
+        let (container, shipments) = store_test_container(owner);
+        let consolidate = |shipments: Vec<ShipmentId>| {
+            ProductTracking::consolidate(Origin::signed(owner), container.clone(), shipments)
+        };
+
+        assert_noop!(consolidate(vec![]), Error::<Test>::ContainerHasNoShipments);
+        assert_noop!(consolidate(vec![container.clone()]), Error::<Test>::ShipmentIsContainer);
+        assert_noop!(consolidate(vec![b"0003".to_vec()]), Error::<Test>::ShipmentIsUnknown);
+        assert_noop!(
+            consolidate(vec![shipments[0].clone(), shipments[0].clone()]),
+            Error::<Test>::ShipmentIsConsolidated
+        );
+        assert_noop!(
+            consolidate(vec![shipments[0].clone(); CONTAINER_MAX_SHIPMENTS + 1]),
+            Error::<Test>::ContainerHasTooManyShipments
+        );
+
+        // Shipments must be in the same status & custody as the container
+        store_test_shipment::<Test>(b"0003".to_vec(), owner, ShipmentStatus::InTransit, vec![], 42);
+        assert_noop!(consolidate(vec![b"0003".to_vec()]), Error::<Test>::ShipmentStatusMismatch);
+        store_test_shipment::<Test>(b"0004".to_vec(), carrier, ShipmentStatus::Pending, vec![], 42);
+        assert_noop!(consolidate(vec![b"0004".to_vec()]), Error::<Test>::NotShipmentCustodian);
+
+        // Shipments can only be in one container, and containers can't be nested
+        assert_ok!(consolidate(shipments.clone()));
+        assert_noop!(consolidate(vec![shipments[1].clone()]), Error::<Test>::ShipmentIsConsolidated);
+        store_test_shipment::<Test>(b"C002".to_vec(), owner, ShipmentStatus::Pending, vec![], 42);
+        assert_noop!(
+            ProductTracking::consolidate(Origin::signed(owner), b"C002".to_vec(), vec![container.clone()]),
+            Error::<Test>::ShipmentIsContainer
+        );
+        assert_noop!(
+            ProductTracking::consolidate(Origin::signed(owner), shipments[0].clone(), vec![b"C002".to_vec()]),
+            Error::<Test>::ShipmentIsConsolidated
+        );
+
+        // Only the container's custodian may load it
+        assert_noop!(
+            ProductTracking::consolidate(Origin::signed(carrier), container.clone(), vec![b"C002".to_vec()]),
+            Error::<Test>::NotAuthorizedForShipment
+        );
+    })
+}
+
+#[test]
+fn track_consolidated_shipments() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let now = 42;
+        let (container, shipments) = store_test_container(owner);
+        assert_ok!(ProductTracking::consolidate(
+            Origin::signed(owner),
+            container.clone(),
+            shipments.clone()
+        ));
+
+        // Consolidated shipments are tracked through their container only
+        assert_noop!(
+            ProductTracking::track_shipment(
+                Origin::signed(owner),
+                shipments[0].clone(),
+                ShippingOperation::Pickup,
+                now,
+                None,
+                None
+            ),
+            Error::<Test>::ShipmentIsConsolidated
+        );
+
+        for operation in vec![ShippingOperation::Pickup, ShippingOperation::Scan] {
+            assert_ok!(ProductTracking::track_shipment(
+                Origin::signed(owner),
+                container.clone(),
+                operation,
+                now,
+                Some(read_point(52.52, 13.405)),
+                None
+            ));
+        }
+
+        // Operations cascade to the container's shipments
+        for id in shipments.iter() {
+            assert_eq!(
+                ProductTracking::shipment_by_id(id).map(|s| s.status),
+                Some(ShipmentStatus::InTransit)
+            );
+            assert_eq!(
+                event_types_of(id),
+                vec![
+                    ShippingEventType::ShipmentConsolidation,
+                    ShippingEventType::ShipmentPickup,
+                    ShippingEventType::ShipmentScan
+                ]
+            );
+        }
+        let last_event = ProductTracking::events_of_shipment(&shipments[1]).pop().unwrap();
+        assert_eq!(
+            ProductTracking::event_by_idx(last_event).map(|e| e.location),
+            Some(Some(read_point(52.52, 13.405)))
+        );
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_tracking(RawEvent::ShipmentStatusUpdated(
+                owner,
+                shipments[0].clone(),
+                5,
+                ShipmentStatus::InTransit
+            ))));
+
+        // ...provided they're allowed for each one of them
+        store_test_shipment::<Test>(shipments[1].clone(), owner, ShipmentStatus::OnHold, vec![], now);
+        assert_noop!(
+            ProductTracking::track_shipment(
+                Origin::signed(owner),
+                container,
+                ShippingOperation::Deliver,
+                now,
+                None,
+                None
+            ),
+            Error::<Test>::InvalidStatusTransition
+        );
+    })
+}
+
+#[test]
+fn track_container_with_stricter_shipment_threshold() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let now = 42;
+        let (container, shipments) = store_test_container(owner);
+        store_test_device("probe-1", owner, vec![ReadingType::Temperature]);
+        assert_ok!(ProductTracking::consolidate(
+            Origin::signed(owner),
+            container.clone(),
+            shipments.clone()
+        ));
+        let threshold = |id: &ShipmentId, max: i32| {
+            ProductTracking::set_condition_threshold(
+                Origin::signed(owner),
+                id.clone(),
+                ReadingType::Temperature,
+                None,
+                Some(I16F16::from_num(max)),
+            )
+        };
+        assert_ok!(threshold(&container, 8));
+        assert_ok!(threshold(&shipments[0], 4));
+
+        assert_ok!(ProductTracking::track_shipment(
+            Origin::signed(owner),
+            container.clone(),
+            ShippingOperation::Pickup,
+            now,
+            None,
+            Some(sign_readings(&container, &[temperature_reading("probe-1", 6.0)]))
+        ));
+
+        // Readings are only stored along with the container's event
+        let readings_of_last_event = |id: &ShipmentId| {
+            ProductTracking::events_of_shipment(id)
+                .pop()
+                .and_then(ProductTracking::event_by_idx)
+                .map(|event| event.readings.len())
+        };
+        assert_eq!(readings_of_last_event(&container), Some(1));
+        for id in shipments.iter() {
+            assert_eq!(readings_of_last_event(id), Some(0));
+        }
+
+        // Only the shipment whose own threshold is violated gets flagged
+        assert!(ProductTracking::condition_violations_of(&container).is_empty());
+        assert_eq!(
+            ProductTracking::condition_violations_of(&shipments[0]),
+            vec![ReadingType::Temperature]
+        );
+        assert!(ProductTracking::condition_violations_of(&shipments[1]).is_empty());
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_tracking(RawEvent::ShipmentConditionViolated(
+                shipments[0].clone(),
+                ReadingType::Temperature,
+                I16F16::from_num(6),
+                b"probe-1".to_vec()
+            ))));
+    })
+}
+
+#[test]
+fn deliver_container_outside_shipment_destination() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let now = 42;
+        let (container, shipments) = store_test_container(owner);
+        assert_ok!(ProductTracking::consolidate(
+            Origin::signed(owner),
+            container.clone(),
+            shipments.clone()
+        ));
+        assert_ok!(ProductTracking::track_shipment(
+            Origin::signed(owner),
+            container.clone(),
+            ShippingOperation::Pickup,
+            now,
+            None,
+            None
+        ));
+        // The container has no route, but one of its shipments must be delivered in Paris
+        assert_ok!(ProductTracking::set_route(
+            Origin::signed(owner),
+            shipments[1].clone(),
+            None,
+            Some(paris_circle()),
+            vec![]
+        ));
+
+        let deliver = |location| {
+            ProductTracking::track_shipment(
+                Origin::signed(owner),
+                container.clone(),
+                ShippingOperation::Deliver,
+                now,
+                location,
+                None,
+            )
+        };
+        assert_noop!(
+            deliver(Some(read_point(52.52, 13.405))),
+            Error::<Test>::ShipmentIsOutsideDestination
+        );
+        assert_noop!(deliver(None), Error::<Test>::ShipmentLocationIsMissing);
+
+        assert_ok!(deliver(Some(read_point(48.86, 2.34))));
+        for id in core::iter::once(&container).chain(shipments.iter()) {
+            assert_eq!(
+                ProductTracking::shipment_by_id(id).map(|s| s.status),
+                Some(ShipmentStatus::Delivered)
+            );
+        }
+    })
+}
+
+#[test]
+fn hand_over_consolidated_shipments() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let carrier = account_key(TEST_CARRIER);
+        let now = 42;
+        let (container, shipments) = store_test_container(owner);
+        store_test_carrier::<Test>(container.clone(), carrier);
+        store_test_carrier::<Test>(shipments[0].clone(), carrier);
+        assert_ok!(ProductTracking::consolidate(
+            Origin::signed(owner),
+            container.clone(),
+            shipments.clone()
+        ));
+
+        // Consolidated shipments are handed over along with their container only
+        assert_noop!(
+            ProductTracking::offer_custody(Origin::signed(owner), shipments[0].clone(), carrier),
+            Error::<Test>::ShipmentIsConsolidated
+        );
+
+        assert_ok!(ProductTracking::offer_custody(
+            Origin::signed(owner),
+            container.clone(),
+            carrier
+        ));
+        assert_ok!(ProductTracking::accept_custody(
+            Origin::signed(carrier),
+            container.clone(),
+            now,
+            None
+        ));
+
+        for id in core::iter::once(&container).chain(shipments.iter()) {
+            assert_eq!(ProductTracking::shipment_by_id(id).map(|s| s.custodian), Some(carrier));
+            assert_eq!(event_types_of(id).pop(), Some(ShippingEventType::CustodyTransfer));
+        }
+    })
+}
+
+#[test]
+fn deconsolidate_shipments() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let carrier = account_key(TEST_CARRIER);
+        let now = 42;
+        let (container, shipments) = store_test_container(owner);
+
+        assert_noop!(
+            ProductTracking::deconsolidate(Origin::signed(owner), container.clone()),
+            Error::<Test>::ContainerHasNoShipments
+        );
+
+        assert_ok!(ProductTracking::consolidate(
+            Origin::signed(owner),
+            container.clone(),
+            shipments.clone()
+        ));
+
+        // Only the container's custodian may unload it
+        assert_noop!(
+            ProductTracking::deconsolidate(Origin::signed(carrier), container.clone()),
+            Error::<Test>::NotAuthorizedForShipment
+        );
+
+        assert_ok!(ProductTracking::deconsolidate(Origin::signed(owner), container.clone()));
+
+        assert!(ProductTracking::shipments_in_container(&container).is_empty());
+        for id in core::iter::once(&container).chain(shipments.iter()) {
+            assert_eq!(ProductTracking::container_of(id), None);
+            assert_eq!(
+                event_types_of(id),
+                vec![
+                    ShippingEventType::ShipmentConsolidation,
+                    ShippingEventType::ShipmentDeconsolidation
+                ]
+            );
+        }
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_tracking(RawEvent::ShipmentsDeconsolidated(
+                owner,
+                container,
+                shipments.clone()
+            ))));
+
+        // Shipments can be tracked on their own again
+        assert_ok!(ProductTracking::track_shipment(
+            Origin::signed(owner),
+            shipments[0].clone(),
+            ShippingOperation::Pickup,
+            now,
+            None,
+            None
+        ));
+    })
+}
+
+// Shipment & shipping event exported in EPCIS tests
+fn epcis_test_shipment() -> Shipment<<Test as system::Trait>::AccountId, u64> {
+    Shipment {
+        id: TEST_SHIPMENT_ID.as_bytes().to_owned(),
+        owner: account_key(TEST_ORGANIZATION),
+        custodian: account_key(TEST_CARRIER),
+        status: ShipmentStatus::InTransit,
+        products: vec![TEST_PRODUCT_ID.as_bytes().to_owned()],
+        lots: vec![(b"00012345600002".to_vec(), b"L42".to_vec())],
+        registered: 1_601_550_000_000u64,
+        delivered: None,
+        waypoints: vec![],
+        expected_delivery: None,
+    }
+}
+
+fn epcis_test_event(event_type: ShippingEventType) -> ShippingEvent<u64> {
+    ShippingEvent {
+        event_type,
+        shipment_id: TEST_SHIPMENT_ID.as_bytes().to_owned(),
+        location: Some(read_point(52.5, 13.25)),
+        readings: vec![
+            Reading {
+                device_id: b"probe-1".to_vec(),
+                reading_type: ReadingType::Temperature,
+                timestamp: 1_601_553_599_000u64,
+                value: I16F16::from_num(20.5),
+            },
+            Reading {
+                device_id: b"probe-2".to_vec(),
+                reading_type: ReadingType::Shock,
+                timestamp: 1_601_553_599_500u64,
+                value: I16F16::from_num(1.25),
+            },
+        ],
+        timestamp: 1_601_553_600_123u64,
+    }
+}
+
+#[test]
+fn export_shipping_events_as_epcis() {
+    let object_golden_files = vec![
+        (
+            ShippingEventType::ShipmentRegistration,
+            include_str!("../fixtures/epcis/shipment_registration.jsonld"),
+        ),
+        (
+            ShippingEventType::ShipmentPickup,
+            include_str!("../fixtures/epcis/shipment_pickup.jsonld"),
+        ),
+        (
+            ShippingEventType::ShipmentScan,
+            include_str!("../fixtures/epcis/shipment_scan.jsonld"),
+        ),
+        (
+            ShippingEventType::ShipmentDeliver,
+            include_str!("../fixtures/epcis/shipment_deliver.jsonld"),
+        ),
+        (
+            ShippingEventType::CustodyTransfer,
+            include_str!("../fixtures/epcis/custody_transfer.jsonld"),
+        ),
+        (
+            ShippingEventType::ShipmentCancel,
+            include_str!("../fixtures/epcis/shipment_cancel.jsonld"),
+        ),
+        (
+            ShippingEventType::ShipmentHold,
+            include_str!("../fixtures/epcis/shipment_hold.jsonld"),
+        ),
+        (
+            ShippingEventType::ShipmentResume,
+            include_str!("../fixtures/epcis/shipment_resume.jsonld"),
+        ),
+        (
+            ShippingEventType::ShipmentReturn,
+            include_str!("../fixtures/epcis/shipment_return.jsonld"),
+        ),
+        (
+            ShippingEventType::ShipmentLoss,
+            include_str!("../fixtures/epcis/shipment_loss.jsonld"),
+        ),
+    ];
+    let aggregation_golden_files = vec![
+        (
+            ShippingEventType::ShipmentConsolidation,
+            include_str!("../fixtures/epcis/shipment_consolidation.jsonld"),
+        ),
+        (
+            ShippingEventType::ShipmentDeconsolidation,
+            include_str!("../fixtures/epcis/shipment_deconsolidation.jsonld"),
+        ),
+    ];
+
+    let shipment = epcis_test_shipment();
+    for (event_type, golden_file) in object_golden_files {
+        let expected: serde_json::Value = serde_json::from_str(golden_file).unwrap();
+        assert_eq!(
+            epcis::object_event(&shipment, &epcis_test_event(event_type.clone())),
+            expected,
+            "{:?}",
+            event_type
+        );
+    }
+
+    let container = TEST_CONTAINER_ID.as_bytes().to_owned();
+    let shipments = vec![b"0001".to_vec(), b"0002".to_vec()];
+    for (event_type, golden_file) in aggregation_golden_files {
+        let expected: serde_json::Value = serde_json::from_str(golden_file).unwrap();
+        assert_eq!(
+            epcis::aggregation_event(&container, &shipments, &epcis_test_event(event_type.clone())),
+            expected,
+            "{:?}",
+            event_type
+        );
+    }
+}
+
+#[test]
+fn export_product_identifiers_as_epcis() {
+    let event = epcis_test_event(ShippingEventType::ShipmentScan);
+    let epc_classes = |products: Vec<&str>, lots: Vec<(&str, &str)>| {
+        let shipment = Shipment {
+            products: products.iter().map(|id| id.as_bytes().to_owned()).collect(),
+            lots: lots
+                .iter()
+                .map(|(id, lot)| (id.as_bytes().to_owned(), lot.as_bytes().to_owned()))
+                .collect(),
+            ..epcis_test_shipment()
+        };
+        epcis::object_event(&shipment, &event)["quantityList"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|quantity| quantity["epcClass"].as_str().unwrap().to_owned())
+            .collect::<Vec<_>>()
+    };
+
+    // GTIN-8, -12 & -13 are zero-padded to 14 digits
+    assert_eq!(
+        epc_classes(vec!["96385074", "036000291452", "4006381333931"], vec![]),
+        vec![
+            "https://id.gs1.org/01/00000096385074",
+            "https://id.gs1.org/01/00036000291452",
+            "https://id.gs1.org/01/04006381333931",
+        ]
+    );
+
+    // Other product IDs are URNs, percent-encoded as lot numbers are
+    assert_eq!(
+        epc_classes(
+            vec!["SKU-1", "SKU 2/B"],
+            vec![("4006381333931", "L 42/A"), ("SKU-1", "L:1")]
+        ),
+        vec![
+            "urn:x-product-tracking:product:SKU-1",
+            "urn:x-product-tracking:product:SKU%202%2FB",
+            "https://id.gs1.org/01/04006381333931/10/L%2042%2FA",
+            "urn:x-product-tracking:lot:SKU-1:L%3A1",
+        ]
+    );
+}
+
+#[test]
+fn export_readings_as_epcis() {
+    let units = vec![
+        (ReadingType::Humidity, "gs1:RelativeHumidity", "P1"),
+        (ReadingType::Pressure, "gs1:AbsolutePressure", "KPA"),
+        (ReadingType::Shock, "gs1:Acceleration", "MSK"),
+        (ReadingType::Tilt, "gs1:Angle", "DD"),
+        (ReadingType::Temperature, "gs1:Temperature", "CEL"),
+        (ReadingType::Vibration, "gs1:Acceleration", "MSK"),
+    ];
+
+    for (reading_type, measurement_type, uom) in units {
+        let mut event = epcis_test_event(ShippingEventType::ShipmentScan);
+        event.readings.truncate(1);
+        event.readings[0].reading_type = reading_type.clone();
+
+        let object = epcis::object_event(&epcis_test_shipment(), &event);
+        assert_eq!(
+            object["sensorElementList"][0]["sensorReport"],
+            serde_json::json!([{ "type": measurement_type, "value": 20.5, "uom": uom }]),
+            "{:?}",
+            reading_type
+        );
+    }
+}
+
+#[test]
+fn export_shipment_as_epcis_document() {
+    new_test_ext().execute_with(|| {
+        let id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        let owner = account_key(TEST_ORGANIZATION);
+
+        // Unknown shipment has no document
+        assert_eq!(ProductTracking::epcis_document(&id, 42), None);
+
+        store_test_shipment::<Test>(id.clone(), owner, ShipmentStatus::Pending, vec![], 42);
+        store_test_event::<Test>(id.clone(), ShippingEventType::ShipmentRegistration);
+        store_test_event::<Test>(id.clone(), ShippingEventType::ShipmentPickup);
+
+        let document = ProductTracking::epcis_document(&id, 1_601_553_600_000).unwrap();
+        assert_eq!(document["type"], "EPCISDocument");
+        assert_eq!(document["creationDate"], "2020-10-01T12:00:00.000Z");
+
+        let events = document["epcisBody"]["eventList"].as_array().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["bizStep"], "staging_outbound");
+        assert_eq!(events[1]["bizStep"], "shipping");
+        assert_eq!(events[1]["eventTime"], "1970-01-01T00:00:00.042Z");
+
+        // Events without products, location or readings leave those out
+        for field in &["quantityList", "readPoint", "sensorElementList"] {
+            assert!(events[1].get(field).is_none());
+        }
+    })
+}
+
+#[test]
+fn export_consolidation_as_epcis_document() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let sender = account_key(TEST_SENDER);
+        add_org_member(owner, sender);
+        let (container, shipments) = store_test_container(owner);
+
+        assert_ok!(ProductTracking::consolidate(
+            Origin::signed(sender),
+            container.clone(),
+            shipments.clone()
+        ));
+
+        // Container's aggregation has all of its shipments as children
+        let document = ProductTracking::epcis_document(&container, 42).unwrap();
+        let aggregation = &document["epcisBody"]["eventList"][0];
+        assert_eq!(aggregation["type"], "AggregationEvent");
+        assert_eq!(aggregation["parentID"], "urn:x-product-tracking:shipment:C001");
+        assert_eq!(
+            aggregation["childEPCs"],
+            serde_json::json!([
+                "urn:x-product-tracking:shipment:0001",
+                "urn:x-product-tracking:shipment:0002"
+            ])
+        );
+
+        // Each shipment's aggregation only has the shipment itself as child
+        let document = ProductTracking::epcis_document(&shipments[1], 42).unwrap();
+        let aggregation = &document["epcisBody"]["eventList"][0];
+        assert_eq!(aggregation["type"], "AggregationEvent");
+        assert_eq!(aggregation["parentID"], "urn:x-product-tracking:shipment:C001");
+        assert_eq!(
+            aggregation["childEPCs"],
+            serde_json::json!(["urn:x-product-tracking:shipment:0002"])
+        );
+
+        // Aggregations are still exported once the container has been emptied
+        assert_ok!(ProductTracking::deconsolidate(Origin::signed(sender), container.clone()));
+        assert!(ProductTracking::shipments_in_container(&container).is_empty());
+
+        let document = ProductTracking::epcis_document(&container, 42).unwrap();
+        let aggregation = &document["epcisBody"]["eventList"][1];
+        assert_eq!(aggregation["type"], "AggregationEvent");
+        assert_eq!(aggregation["action"], "DELETE");
+        assert_eq!(
+            aggregation["childEPCs"],
+            serde_json::json!([
+                "urn:x-product-tracking:shipment:0001",
+                "urn:x-product-tracking:shipment:0002"
+            ])
+        );
+    })
+}
+
+#[test]
+fn register_device() {
+    new_test_ext().execute_with(|| {
+        let sender = account_key(TEST_SENDER);
+        let owner = account_key(TEST_ORGANIZATION);
+        add_org_member(owner, sender);
+        let id = b"probe-1".to_vec();
+        let public_key = device_key("probe-1").public();
+
+        // Dispatchable call succeeds
+        assert_ok!(ProductTracking::register_device(
+            Origin::signed(sender),
+            id.clone(),
+            owner,
+            public_key,
+            vec![ReadingType::Temperature, ReadingType::Humidity]
+        ));
+
+        // Storage is correctly updated
+        assert_eq!(
+            ProductTracking::device_by_id(&id),
+            Some(Device {
+                id: id.clone(),
+                owner,
+                public_key,
+                reading_types: vec![ReadingType::Temperature, ReadingType::Humidity],
+            })
+        );
+        assert_eq!(ProductTracking::devices_of_org(owner), vec![id.clone()]);
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_tracking(RawEvent::DeviceRegistered(
+                sender,
+                id.clone(),
+                owner
+            ))));
+
+        // Devices are registered once
+        assert_noop!(
+            ProductTracking::register_device(
+                Origin::signed(sender),
+                id,
+                owner,
+                public_key,
+                vec![ReadingType::Temperature]
+            ),
+            Error::<Test>::DeviceAlreadyExists
+        );
+    })
+}
+
+#[test]
+fn register_device_with_invalid_details() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let public_key = device_key("probe-1").public();
+
+        assert_noop!(
+            ProductTracking::register_device(
+                Origin::signed(owner),
+                vec![],
+                owner,
+                public_key,
+                vec![ReadingType::Temperature]
+            ),
+            Error::<Test>::InvalidOrMissingIdentifier
+        );
+
+        // Devices take readings of at least one type, each listed once
+        for reading_types in vec![
+            vec![],
+            vec![ReadingType::Temperature, ReadingType::Temperature],
+        ] {
+            assert_noop!(
+                ProductTracking::register_device(
+                    Origin::signed(owner),
+                    b"probe-1".to_vec(),
+                    owner,
+                    public_key,
+                    reading_types
+                ),
+                Error::<Test>::InvalidDeviceReadingTypes
+            );
+        }
+
+        // Only the owner organization's members may register its devices
+        assert_noop!(
+            ProductTracking::register_device(
+                Origin::signed(account_key(TEST_CARRIER)),
+                b"probe-1".to_vec(),
+                owner,
+                public_key,
+                vec![ReadingType::Temperature]
+            ),
+            Error::<Test>::NotDeviceOwner
+        );
+    })
+}
+
+#[test]
+fn deregister_device() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let id = b"probe-1".to_vec();
+
+        assert_noop!(
+            ProductTracking::deregister_device(Origin::signed(owner), id.clone()),
+            Error::<Test>::DeviceIsUnknown
+        );
+
+        assert_ok!(ProductTracking::register_device(
+            Origin::signed(owner),
+            id.clone(),
+            owner,
+            device_key("probe-1").public(),
+            vec![ReadingType::Temperature]
+        ));
+
+        // Only the owner organization's members may deregister its devices
+        assert_noop!(
+            ProductTracking::deregister_device(Origin::signed(account_key(TEST_CARRIER)), id.clone()),
+            Error::<Test>::NotDeviceOwner
+        );
+
+        assert_ok!(ProductTracking::deregister_device(Origin::signed(owner), id.clone()));
+
+        assert_eq!(ProductTracking::device_by_id(&id), None);
+        assert!(ProductTracking::devices_of_org(owner).is_empty());
+
+        // Event is raised
+        assert!(System::events().iter().any(|er| er.event
+            == TestEvent::product_tracking(RawEvent::DeviceDeregistered(owner, id.clone()))));
+    })
+}
+
+#[test]
+fn track_shipment_with_signed_readings() {
+    new_test_ext().execute_with(|| {
+        let owner = account_key(TEST_ORGANIZATION);
+        let carrier = account_key(TEST_CARRIER);
+        let shipment_id = TEST_SHIPMENT_ID.as_bytes().to_owned();
+        let now = 42;
+        store_test_shipment::<Test>(shipment_id.clone(), owner, ShipmentStatus::Pending, vec![], now);
+        store_test_carrier::<Test>(shipment_id.clone(), carrier);
+        store_test_device("probe-1", owner, vec![ReadingType::Temperature]);
+        store_test_device("probe-2", carrier, vec![ReadingType::Temperature]);
+        store_test_device("probe-3", account_key(TEST_SENDER), vec![ReadingType::Temperature]);
+
+        let scan = |readings: Vec<SignedReading<u64>>| {
+            ProductTracking::track_shipment(
+                Origin::signed(owner),
+                shipment_id.clone(),
+                ShippingOperation::Scan,
+                now,
+                None,
+                Some(readings),
+            )
+        };
+
+        // Readings of unknown devices are rejected...
+        assert_noop!(
+            scan(sign_readings(&shipment_id, &[temperature_reading("probe-9", 5.0)])),
+            Error::<Test>::DeviceIsUnknown
+        );
+
+        // ...as are those of devices of organizations unrelated to the shipment...
+        assert_noop!(
+            scan(sign_readings(&shipment_id, &[temperature_reading("probe-3", 5.0)])),
+            Error::<Test>::DeviceNotAuthorizedForShipment
+        );
+
+        // ...or of types the device doesn't take
+        let humidity = Reading {
+            device_id: b"probe-1".to_vec(),
+            reading_type: ReadingType::Humidity,
+            timestamp: now,
+            value: I16F16::from_num(95),
+        };
+        assert_noop!(
+            scan(sign_readings(&shipment_id, &[humidity])),
+            Error::<Test>::DeviceReadingTypeNotAllowed
+        );
+
+        // Signatures must cover the reading as submitted, for this shipment
+        let mut tampered = sign_readings(&shipment_id, &[temperature_reading("probe-1", 5.0)]);
+        tampered[0].reading.value = I16F16::from_num(4);
+        assert_noop!(scan(tampered), Error::<Test>::InvalidReadingSignature);
+        assert_noop!(
+            scan(sign_readings(&b"0002".to_vec(), &[temperature_reading("probe-1", 5.0)])),
+            Error::<Test>::InvalidReadingSignature
+        );
+
+        // Devices of the owner & authorized carriers are accepted
+        let readings = vec![
+            temperature_reading("probe-1", 5.0),
+            temperature_reading("probe-2", 6.0),
+        ];
+        assert_ok!(scan(sign_readings(&shipment_id, &readings)));
+        assert_eq!(
+            AllEvents::<Test>::get(EventCount::get()).map(|event| event.readings),
+            Some(readings)
         );
     })
 }
@@ -1,51 +1,131 @@
 use codec::{Decode, Encode};
 use core::fmt;
-use fixed::types::I16F16;
+use fixed::types::{I16F16, I64F64};
 use frame_support::{sp_runtime::RuntimeDebug, sp_std::prelude::*};
-use product_registry::ProductId;
+use sp_core::sr25519;
+use product_registry::{LotId, ProductId};
 
 // Custom types
 pub type Identifier = Vec<u8>;
 pub type Decimal = I16F16;
+// Wider fixed-point type, for computations overflowing a Decimal
+pub type WideDecimal = I64F64;
 pub type ShipmentId = Identifier;
 pub type ShippingEventIndex = u128;
 pub type DeviceId = Identifier;
+pub type DevicePublicKey = sr25519::Public;
+pub type DeviceSignature = sr25519::Signature;
 
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
 pub enum ShipmentStatus {
     Pending,
     InTransit,
     Delivered,
+    Cancelled,
+    OnHold,
+    Returned,
+    Lost,
+}
+
+impl ShipmentStatus {
+    // Status a shipment moves to when the given operation takes place,
+    // None if the operation isn't allowed in the current status
+    pub fn transition(&self, operation: &ShippingOperation) -> Option<ShipmentStatus> {
+        use ShipmentStatus::*;
+        use ShippingOperation::*;
+
+        match (self, operation) {
+            (Pending, Pickup) => Some(InTransit),
+            (Pending, Scan) => Some(Pending),
+            (Pending, Cancel) => Some(Cancelled),
+
+            (InTransit, Scan) => Some(InTransit),
+            (InTransit, Deliver) => Some(Delivered),
+            (InTransit, Hold) => Some(OnHold),
+            (InTransit, Return) => Some(Returned),
+            (InTransit, ReportLoss) => Some(Lost),
+
+            (OnHold, Scan) => Some(OnHold),
+            (OnHold, Resume) => Some(InTransit),
+            (OnHold, Cancel) => Some(Cancelled),
+            (OnHold, Return) => Some(Returned),
+            (OnHold, ReportLoss) => Some(Lost),
+
+            // Delivered, cancelled, returned & lost shipments are closed
+            _ => None,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        matches!(
+            self,
+            ShipmentStatus::Delivered
+                | ShipmentStatus::Cancelled
+                | ShipmentStatus::Returned
+                | ShipmentStatus::Lost
+        )
+    }
 }
 
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
 pub struct Shipment<AccountId, Moment> {
     pub id: ShipmentId,
     pub owner: AccountId,
+    // Organization currently in custody of the shipment, the owner until handed off
+    pub custodian: AccountId,
     pub status: ShipmentStatus,
     pub products: Vec<ProductId>,
+    pub lots: Vec<LotId>,
     pub registered: Moment,
     pub delivered: Option<Moment>,
+    // Planned route, as points the shipment is expected to go through
+    pub waypoints: Vec<ReadPoint>,
+    pub expected_delivery: Option<Moment>,
 }
 
 impl<AccountId, Moment> Shipment<AccountId, Moment> {
-    pub fn pickup(mut self) -> Self {
-        self.status = ShipmentStatus::InTransit;
+    pub fn update_status(mut self, status: ShipmentStatus, timestamp: Moment) -> Self {
+        if status == ShipmentStatus::Delivered {
+            self.delivered = Some(timestamp);
+        }
+        self.status = status;
         self
     }
 
-    pub fn deliver(mut self, delivered_on: Moment) -> Self {
-        self.status = ShipmentStatus::Delivered;
-        self.delivered = Some(delivered_on);
+    pub fn hand_over(mut self, custodian: AccountId) -> Self {
+        self.custodian = custodian;
         self
     }
 }
 
+// Versions of the pallet's storage layout, used to run migrations on runtime upgrades
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Releases {
+    // Unversioned storage of the first release (spec_version 1), from before
+    // the version was tracked: shipments have neither custodian nor lots
+    V1,
+    // Shipments have a custodian & lots
+    V2,
+    // Shipments have a planned route & expected delivery
+    V3,
+}
+
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1
+    }
+}
+
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
 pub enum ShippingOperation {
     Pickup,
     Scan,
     Deliver,
+    Cancel,
+    Hold,
+    Resume,
+    Return,
+    ReportLoss,
 }
 
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
@@ -54,6 +134,14 @@ pub enum ShippingEventType {
     ShipmentPickup,
     ShipmentScan,
     ShipmentDeliver,
+    CustodyTransfer,
+    ShipmentCancel,
+    ShipmentHold,
+    ShipmentResume,
+    ShipmentReturn,
+    ShipmentLoss,
+    ShipmentConsolidation,
+    ShipmentDeconsolidation,
 }
 
 impl From<ShippingOperation> for ShippingEventType {
@@ -62,6 +150,11 @@ impl From<ShippingOperation> for ShippingEventType {
             ShippingOperation::Pickup => ShippingEventType::ShipmentPickup,
             ShippingOperation::Scan => ShippingEventType::ShipmentScan,
             ShippingOperation::Deliver => ShippingEventType::ShipmentDeliver,
+            ShippingOperation::Cancel => ShippingEventType::ShipmentCancel,
+            ShippingOperation::Hold => ShippingEventType::ShipmentHold,
+            ShippingOperation::Resume => ShippingEventType::ShipmentResume,
+            ShippingOperation::Return => ShippingEventType::ShipmentReturn,
+            ShippingOperation::ReportLoss => ShippingEventType::ShipmentLoss,
         }
     }
 }
@@ -90,6 +183,120 @@ pub struct ReadPoint {
     pub longitude: Decimal,
 }
 
+// Length of a degree of latitude, in meters, on a sphere of the Earth's mean radius
+const METERS_PER_DEGREE: u32 = 111_195;
+
+impl ReadPoint {
+    pub fn is_valid(&self) -> bool {
+        self.latitude.abs() <= Decimal::from_num(90) && self.longitude.abs() <= Decimal::from_num(180)
+    }
+
+    // Approximate distance to another point in meters, using an equirectangular
+    // projection: accurate enough over the distances geofences span
+    pub fn distance_to(&self, other: &ReadPoint) -> u64 {
+        let degree = WideDecimal::from_num(METERS_PER_DEGREE);
+        let lat = WideDecimal::from_num(self.latitude);
+        let other_lat = WideDecimal::from_num(other.latitude);
+
+        // Take the shortest way around, across the antimeridian if need be
+        let half_turn = WideDecimal::from_num(180);
+        let mut delta_lon = WideDecimal::from_num(other.longitude) - WideDecimal::from_num(self.longitude);
+        if delta_lon > half_turn {
+            delta_lon -= half_turn * WideDecimal::from_num(2);
+        } else if delta_lon < -half_turn {
+            delta_lon += half_turn * WideDecimal::from_num(2);
+        }
+
+        let mean_lat = (lat + other_lat) / WideDecimal::from_num(2);
+        let x = delta_lon * cos_degrees(mean_lat) * degree;
+        let y = (other_lat - lat) * degree;
+        sqrt((x * x + y * y).to_num::<u64>())
+    }
+}
+
+// Bhaskara I's approximation of the cosine of an angle between -90 & 90 degrees,
+// within 0.2% of the actual value
+fn cos_degrees(angle: WideDecimal) -> WideDecimal {
+    let squared = angle * angle;
+    let right_squared = WideDecimal::from_num(90 * 90 * 4);
+    (right_squared - squared * WideDecimal::from_num(4)) / (right_squared + squared)
+}
+
+// Integer square root, using Newton's method
+fn sqrt(n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = x / 2 + 1;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+// Area a shipment is expected to be in, either a circle or a polygon
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum Geofence {
+    // Center & radius in meters
+    Circle(ReadPoint, u32),
+    // Vertices, in order; polygons crossing the antimeridian aren't supported
+    Polygon(Vec<ReadPoint>),
+}
+
+impl Geofence {
+    pub fn contains(&self, point: &ReadPoint) -> bool {
+        match self {
+            Geofence::Circle(center, radius) => center.distance_to(point) <= *radius as u64,
+            Geofence::Polygon(vertices) => Self::polygon_contains(vertices, point),
+        }
+    }
+
+    // Ray casting: a point is inside a polygon if a ray cast from it
+    // crosses the polygon's edges an odd number of times
+    fn polygon_contains(vertices: &[ReadPoint], point: &ReadPoint) -> bool {
+        let x = WideDecimal::from_num(point.longitude);
+        let y = WideDecimal::from_num(point.latitude);
+        let mut inside = false;
+        for (i, a) in vertices.iter().enumerate() {
+            let b = &vertices[(i + 1) % vertices.len()];
+            let (ax, ay) = (WideDecimal::from_num(a.longitude), WideDecimal::from_num(a.latitude));
+            let (bx, by) = (WideDecimal::from_num(b.longitude), WideDecimal::from_num(b.latitude));
+            if (ay > y) != (by > y) && x < (bx - ax) * (y - ay) / (by - ay) + ax {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+}
+
+// Geofences along a shipment's route: scans outside of the origin, destination & corridor
+// fences deviate from the route, deliveries must take place within the destination fence
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ShipmentRoute {
+    pub origin: Option<Geofence>,
+    pub destination: Option<Geofence>,
+    pub corridor: Vec<Geofence>,
+}
+
+impl ShipmentRoute {
+    pub fn fences(&self) -> impl Iterator<Item = &Geofence> {
+        self.origin.iter().chain(self.destination.iter()).chain(self.corridor.iter())
+    }
+
+    // Whether the point is on route, any point being so when no corridor is set
+    pub fn is_on_route(&self, point: &ReadPoint) -> bool {
+        self.corridor.is_empty() || self.fences().any(|fence| fence.contains(point))
+    }
+
+    pub fn is_at_destination(&self, point: &ReadPoint) -> bool {
+        self.destination.as_ref().map_or(true, |fence| fence.contains(point))
+    }
+}
+
+// Readings are expected in degrees Celsius, % of relative humidity, kPa,
+// m/s² for shocks & vibrations, and degrees for tilts
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
 pub enum ReadingType {
     Humidity,
@@ -100,6 +307,20 @@ pub enum ReadingType {
     Vibration,
 }
 
+// Acceptable range of a shipment's readings of a given type, either bound being optional
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ConditionThreshold {
+    pub reading_type: ReadingType,
+    pub min: Option<Decimal>,
+    pub max: Option<Decimal>,
+}
+
+impl ConditionThreshold {
+    pub fn is_violated_by(&self, value: Decimal) -> bool {
+        self.min.map_or(false, |min| value < min) || self.max.map_or(false, |max| value > max)
+    }
+}
+
 #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
 pub struct Reading<Moment> {
     pub device_id: DeviceId,
@@ -108,3 +329,26 @@ pub struct Reading<Moment> {
     pub timestamp: Moment,
     pub value: Decimal,
 }
+
+impl<Moment: Encode> Reading<Moment> {
+    // Message a device signs for its reading to be accepted for the shipment
+    pub fn signing_payload(&self, shipment_id: &ShipmentId) -> Vec<u8> {
+        (shipment_id, &self.reading_type, &self.timestamp, &self.value).encode()
+    }
+}
+
+// Reading as submitted along a shipping event, signed by its device
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct SignedReading<Moment> {
+    pub reading: Reading<Moment>,
+    pub signature: DeviceSignature,
+}
+
+// Sensor device registered by an organization, whose readings must be signed with its key
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Device<AccountId> {
+    pub id: DeviceId,
+    pub owner: AccountId,
+    pub public_key: DevicePublicKey,
+    pub reading_types: Vec<ReadingType>,
+}
@@ -0,0 +1,127 @@
+//! Benchmarking setup for the registrar pallet.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+
+use frame_benchmarking::{account, benchmarks};
+use frame_support::traits::UnfilteredDispatchable;
+
+const SEED: u32 = 0;
+/// Organizations and members already registered, so that the benchmarked calls
+/// decode, search and re-encode lists of a realistic consortium size.
+const CONSORTIUM_SIZE: u32 = 100;
+
+fn setup_organizations<T: Trait>() {
+	for i in 0..CONSORTIUM_SIZE {
+		let org: T::AccountId = account("org", i, SEED);
+		Module::<T>::create_org(&org, b"Organization".to_vec()).expect("org does not exist yet; qed");
+	}
+}
+
+fn setup_members<T: Trait>(org: &T::AccountId) {
+	for i in 0..CONSORTIUM_SIZE {
+		let member: T::AccountId = account("member", i, SEED);
+		Module::<T>::add_to_org(org, &member).expect("account is not a member yet; qed");
+	}
+}
+
+/// Gives all organizations but the last one a distinct 12-digit company prefix.
+fn setup_company_prefixes<T: Trait>() {
+	for i in 0..CONSORTIUM_SIZE - 1 {
+		let mut prefix = b"1000000".to_vec();
+		prefix.extend((0..5).rev().map(|k| b'0' + (i / 10u32.pow(k) % 10) as u8));
+		Module::<T>::hold_company_prefix(&account("org", i, SEED), &prefix);
+	}
+}
+
+fn setup_certifiers<T: Trait>() {
+	let certifiers = (0..CONSORTIUM_SIZE)
+		.map(|i| account("certifier", i, SEED))
+		.collect::<Vec<T::AccountId>>();
+	<Certifiers<T>>::put(certifiers);
+}
+
+benchmarks! {
+	_ { }
+
+	create_organization {
+		setup_organizations::<T>();
+		let caller: T::AccountId = account("caller", 0, SEED);
+	}: _(RawOrigin::Signed(caller.clone()), b"Benchmark Org".to_vec())
+	verify {
+		assert!(Module::<T>::organizations().contains(&caller));
+	}
+
+	add_to_organization {
+		setup_organizations::<T>();
+		let org: T::AccountId = account("org", 0, SEED);
+		setup_members::<T>(&org);
+		let account: T::AccountId = account("new_member", 0, SEED);
+	}: _(RawOrigin::Signed(org.clone()), account.clone())
+	verify {
+		assert!(Module::<T>::member_of_organization(&org, &account));
+	}
+
+	set_company_prefix {
+		setup_organizations::<T>();
+		setup_company_prefixes::<T>();
+		// Worst case: the organization's previous 12-digit prefix is replaced by another one
+		let org: T::AccountId = account("org", CONSORTIUM_SIZE - 1, SEED);
+		Module::<T>::hold_company_prefix(&org, b"061414112345");
+		let call = Call::<T>::set_company_prefix(org.clone(), b"061414154321".to_vec());
+		let origin = T::CompanyPrefixOrigin::successful_origin();
+	}: { call.dispatch_bypass_filter(origin)? }
+	verify {
+		assert_eq!(Module::<T>::company_prefix(&org), Some(b"061414154321".to_vec()));
+	}
+
+	remove_company_prefix {
+		setup_organizations::<T>();
+		setup_company_prefixes::<T>();
+		let org: T::AccountId = account("org", 0, SEED);
+		let call = Call::<T>::remove_company_prefix(org.clone());
+		let origin = T::CompanyPrefixOrigin::successful_origin();
+	}: { call.dispatch_bypass_filter(origin)? }
+	verify {
+		assert_eq!(Module::<T>::company_prefix(&org), None);
+	}
+
+	add_certifier {
+		setup_certifiers::<T>();
+		let certifier: T::AccountId = account("new_certifier", 0, SEED);
+		let call = Call::<T>::add_certifier(certifier.clone());
+		let origin = T::CertifierOrigin::successful_origin();
+	}: { call.dispatch_bypass_filter(origin)? }
+	verify {
+		assert!(Module::<T>::is_certifier(&certifier));
+	}
+
+	remove_certifier {
+		setup_certifiers::<T>();
+		let certifier: T::AccountId = account("certifier", CONSORTIUM_SIZE - 1, SEED);
+		let call = Call::<T>::remove_certifier(certifier.clone());
+		let origin = T::CertifierOrigin::successful_origin();
+	}: { call.dispatch_bypass_filter(origin)? }
+	verify {
+		assert!(!Module::<T>::is_certifier(&certifier));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock::{new_test_ext, Test};
+	use frame_support::assert_ok;
+
+	// Each benchmark sets up its own consortium, hence runs on fresh storage
+	#[test]
+	fn test_benchmarks() {
+		new_test_ext().execute_with(|| assert_ok!(test_benchmark_create_organization::<Test>()));
+		new_test_ext().execute_with(|| assert_ok!(test_benchmark_add_to_organization::<Test>()));
+		new_test_ext().execute_with(|| assert_ok!(test_benchmark_set_company_prefix::<Test>()));
+		new_test_ext().execute_with(|| assert_ok!(test_benchmark_remove_company_prefix::<Test>()));
+		new_test_ext().execute_with(|| assert_ok!(test_benchmark_add_certifier::<Test>()));
+		new_test_ext().execute_with(|| assert_ok!(test_benchmark_remove_certifier::<Test>()));
+	}
+}
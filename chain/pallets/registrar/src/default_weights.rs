@@ -0,0 +1,39 @@
+//! Default weights for the registrar pallet, hand-estimated rather than benchmarked, to be used in tests & mock runtimes.
+//! Runtimes should use weights generated by benchmarking on their reference hardware.
+
+#![allow(unused_parens)]
+
+use frame_support::weights::{constants::RocksDbWeight as DbWeight, Weight};
+
+impl crate::WeightInfo for () {
+	fn create_organization() -> Weight {
+		(71_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn add_to_organization() -> Weight {
+		(83_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(4 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn set_company_prefix() -> Weight {
+		(96_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(28 as Weight))
+			.saturating_add(DbWeight::get().writes(20 as Weight))
+	}
+	fn remove_company_prefix() -> Weight {
+		(48_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(9 as Weight))
+			.saturating_add(DbWeight::get().writes(10 as Weight))
+	}
+	fn add_certifier() -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn remove_certifier() -> Weight {
+		(32_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+}
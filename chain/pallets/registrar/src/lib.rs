@@ -4,14 +4,44 @@ use sp_std::{prelude::*, vec::Vec, if_std};
 use frame_support::{
 	decl_module, decl_event, decl_storage, decl_error,
 	ensure, dispatch,
-	traits::EnsureOrigin
+	traits::EnsureOrigin,
+	weights::Weight,
 };
 use frame_system::{self as system, ensure_signed, RawOrigin};
 
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+mod benchmarking;
+mod default_weights;
+
+/// Shortest & longest GS1 Company Prefixes.
+pub const COMPANY_PREFIX_MIN_LENGTH: usize = 4;
+pub const COMPANY_PREFIX_MAX_LENGTH: usize = 12;
+
+/// Weight functions needed for this pallet.
+pub trait WeightInfo {
+	fn create_organization() -> Weight;
+	fn add_to_organization() -> Weight;
+	fn set_company_prefix() -> Weight;
+	fn remove_company_prefix() -> Weight;
+	fn add_certifier() -> Weight;
+	fn remove_certifier() -> Weight;
+}
+
 /// Configure the pallet by specifying the parameters and types on which it depends.
 pub trait Trait: system::Trait + did::Trait {
 	/// Because this pallet emits events, it depends on the runtime's definition of an event.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// The origin which may accredit and withdraw product certifiers, e.g. root or governance.
+	type CertifierOrigin: EnsureOrigin<Self::Origin>;
+	/// The origin which may assign, correct and remove GS1 Company Prefixes, e.g. root or governance.
+	type CompanyPrefixOrigin: EnsureOrigin<Self::Origin>;
+	/// Weight information for the extrinsics of this pallet.
+	type WeightInfo: WeightInfo;
 }
 
 // Errors inform users why an extrinsic failed.
@@ -23,6 +53,16 @@ decl_error! {
 		InvalidOrganization,
 		/// Cannot add a user to an organization to which they already belong.
 		MemberOfOrganization,
+		/// The GS1 Company Prefix must be made of 4 to 12 digits.
+		InvalidCompanyPrefix,
+		/// Cannot remove the GS1 Company Prefix of an organization that has none.
+		NoCompanyPrefix,
+		/// The GS1 Company Prefix is, contains or is contained in another organization's prefix.
+		CompanyPrefixOverlaps,
+		/// Cannot accredit an account that already is a certifier.
+		CertifierExists,
+		/// Cannot withdraw the accreditation of an account that is not a certifier.
+		InvalidCertifier,
 	}
 }
 
@@ -34,6 +74,14 @@ decl_event!(
 		CreatedOrganization(AccountId, Vec<u8>),
 		/// An account was added to an organization. [account, organization_id]
 		AddedToOrganization(AccountId, Vec<u8>),
+		/// A GS1 Company Prefix has been set for an organization. [organization_id, prefix]
+		CompanyPrefixSet(AccountId, Vec<u8>),
+		/// The GS1 Company Prefix of an organization has been removed. [organization_id, prefix]
+		CompanyPrefixRemoved(AccountId, Vec<u8>),
+		/// An account has been accredited as a product certifier. [account]
+		CertifierAdded(AccountId),
+		/// The accreditation of a product certifier has been withdrawn. [account]
+		CertifierRemoved(AccountId),
 	}
 );
 
@@ -46,6 +94,16 @@ decl_storage! {
 			pub Organizations get(fn organizations): Vec<T::AccountId>;
 			/// Maps organizations to their members.
 			pub MembersOf get(fn members_of):map hasher(blake2_128_concat) T::AccountId => Vec<T::AccountId>;
+			/// The organizations holding GS1 Company Prefixes, by prefix.
+			/// No prefix is, contains or is contained in another one.
+			pub CompanyPrefixHolders get(fn company_prefix_holder): map hasher(blake2_128_concat) Vec<u8> => Option<T::AccountId>;
+			/// The GS1 Company Prefix held by each organization.
+			pub CompanyPrefixOf get(fn company_prefix): map hasher(blake2_128_concat) T::AccountId => Option<Vec<u8>>;
+			/// The number of held prefixes extending each shorter string of digits, so that
+			/// prefixes contained in a new one are found without iterating over all of them.
+			pub CompanyPrefixExtensions get(fn company_prefix_extensions): map hasher(blake2_128_concat) Vec<u8> => u32;
+			/// The accounts accredited as product certifiers by the CertifierOrigin.
+			pub Certifiers get(fn certifiers): Vec<T::AccountId>;
 	}
 	add_extra_genesis {
 		config(orgs): Vec<(T::AccountId, Vec<u8>)>;
@@ -82,7 +140,7 @@ decl_module! {
 		/// been created. Will emit a CreatedOrganization event on success.
 		///
 		/// The dispatch origin for this call must be Signed.
-		#[weight = 10_000]
+		#[weight = T::WeightInfo::create_organization()]
 		pub fn create_organization(origin, org_name: Vec<u8>) -> dispatch::DispatchResult {
 			let who = ensure_signed(origin)?;
 			Self::create_org(&who, org_name.clone())?;
@@ -94,13 +152,103 @@ decl_module! {
 		/// does not exist or the account is already a member. Will emit a AddedToOrganization event on success.
 		///
 		/// The dispatch origin for this call must be Signed.
-		#[weight = 10_000]
+		#[weight = T::WeightInfo::add_to_organization()]
 		pub fn add_to_organization(origin, account: T::AccountId) -> dispatch::DispatchResult {
 			let who = ensure_signed(origin)?;
 			Self::add_to_org(&who, &account)?;
 			Self::deposit_event(RawEvent::AddedToOrganization(who, b"OrgMember".to_vec()));
 			Ok(())
 		}
+
+		/// Set the GS1 Company Prefix of an organization, replacing any previous one. Will return an
+		/// InvalidOrganization error if the account is not an organization, an InvalidCompanyPrefix error
+		/// if the prefix is not made of 4 to 12 digits, or a CompanyPrefixOverlaps error if another
+		/// organization holds a prefix that is, contains or is contained in this one. Will emit a
+		/// CompanyPrefixSet event on success.
+		///
+		/// The dispatch origin for this call must be the CompanyPrefixOrigin.
+		#[weight = T::WeightInfo::set_company_prefix()]
+		pub fn set_company_prefix(origin, org: T::AccountId, prefix: Vec<u8>) -> dispatch::DispatchResult {
+			T::CompanyPrefixOrigin::ensure_origin(origin)?;
+			ensure!(Self::organizations().contains(&org), Error::<T>::InvalidOrganization);
+			ensure!(
+				(COMPANY_PREFIX_MIN_LENGTH..=COMPANY_PREFIX_MAX_LENGTH).contains(&prefix.len())
+					&& prefix.iter().all(u8::is_ascii_digit),
+				Error::<T>::InvalidCompanyPrefix
+			);
+
+			// Prefixes must be exclusive: a GTIN may only fall under a single one.
+			// The organization's previous prefix is replaced, so it doesn't overlap.
+			let previous = Self::company_prefix(&org);
+			ensure!(
+				(COMPANY_PREFIX_MIN_LENGTH..=prefix.len())
+					.filter_map(|len| Self::company_prefix_holder(&prefix[..len]))
+					.all(|holder| holder == org),
+				Error::<T>::CompanyPrefixOverlaps
+			);
+			let previous_extends = previous.as_ref()
+				.map_or(false, |p| p.len() > prefix.len() && p.starts_with(&prefix));
+			ensure!(
+				Self::company_prefix_extensions(&prefix) == previous_extends as u32,
+				Error::<T>::CompanyPrefixOverlaps
+			);
+
+			if let Some(previous) = previous {
+				Self::release_company_prefix(&org, &previous);
+			}
+			Self::hold_company_prefix(&org, &prefix);
+			Self::deposit_event(RawEvent::CompanyPrefixSet(org, prefix));
+			Ok(())
+		}
+
+		/// Remove the GS1 Company Prefix of an organization. Will return a NoCompanyPrefix error if
+		/// the organization holds no prefix. Will emit a CompanyPrefixRemoved event on success.
+		///
+		/// The dispatch origin for this call must be the CompanyPrefixOrigin.
+		#[weight = T::WeightInfo::remove_company_prefix()]
+		pub fn remove_company_prefix(origin, org: T::AccountId) -> dispatch::DispatchResult {
+			T::CompanyPrefixOrigin::ensure_origin(origin)?;
+
+			let prefix = Self::company_prefix(&org).ok_or(Error::<T>::NoCompanyPrefix)?;
+			Self::release_company_prefix(&org, &prefix);
+
+			Self::deposit_event(RawEvent::CompanyPrefixRemoved(org, prefix));
+			Ok(())
+		}
+
+		/// Accredit an account as a product certifier. Will return a CertifierExists error if the
+		/// account already is a certifier. Will emit a CertifierAdded event on success.
+		///
+		/// The dispatch origin for this call must be the CertifierOrigin.
+		#[weight = T::WeightInfo::add_certifier()]
+		pub fn add_certifier(origin, account: T::AccountId) -> dispatch::DispatchResult {
+			T::CertifierOrigin::ensure_origin(origin)?;
+
+			let mut certifiers = Self::certifiers();
+			ensure!(!certifiers.contains(&account), Error::<T>::CertifierExists);
+			certifiers.push(account.clone());
+			<Certifiers<T>>::put(certifiers);
+
+			Self::deposit_event(RawEvent::CertifierAdded(account));
+			Ok(())
+		}
+
+		/// Withdraw the accreditation of a product certifier. Will return an InvalidCertifier error if
+		/// the account is not a certifier. Will emit a CertifierRemoved event on success.
+		///
+		/// The dispatch origin for this call must be the CertifierOrigin.
+		#[weight = T::WeightInfo::remove_certifier()]
+		pub fn remove_certifier(origin, account: T::AccountId) -> dispatch::DispatchResult {
+			T::CertifierOrigin::ensure_origin(origin)?;
+
+			let mut certifiers = Self::certifiers();
+			let index = certifiers.iter().position(|c| c == &account).ok_or(Error::<T>::InvalidCertifier)?;
+			certifiers.remove(index);
+			<Certifiers<T>>::put(certifiers);
+
+			Self::deposit_event(RawEvent::CertifierRemoved(account));
+			Ok(())
+		}
 	}
 }
 
@@ -138,6 +286,37 @@ impl<T: Trait> Module<T> {
 		Ok(())
 	}
 
+	/// Records the organization as the holder of the GS1 Company Prefix.
+	pub(crate) fn hold_company_prefix(org: &T::AccountId, prefix: &[u8]) {
+		<CompanyPrefixHolders<T>>::insert(prefix, org);
+		<CompanyPrefixOf<T>>::insert(org, prefix);
+		for len in COMPANY_PREFIX_MIN_LENGTH..prefix.len() {
+			CompanyPrefixExtensions::mutate(&prefix[..len], |count| *count += 1);
+		}
+	}
+
+	/// Withdraws the GS1 Company Prefix held by the organization.
+	fn release_company_prefix(org: &T::AccountId, prefix: &[u8]) {
+		<CompanyPrefixHolders<T>>::remove(prefix);
+		<CompanyPrefixOf<T>>::remove(org);
+		for len in COMPANY_PREFIX_MIN_LENGTH..prefix.len() {
+			match Self::company_prefix_extensions(&prefix[..len]) {
+				0 | 1 => CompanyPrefixExtensions::remove(&prefix[..len]),
+				count => CompanyPrefixExtensions::insert(&prefix[..len], count - 1),
+			}
+		}
+	}
+
+	/// Returns true if and only if the account has been accredited as a certifier by the CertifierOrigin.
+	pub fn is_certifier(account: &T::AccountId) -> bool {
+		Self::certifiers().contains(account)
+	}
+
+	/// Returns true if and only if the account is the given organization or one of its members.
+	pub fn member_of_organization(org: &T::AccountId, account: &T::AccountId) -> bool {
+		org == account || <did::Module<T>>::valid_delegate(org, &b"OrgMember".to_vec(), &account).is_ok()
+	}
+
 	/// Returns true if and only if the account is a member of an organization.
 	pub fn part_of_organization(account: &T::AccountId) -> bool {
 		let orgs = <Module<T>>::organizations();
@@ -170,6 +349,17 @@ impl<T: Trait> EnsureOrigin<T::Origin> for EnsureOrg<T> {
 
 	#[cfg(feature = "runtime-benchmarks")]
 	fn successful_origin() -> T::Origin {
-		T::Origin::from(RawOrigin::Signed(Default::default()))
+		// A member of the default organization, creating both if needed.
+		let org: T::AccountId = Default::default();
+		let member: T::AccountId = frame_benchmarking::account("member", 0, 0);
+		if !<Module<T>>::organizations().contains(&org) {
+			<Module<T>>::create_org(&org, b"Benchmark Org".to_vec())
+				.expect("organization does not exist yet; qed");
+		}
+		if !<Module<T>>::members_of(&org).contains(&member) {
+			<Module<T>>::add_to_org(&org, &member)
+				.expect("organization exists and account is not a member yet; qed");
+		}
+		T::Origin::from(RawOrigin::Signed(member))
 	}
 }
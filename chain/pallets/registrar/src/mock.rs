@@ -0,0 +1,103 @@
+// Creating mock runtime here
+
+use crate::{Module, Trait};
+use frame_support::{impl_outer_event, impl_outer_origin, parameter_types, weights::Weight};
+use frame_system as system;
+use sp_core::{sr25519, Pair, H256};
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	AccountId32, MultiSignature, MultiSigner, Perbill,
+};
+
+use crate as registrar;
+
+impl_outer_origin! {
+	pub enum Origin for Test {}
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		system<T>,
+		did<T>,
+		registrar<T>,
+	}
+}
+
+// For testing the pallet, we construct most of a mock runtime. This means
+// first constructing a configuration type (`Test`) which `impl`s each of the
+// configuration traits of pallets we want to use.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+}
+
+impl system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Call = ();
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = AccountId32;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type PalletInfo = ();
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+impl did::Trait for Test {
+	type Event = TestEvent;
+	type Public = MultiSigner;
+	type Signature = MultiSignature;
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type CertifierOrigin = system::EnsureRoot<AccountId32>;
+	type CompanyPrefixOrigin = system::EnsureRoot<AccountId32>;
+	type WeightInfo = ();
+}
+
+pub type System = system::Module<Test>;
+pub type Did = did::Module<Test>;
+pub type Registrar = Module<Test>;
+
+// This function basically just builds a genesis storage key/value store according to
+// our desired mockup.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let storage = system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+
+	let mut ext = sp_io::TestExternalities::from(storage);
+	// Events are not emitted on block 0 -> advance to block 1.
+	// Any dispatchable calls made during genesis block will have no events emitted.
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+pub fn account_key(s: &str) -> AccountId32 {
+	sr25519::Pair::from_string(&format!("//{}", s), None)
+		.expect("static values are valid; qed")
+		.public()
+		.into()
+}
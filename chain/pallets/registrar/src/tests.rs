@@ -0,0 +1,185 @@
+// Tests to be written here
+
+use super::*;
+use crate::{mock::*, Error};
+use frame_support::{assert_noop, assert_ok, dispatch::DispatchError};
+use sp_runtime::AccountId32;
+
+const TEST_CERTIFIER: &str = "Bureau";
+const TEST_ORGANIZATION: &str = "Northwind";
+const TEST_OTHER_ORGANIZATION: &str = "Contoso";
+
+fn create_test_organization(name: &str) -> AccountId32 {
+	let org = account_key(name);
+	assert_ok!(Registrar::create_organization(Origin::signed(org.clone()), name.as_bytes().to_vec()));
+	org
+}
+
+#[test]
+fn add_certifier() {
+	new_test_ext().execute_with(|| {
+		let certifier = account_key(TEST_CERTIFIER);
+
+		assert_ok!(Registrar::add_certifier(Origin::root(), certifier.clone()));
+
+		assert!(Registrar::is_certifier(&certifier));
+		assert!(System::events().iter().any(|er| er.event
+			== TestEvent::registrar(RawEvent::CertifierAdded(certifier.clone()))));
+
+		assert_noop!(
+			Registrar::add_certifier(Origin::root(), certifier),
+			Error::<Test>::CertifierExists
+		);
+	});
+}
+
+#[test]
+fn add_certifier_without_certifier_origin() {
+	new_test_ext().execute_with(|| {
+		let certifier = account_key(TEST_CERTIFIER);
+
+		assert_noop!(
+			Registrar::add_certifier(Origin::signed(certifier.clone()), certifier),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn self_issued_certifier_attribute_is_not_an_accreditation() {
+	new_test_ext().execute_with(|| {
+		let certifier = account_key(TEST_CERTIFIER);
+
+		// Anyone may write attributes of their own DID
+		assert_ok!(Did::add_attribute(
+			Origin::signed(certifier.clone()),
+			certifier.clone(),
+			b"Certifier".to_vec(),
+			b"Self-accredited".to_vec(),
+			None
+		));
+
+		assert!(!Registrar::is_certifier(&certifier));
+	});
+}
+
+#[test]
+fn remove_certifier() {
+	new_test_ext().execute_with(|| {
+		let certifier = account_key(TEST_CERTIFIER);
+
+		assert_noop!(
+			Registrar::remove_certifier(Origin::root(), certifier.clone()),
+			Error::<Test>::InvalidCertifier
+		);
+
+		assert_ok!(Registrar::add_certifier(Origin::root(), certifier.clone()));
+		assert_noop!(
+			Registrar::remove_certifier(Origin::signed(certifier.clone()), certifier.clone()),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(Registrar::remove_certifier(Origin::root(), certifier.clone()));
+
+		assert!(!Registrar::is_certifier(&certifier));
+		assert!(System::events().iter().any(|er| er.event
+			== TestEvent::registrar(RawEvent::CertifierRemoved(certifier.clone()))));
+	});
+}
+
+#[test]
+fn set_company_prefix() {
+	new_test_ext().execute_with(|| {
+		let org = create_test_organization(TEST_ORGANIZATION);
+
+		assert_noop!(
+			Registrar::set_company_prefix(Origin::signed(org.clone()), org.clone(), b"0012345".to_vec()),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Registrar::set_company_prefix(Origin::root(), account_key(TEST_OTHER_ORGANIZATION), b"0012345".to_vec()),
+			Error::<Test>::InvalidOrganization
+		);
+		for prefix in &[&b"001"[..], b"0012345678901", b"00123A5"] {
+			assert_noop!(
+				Registrar::set_company_prefix(Origin::root(), org.clone(), prefix.to_vec()),
+				Error::<Test>::InvalidCompanyPrefix
+			);
+		}
+
+		assert_ok!(Registrar::set_company_prefix(Origin::root(), org.clone(), b"0012345".to_vec()));
+
+		assert_eq!(Registrar::company_prefix(&org), Some(b"0012345".to_vec()));
+		assert_eq!(Registrar::company_prefix_holder(b"0012345".to_vec()), Some(org.clone()));
+		assert!(System::events().iter().any(|er| er.event
+			== TestEvent::registrar(RawEvent::CompanyPrefixSet(org.clone(), b"0012345".to_vec()))));
+	});
+}
+
+#[test]
+fn set_overlapping_company_prefix() {
+	new_test_ext().execute_with(|| {
+		let org = create_test_organization(TEST_ORGANIZATION);
+		let other_org = create_test_organization(TEST_OTHER_ORGANIZATION);
+		assert_ok!(Registrar::set_company_prefix(Origin::root(), org, b"0012345".to_vec()));
+
+		// Same prefix, prefix contained in it & prefix containing it
+		for prefix in &[&b"0012345"[..], b"00123", b"001234567"] {
+			assert_noop!(
+				Registrar::set_company_prefix(Origin::root(), other_org.clone(), prefix.to_vec()),
+				Error::<Test>::CompanyPrefixOverlaps
+			);
+		}
+
+		assert_ok!(Registrar::set_company_prefix(Origin::root(), other_org, b"0012346".to_vec()));
+	});
+}
+
+#[test]
+fn correct_company_prefix() {
+	new_test_ext().execute_with(|| {
+		let org = create_test_organization(TEST_ORGANIZATION);
+		let other_org = create_test_organization(TEST_OTHER_ORGANIZATION);
+		assert_ok!(Registrar::set_company_prefix(Origin::root(), org.clone(), b"0012345".to_vec()));
+
+		// An organization's previous prefix doesn't overlap its new one
+		assert_ok!(Registrar::set_company_prefix(Origin::root(), org.clone(), b"00123456".to_vec()));
+		assert_ok!(Registrar::set_company_prefix(Origin::root(), org.clone(), b"00123".to_vec()));
+
+		assert_eq!(Registrar::company_prefix(&org), Some(b"00123".to_vec()));
+		assert_eq!(Registrar::company_prefix_holder(b"0012345".to_vec()), None);
+		assert_eq!(Registrar::company_prefix_holder(b"00123456".to_vec()), None);
+		assert_eq!(Registrar::company_prefix_holder(b"00123".to_vec()), Some(org.clone()));
+
+		// Previous prefixes are released
+		assert_ok!(Registrar::set_company_prefix(Origin::root(), org, b"0054321".to_vec()));
+		assert_ok!(Registrar::set_company_prefix(Origin::root(), other_org, b"00123".to_vec()));
+	});
+}
+
+#[test]
+fn remove_company_prefix() {
+	new_test_ext().execute_with(|| {
+		let org = create_test_organization(TEST_ORGANIZATION);
+		let other_org = create_test_organization(TEST_OTHER_ORGANIZATION);
+
+		assert_noop!(
+			Registrar::remove_company_prefix(Origin::root(), org.clone()),
+			Error::<Test>::NoCompanyPrefix
+		);
+
+		assert_ok!(Registrar::set_company_prefix(Origin::root(), org.clone(), b"0012345".to_vec()));
+		assert_noop!(
+			Registrar::remove_company_prefix(Origin::signed(org.clone()), org.clone()),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(Registrar::remove_company_prefix(Origin::root(), org.clone()));
+
+		assert_eq!(Registrar::company_prefix(&org), None);
+		assert_eq!(Registrar::company_prefix_holder(b"0012345".to_vec()), None);
+		assert!(System::events().iter().any(|er| er.event
+			== TestEvent::registrar(RawEvent::CompanyPrefixRemoved(org.clone(), b"0012345".to_vec()))));
+
+		// The prefix, and those it contained, are free again
+		assert_ok!(Registrar::set_company_prefix(Origin::root(), other_org, b"00123".to_vec()));
+	});
+}
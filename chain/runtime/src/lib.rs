@@ -24,6 +24,9 @@ use sp_version::RuntimeVersion;
 #[cfg(feature = "std")]
 use sp_version::NativeVersion;
 
+/// Weights for the sample's pallets, hand-estimated until benchmarked.
+pub mod weights;
+
 // A few exports that help ease life for downstream crates.
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
@@ -63,6 +66,9 @@ pub type Balance = u128;
 /// Index of a transaction in the chain.
 pub type Index = u32;
 
+/// A timestamp: milliseconds since the unix epoch.
+pub type Moment = u64;
+
 /// A hash of some data used by the chain.
 pub type Hash = sp_core::H256;
 
@@ -98,10 +104,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     spec_name: create_runtime_str!("enterprise-sample"),
     impl_name: create_runtime_str!("enterprise-sample"),
     authoring_version: 1,
-    spec_version: 1,
+    spec_version: 2,
     impl_version: 1,
     apis: RUNTIME_API_VERSIONS,
-    transaction_version: 1,
+    transaction_version: 2,
 };
 
 pub const MILLISECS_PER_BLOCK: u64 = 6000;
@@ -225,8 +231,7 @@ parameter_types! {
 }
 
 impl pallet_timestamp::Trait for Runtime {
-    /// A timestamp: milliseconds since the unix epoch.
-    type Moment = u64;
+    type Moment = Moment;
     type OnTimestampSet = Aura;
     type MinimumPeriod = MinimumPeriod;
     type WeightInfo = ();
@@ -431,16 +436,100 @@ impl pallet_did::Trait for Runtime {
 
 impl registrar::Trait for Runtime {
     type Event = Event;
+    type CertifierOrigin = frame_system::EnsureRoot<AccountId>;
+    type CompanyPrefixOrigin = frame_system::EnsureRoot<AccountId>;
+    type WeightInfo = weights::pallet_registrar::WeightInfo;
+}
+
+/// Resolves organization membership for the product registry using the registrar.
+pub struct RegistrarOrganizations;
+
+impl product_registry::Organizations<AccountId> for RegistrarOrganizations {
+    fn is_member(org: &AccountId, account: &AccountId) -> bool {
+        Registrar::member_of_organization(org, account)
+    }
+
+    fn is_organization(account: &AccountId) -> bool {
+        Registrar::organizations().contains(account)
+    }
+
+    fn company_prefix(org: &AccountId) -> Option<Vec<u8>> {
+        Registrar::company_prefix(org)
+    }
+
+    fn company_prefix_holder(prefix: &[u8]) -> Option<AccountId> {
+        Registrar::company_prefix_holder(prefix)
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn add_organization(account: &AccountId) {
+        Registrar::create_org(account, b"Benchmark Org".to_vec())
+            .expect("account is not an organization yet; qed");
+    }
+}
+
+pub struct RegistrarCertifiers;
+
+impl product_registry::Certifiers<AccountId> for RegistrarCertifiers {
+    fn is_certifier(account: &AccountId) -> bool {
+        Registrar::is_certifier(account)
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn add_certifier(account: &AccountId) {
+        Registrar::add_certifier(Origin::root(), account.clone())
+            .expect("root is the certifier origin; qed");
+    }
+}
+
+parameter_types! {
+    pub const ProductIdMaxLength: u32 = 36;
+    pub const ProductPropNameMaxLength: u32 = 10;
+    pub const ProductPropValueMaxLength: u32 = 20;
+    pub const ProductMaxProps: u32 = 3;
+    pub const RecallReasonMaxLength: u32 = 140;
+    pub const ProductBatchMaxSize: u32 = 100;
+    pub const LotNumberMaxLength: u32 = 20;
+    pub const CertificationMaxLength: u32 = 64;
+    pub const ProductMaxCertifications: u32 = 16;
+    pub const ProductMaxComponents: u32 = 20;
+    pub const ProductMaxComponentNodes: u32 = 200;
+    pub const DocumentTypeMaxLength: u32 = 10;
+    pub const DocumentUriMaxLength: u32 = 256;
+    pub const ProductMaxDocuments: u32 = 20;
+    pub IndexedPropNames: Vec<product_registry::PropName> = vec![b"category".to_vec()];
 }
 
 impl product_registry::Trait for Runtime {
     type Event = Event;
     type CreateRoleOrigin = registrar::EnsureOrg<Runtime>;
+    type Organizations = RegistrarOrganizations;
+    type OnProductRecalled = ProductTracking;
+    type Certifiers = RegistrarCertifiers;
+    type ProductIdMaxLength = ProductIdMaxLength;
+    type ProductPropNameMaxLength = ProductPropNameMaxLength;
+    type ProductPropValueMaxLength = ProductPropValueMaxLength;
+    type ProductMaxProps = ProductMaxProps;
+    type RecallReasonMaxLength = RecallReasonMaxLength;
+    type ProductBatchMaxSize = ProductBatchMaxSize;
+    type LotNumberMaxLength = LotNumberMaxLength;
+    type CertificationMaxLength = CertificationMaxLength;
+    type ProductMaxCertifications = ProductMaxCertifications;
+    type ProductMaxComponents = ProductMaxComponents;
+    type ProductMaxComponentNodes = ProductMaxComponentNodes;
+    type DocumentTypeMaxLength = DocumentTypeMaxLength;
+    type DocumentUriMaxLength = DocumentUriMaxLength;
+    type ProductMaxDocuments = ProductMaxDocuments;
+    type IndexedPropNames = IndexedPropNames;
+    type WeightInfo = weights::pallet_product_registry::WeightInfo;
 }
 
 impl product_tracking::Trait for Runtime {
     type Event = Event;
     type CreateRoleOrigin = registrar::EnsureOrg<Runtime>;
+    type Products = ProductRegistry;
+    type Organizations = RegistrarOrganizations;
+    type WeightInfo = weights::pallet_product_tracking::WeightInfo;
 }
 
 impl rbac::Trait for Runtime {
@@ -494,8 +583,8 @@ construct_runtime!(
         PalletDid: pallet_did::{Module, Call, Storage, Event<T>},
         TransactionPayment: pallet_transaction_payment::{Module, Storage},
         Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
-        ProductRegistry: product_registry::{Module, Call, Storage, Event<T>},
-        ProductTracking: product_tracking::{Module, Call, Storage, Event<T>},
+        ProductRegistry: product_registry::{Module, Call, Storage, Event<T>, Config},
+        ProductTracking: product_tracking::{Module, Call, Storage, Event<T>, Config},
         Registrar: registrar::{Module, Call, Storage, Event<T>, Config<T>},
         Rbac: rbac::{Module, Call, Storage, Event<T>, Config<T>},
     }
@@ -658,4 +747,60 @@ impl_runtime_apis! {
             TransactionPayment::query_info(uxt, len)
         }
     }
+
+    impl product_registry_runtime_api::ProductRegistryApi<Block, AccountId, Moment> for Runtime {
+        fn products_of_org(org: AccountId) -> Vec<product_registry::Product<AccountId, Moment>> {
+            ProductRegistry::products_of_org(org)
+                .iter()
+                .filter_map(ProductRegistry::product_by_id)
+                .collect()
+        }
+
+        fn get_product(id: product_registry::ProductId) -> Option<product_registry::Product<AccountId, Moment>> {
+            ProductRegistry::product_by_id(id)
+        }
+
+        fn products_by_prop(
+            org: AccountId,
+            name: product_registry::PropName,
+            value: product_registry::PropValue<Moment>,
+        ) -> Vec<product_registry::Product<AccountId, Moment>> {
+            ProductRegistry::products_by_prop(org, name, &value)
+                .iter()
+                .filter_map(ProductRegistry::product_by_id)
+                .collect()
+        }
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    impl frame_benchmarking::Benchmark<Block> for Runtime {
+        fn dispatch_benchmark(
+            config: frame_benchmarking::BenchmarkConfig
+        ) -> Result<Vec<frame_benchmarking::BenchmarkBatch>, sp_runtime::RuntimeString> {
+            use frame_benchmarking::{Benchmarking, BenchmarkBatch, add_benchmark};
+
+            let whitelist: Vec<Vec<u8>> = vec![
+                // Block Number
+                hex_literal::hex!("26aa394eea5630e07c48ae0c9558cef702a5c1b19ab7a04f536c519aca4983ac").to_vec(),
+                // Total Issuance
+                hex_literal::hex!("c2261276cc9d1f8598ea4b6a74b15c2f57c875e4cff74148e4628f264b974c80").to_vec(),
+                // Execution Phase
+                hex_literal::hex!("26aa394eea5630e07c48ae0c9558cef7ff553b5a9862a516939d82b3d3d8661a").to_vec(),
+                // Event Count
+                hex_literal::hex!("26aa394eea5630e07c48ae0c9558cef70a98fdbe9ce6c55837576c60c7af3850").to_vec(),
+                // System Events
+                hex_literal::hex!("26aa394eea5630e07c48ae0c9558cef780d41e5e16056765bc8461851072c9d7").to_vec(),
+            ];
+
+            let mut batches = Vec::<BenchmarkBatch>::new();
+            let params = (&config, &whitelist);
+
+            add_benchmark!(params, batches, product_registry, ProductRegistry);
+            add_benchmark!(params, batches, product_tracking, ProductTracking);
+            add_benchmark!(params, batches, registrar, Registrar);
+
+            if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
+            Ok(batches)
+        }
+    }
 }
@@ -0,0 +1,16 @@
+//! Weights of the sample's own pallets.
+//!
+//! The benchmarks haven't been run yet: these are hand-estimated placeholders, identical to the
+//! pallets' default weights. Generate the actual weights of all three pallets on the reference
+//! hardware, and again after changing a pallet's extrinsics, from the `chain` directory:
+//!
+//! ```bash
+//! ./scripts/benchmark.sh
+//! ```
+//!
+//! which builds the node with the `runtime-benchmarks` feature and runs its `benchmark` subcommand
+//! with `--output runtime/src/weights/pallet_<pallet>.rs` for each pallet.
+
+pub mod pallet_product_registry;
+pub mod pallet_product_tracking;
+pub mod pallet_registrar;
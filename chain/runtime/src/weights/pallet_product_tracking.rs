@@ -0,0 +1,99 @@
+//! Weights for product_tracking
+//!
+//! Hand-estimated placeholders mirroring the pallet's default weights, not benchmark results.
+//! Laid out as the Substrate benchmark CLI (v2.0.0) outputs them, so that running the benchmarks
+//! as described in `weights/mod.rs` overwrites this file.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::weights::{Weight, constants::RocksDbWeight as DbWeight};
+
+pub struct WeightInfo;
+impl product_tracking::WeightInfo for WeightInfo {
+	fn register_shipment(p: u32, ) -> Weight {
+		(68_000_000 as Weight)
+			.saturating_add((9_500_000 as Weight).saturating_mul(p as Weight))
+			.saturating_add(DbWeight::get().reads(5 as Weight))
+			.saturating_add(DbWeight::get().reads((1 as Weight).saturating_mul(p as Weight)))
+			.saturating_add(DbWeight::get().writes(7 as Weight))
+			.saturating_add(DbWeight::get().writes((1 as Weight).saturating_mul(p as Weight)))
+	}
+	fn track_shipment(r: u32, c: u32, ) -> Weight {
+		(70_000_000 as Weight)
+			.saturating_add((62_000_000 as Weight).saturating_mul(r as Weight))
+			.saturating_add((15_000_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add(DbWeight::get().reads(10 as Weight))
+			.saturating_add(DbWeight::get().reads((1 as Weight).saturating_mul(r as Weight)))
+			.saturating_add(DbWeight::get().reads((4 as Weight).saturating_mul(c as Weight)))
+			.saturating_add(DbWeight::get().writes(6 as Weight))
+			.saturating_add(DbWeight::get().writes((6 as Weight).saturating_mul(c as Weight)))
+	}
+	fn offer_custody() -> Weight {
+		(38_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn accept_custody(c: u32, ) -> Weight {
+		(52_000_000 as Weight)
+			.saturating_add((7_000_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add(DbWeight::get().reads(6 as Weight))
+			.saturating_add(DbWeight::get().reads((1 as Weight).saturating_mul(c as Weight)))
+			.saturating_add(DbWeight::get().writes(6 as Weight))
+			.saturating_add(DbWeight::get().writes((5 as Weight).saturating_mul(c as Weight)))
+	}
+	fn authorize_carrier() -> Weight {
+		(36_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn revoke_carrier() -> Weight {
+		(38_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	fn set_condition_threshold() -> Weight {
+		(34_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_route(c: u32, ) -> Weight {
+		(40_000_000 as Weight)
+			.saturating_add((6_000_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn flag_overdue_shipments(s: u32, ) -> Weight {
+		(6_000_000 as Weight)
+			.saturating_add((12_000_000 as Weight).saturating_mul(s as Weight))
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().reads((1 as Weight).saturating_mul(s as Weight)))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	fn consolidate(c: u32, ) -> Weight {
+		(40_000_000 as Weight)
+			.saturating_add((14_000_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add(DbWeight::get().reads(4 as Weight))
+			.saturating_add(DbWeight::get().reads((3 as Weight).saturating_mul(c as Weight)))
+			.saturating_add(DbWeight::get().writes(6 as Weight))
+			.saturating_add(DbWeight::get().writes((6 as Weight).saturating_mul(c as Weight)))
+	}
+	fn deconsolidate(c: u32, ) -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add((8_000_000 as Weight).saturating_mul(c as Weight))
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().reads((1 as Weight).saturating_mul(c as Weight)))
+			.saturating_add(DbWeight::get().writes(6 as Weight))
+			.saturating_add(DbWeight::get().writes((6 as Weight).saturating_mul(c as Weight)))
+	}
+	fn register_device() -> Weight {
+		(36_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	fn deregister_device() -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+}
@@ -0,0 +1,44 @@
+//! Weights for registrar
+//!
+//! Hand-estimated placeholders mirroring the pallet's default weights, not benchmark results.
+//! Laid out as the Substrate benchmark CLI (v2.0.0) outputs them, so that running the benchmarks
+//! as described in `weights/mod.rs` overwrites this file.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::weights::{Weight, constants::RocksDbWeight as DbWeight};
+
+pub struct WeightInfo;
+impl registrar::WeightInfo for WeightInfo {
+	fn create_organization() -> Weight {
+		(71_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn add_to_organization() -> Weight {
+		(83_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(4 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn set_company_prefix() -> Weight {
+		(96_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(28 as Weight))
+			.saturating_add(DbWeight::get().writes(20 as Weight))
+	}
+	fn remove_company_prefix() -> Weight {
+		(48_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(9 as Weight))
+			.saturating_add(DbWeight::get().writes(10 as Weight))
+	}
+	fn add_certifier() -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn remove_certifier() -> Weight {
+		(32_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+}
@@ -0,0 +1,23 @@
+#!/usr/bin/env bash
+
+set -e
+
+# Generates the runtime weights of the sample's own pallets, to be run from the `chain` directory
+# on the reference hardware.
+
+echo "*** Building the node with runtime benchmarks"
+
+(cd node && cargo build --release --features runtime-benchmarks)
+
+for pallet in product_registry product_tracking registrar ; do
+   echo "*** Benchmarking $pallet"
+   ./target/release/enterprise-sample benchmark \
+      --chain dev \
+      --execution wasm \
+      --wasm-execution compiled \
+      --pallet $pallet \
+      --extrinsic '*' \
+      --steps 50 \
+      --repeat 20 \
+      --output runtime/src/weights/pallet_$pallet.rs
+done
@@ -111,29 +111,29 @@ async function main() {
 
     // create products
     const beef = uuidv4();
-    submit(api, api.tx.productRegistry.registerProduct(beef, users.bob.key.address, [['desc', 'beef burger']]), users.betty);
+    submit(api, api.tx.productRegistry.registerProduct(beef, users.bob.key.address, [['desc', { Text: 'beef burger' }]]), users.betty);
     const veggie = uuidv4();
-    submit(api, api.tx.productRegistry.registerProduct(veggie, users.bob.key.address, [['desc', 'veggie burger']]), users.betty);
+    submit(api, api.tx.productRegistry.registerProduct(veggie, users.bob.key.address, [['desc', { Text: 'veggie burger' }]]), users.betty);
 
     const ricotta = uuidv4();
-    submit(api, api.tx.productRegistry.registerProduct(ricotta, users.charlie.key.address, [['desc', 'fresh ricotta']]), users.clarice);
+    submit(api, api.tx.productRegistry.registerProduct(ricotta, users.charlie.key.address, [['desc', { Text: 'fresh ricotta' }]]), users.clarice);
     const gruyere = uuidv4();
-    submit(api, api.tx.productRegistry.registerProduct(gruyere, users.charlie.key.address, [['desc', 'aged gruyere']]), users.clarice);
+    submit(api, api.tx.productRegistry.registerProduct(gruyere, users.charlie.key.address, [['desc', { Text: 'aged gruyere' }]]), users.clarice);
 
     const bread = uuidv4();
-    submit(api, api.tx.productRegistry.registerProduct(bread, users.dave.key.address, [['desc', 'bread loaf']]), users.daisy);
+    submit(api, api.tx.productRegistry.registerProduct(bread, users.dave.key.address, [['desc', { Text: 'bread loaf' }]]), users.daisy);
     const rolls = uuidv4();
-    submit(api, api.tx.productRegistry.registerProduct(rolls, users.dave.key.address, [['desc', 'dinner rolls']]), users.daisy);
+    submit(api, api.tx.productRegistry.registerProduct(rolls, users.dave.key.address, [['desc', { Text: 'dinner rolls' }]]), users.daisy);
 
     const begonia = uuidv4();
-    submit(api, api.tx.productRegistry.registerProduct(begonia, users.eve.key.address, [['desc', 'begonia rex']]), users.erowid);
+    submit(api, api.tx.productRegistry.registerProduct(begonia, users.eve.key.address, [['desc', { Text: 'begonia rex' }]]), users.erowid);
     const fern = uuidv4();
-    submit(api, api.tx.productRegistry.registerProduct(fern, users.eve.key.address, [['desc', 'sword fern']]), users.erowid);
+    submit(api, api.tx.productRegistry.registerProduct(fern, users.eve.key.address, [['desc', { Text: 'sword fern' }]]), users.erowid);
 
     const iris = uuidv4();
-    submit(api, api.tx.productRegistry.registerProduct(iris, users.ferdie.key.address, [['desc', 'purple iris']]), users.francis);
+    submit(api, api.tx.productRegistry.registerProduct(iris, users.ferdie.key.address, [['desc', { Text: 'purple iris' }]]), users.francis);
     const orchid = uuidv4();
-    submit(api, api.tx.productRegistry.registerProduct(orchid, users.ferdie.key.address, [['desc', 'white orchid']]), users.francis);
+    submit(api, api.tx.productRegistry.registerProduct(orchid, users.ferdie.key.address, [['desc', { Text: 'white orchid' }]]), users.francis);
 
     await new Promise(r => setTimeout(r, block));
 
@@ -149,35 +149,35 @@ async function main() {
     // });
 
     const bobShipment = uuidv4();
-    submit(api, api.tx.productTracking.registerShipment(bobShipment, users.bob.key.address, [beef, veggie]), users.betty);
+    submit(api, api.tx.productTracking.registerShipment(bobShipment, users.bob.key.address, [beef, veggie], [], [], null), users.betty);
     submit(api, api.tx.productTracking.trackShipment(bobShipment, 'Pickup', now, loc(), null), users.betty);
     submit(api, api.tx.productTracking.trackShipment(bobShipment, 'Scan', now + day, loc(), null), users.betty);
     submit(api, api.tx.productTracking.trackShipment(bobShipment, 'Scan', now + rand(2.5, 4.5) * day, loc(), null), users.betty);
     submit(api, api.tx.productTracking.trackShipment(bobShipment, 'Deliver', now + rand(5.0, 6.5) * day, loc(), null), users.betty);
 
     const charlieShipment = uuidv4();
-    submit(api, api.tx.productTracking.registerShipment(charlieShipment, users.charlie.key.address, [ricotta, gruyere]), users.clarice);
+    submit(api, api.tx.productTracking.registerShipment(charlieShipment, users.charlie.key.address, [ricotta, gruyere], [], [], null), users.clarice);
     submit(api, api.tx.productTracking.trackShipment(charlieShipment, 'Pickup', now + hour, loc(), null), users.clarice);
     submit(api, api.tx.productTracking.trackShipment(charlieShipment, 'Scan', now + rand(1.0, 2.5) * day, loc(), null), users.clarice);
     submit(api, api.tx.productTracking.trackShipment(charlieShipment, 'Scan', now + rand(3.0, 3.5) * day, loc(), null), users.clarice);
     submit(api, api.tx.productTracking.trackShipment(charlieShipment, 'Deliver', now + rand(4.0, 6.5) * day, loc(), null), users.clarice);
 
     const daveShipment = uuidv4();
-    submit(api, api.tx.productTracking.registerShipment(daveShipment, users.dave.key.address, [bread, rolls]), users.daisy);
+    submit(api, api.tx.productTracking.registerShipment(daveShipment, users.dave.key.address, [bread, rolls], [], [], null), users.daisy);
     submit(api, api.tx.productTracking.trackShipment(daveShipment, 'Pickup', now + rand(1.0, 6.0) * hour, loc(), null), users.daisy);
     submit(api, api.tx.productTracking.trackShipment(daveShipment, 'Scan', now + day, loc(), null), users.daisy);
     submit(api, api.tx.productTracking.trackShipment(daveShipment, 'Scan', now + rand(1.5, 3.0) * day, loc(), null), users.daisy);
     submit(api, api.tx.productTracking.trackShipment(daveShipment, 'Deliver', now + rand(4.0, 6.5) * day, loc(), null), users.daisy);
 
     const eveShipment = uuidv4();
-    submit(api, api.tx.productTracking.registerShipment(eveShipment, users.eve.key.address, [begonia, fern]), users.erowid);
+    submit(api, api.tx.productTracking.registerShipment(eveShipment, users.eve.key.address, [begonia, fern], [], [], null), users.erowid);
     submit(api, api.tx.productTracking.trackShipment(eveShipment, 'Pickup', now + rand(1.0, 12.0) * hour, loc(), null), users.erowid);
     submit(api, api.tx.productTracking.trackShipment(eveShipment, 'Scan', now + rand(1.5, 3.5) * day, loc(), null), users.erowid);
     submit(api, api.tx.productTracking.trackShipment(eveShipment, 'Scan', now + rand(4.0, 5.5) * day, loc(), null), users.erowid);
     submit(api, api.tx.productTracking.trackShipment(eveShipment, 'Deliver', now + rand(6.0, 6.5) * day, loc(), null), users.erowid);
 
     const ferdieShipment = uuidv4();
-    submit(api, api.tx.productTracking.registerShipment(ferdieShipment, users.ferdie.key.address, [begonia, fern]), users.francis);
+    submit(api, api.tx.productTracking.registerShipment(ferdieShipment, users.ferdie.key.address, [begonia, fern], [], [], null), users.francis);
     submit(api, api.tx.productTracking.trackShipment(ferdieShipment, 'Pickup', now + day, loc(), null), users.francis);
     submit(api, api.tx.productTracking.trackShipment(ferdieShipment, 'Scan', now + rand(2.0, 3.0) * day, loc(), null), users.francis);
     submit(api, api.tx.productTracking.trackShipment(ferdieShipment, 'Scan', now + rand(4.0, 5.0) * day, loc(), null), users.francis);
@@ -18,36 +18,96 @@ export default {
     identity: "AccountId",
   },
   PropName: "Vec<u8>",
-  PropValue: "Vec<u8>",
+  PropValue: {
+    _enum: {
+      Text: "Vec<u8>",
+      Integer: "i64",
+      Decimal: "Decimal",
+      Date: "Moment",
+      Boolean: "bool",
+    },
+  },
   ProductProperty: {
     name: "PropName",
     value: "PropValue",
   },
   ProductId: "Vec<u8>",
+  LotNumber: "Vec<u8>",
+  LotId: "(ProductId, LotNumber)",
+  ProductLot: {
+    lot_number: "LotNumber",
+    product_id: "ProductId",
+    quantity: "u32",
+    produced: "Moment",
+    expires: "Option<Moment>",
+  },
+  ProductComponent: {
+    product_id: "ProductId",
+    quantity: "u32",
+  },
+  DocumentType: "Vec<u8>",
+  ProductDocument: {
+    doc_type: "DocumentType",
+    hash: "Hash",
+    uri: "Vec<u8>",
+    added: "Moment",
+    superseded: "Option<Moment>",
+  },
+  CertificateId: "Vec<u8>",
+  Certification: {
+    id: "CertificateId",
+    certifier: "AccountId",
+    scheme: "Vec<u8>",
+    issued: "Moment",
+    expires: "Option<Moment>",
+    revoked: "Option<Moment>",
+  },
+  ProductStatus: {
+    _enum: ["Active", "Discontinued", "Recalled"],
+  },
   Product: {
     id: "ProductId",
     owner: "AccountId",
     props: "Option<Vec<ProductProperty>>",
     registered: "Moment",
+    status: "ProductStatus",
+  },
+  ProductRevision: {
+    block_number: "BlockNumber",
+    author: "AccountId",
+    name: "PropName",
+    old_value: "Option<PropValue>",
+    new_value: "Option<PropValue>",
+  },
+  RecallNotice: {
+    reason: "Vec<u8>",
+    issuer: "AccountId",
+    timestamp: "Moment",
   },
   Identifier: "Vec<u8>",
   Decimal: "i32",
   ShipmentId: "Identifier",
   ShippingEventIndex: "u128",
   DeviceId: "Identifier",
+  DevicePublicKey: "H256",
+  DeviceSignature: "H512",
   ShipmentStatus: {
-    _enum: ["Pending", "InTransit", "Delivered"],
+    _enum: ["Pending", "InTransit", "Delivered", "Cancelled", "OnHold", "Returned", "Lost"],
   },
   Shipment: {
     id: "ShipmentId",
     owner: "AccountId",
+    custodian: "AccountId",
     status: "ShipmentStatus",
     products: "Vec<ProductId>",
+    lots: "Vec<LotId>",
     registered: "Moment",
     delivered: "Option<Moment>",
+    waypoints: "Vec<ReadPoint>",
+    expected_delivery: "Option<Moment>",
   },
   ShippingOperation: {
-    _enum: ["Pickup", "Scan", "Deliver"],
+    _enum: ["Pickup", "Scan", "Deliver", "Cancel", "Hold", "Resume", "Return", "ReportLoss"],
   },
   ShippingEventType: {
     _enum: [
@@ -55,6 +115,14 @@ export default {
       "ShipmentPickup",
       "ShipmentScan",
       "ShipmentDeliver",
+      "CustodyTransfer",
+      "ShipmentCancel",
+      "ShipmentHold",
+      "ShipmentResume",
+      "ShipmentReturn",
+      "ShipmentLoss",
+      "ShipmentConsolidation",
+      "ShipmentDeconsolidation",
     ],
   },
   ShippingEvent: {
@@ -68,6 +136,17 @@ export default {
     latitude: "Decimal",
     longitude: "Decimal",
   },
+  Geofence: {
+    _enum: {
+      Circle: "(ReadPoint, u32)",
+      Polygon: "Vec<ReadPoint>",
+    },
+  },
+  ShipmentRoute: {
+    origin: "Option<Geofence>",
+    destination: "Option<Geofence>",
+    corridor: "Vec<Geofence>",
+  },
   ReadingType: {
     _enum: [
       "Humidity",
@@ -78,12 +157,27 @@ export default {
       "Vibration",
     ],
   },
+  ConditionThreshold: {
+    reading_type: "ReadingType",
+    min: "Option<Decimal>",
+    max: "Option<Decimal>",
+  },
   Reading: {
     device_id: "DeviceId",
     reading_type: "ReadingType",
     timestamp: "Moment",
     value: "Decimal",
   },
+  SignedReading: {
+    reading: "Reading",
+    signature: "DeviceSignature",
+  },
+  Device: {
+    id: "DeviceId",
+    owner: "AccountId",
+    public_key: "DevicePublicKey",
+    reading_types: "Vec<ReadingType>",
+  },
   Permission: {
     _enum: ["Execute", "Manage"],
   },
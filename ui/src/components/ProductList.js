@@ -52,7 +52,7 @@ export default function Main (props) {
       return <Table.Row key={id}>
         <Table.Cell>{ id }</Table.Cell>
         <Table.Cell>{ product.owner.toString() }</Table.Cell>
-        <Table.Cell>{ u8aToString(props[0].value) }</Table.Cell>
+        <Table.Cell>{ props[0].value.isText ? u8aToString(props[0].value.asText) : props[0].value.toString() }</Table.Cell>
       </Table.Row>;
     })}</Table.Body>
   </Table>;
@@ -14,7 +14,7 @@ export default function RegisterProductForm (props) {
     if (data.state === 'id') {
       newParams.id = (data.value.length === 0 ? null : stringToHex(data.value));
     } else if (data.state === 'desc') {
-      newParams.props = (data.value.length === 0 ? null : [['0x64657363', stringToHex(data.value)]]);
+      newParams.props = (data.value.length === 0 ? null : [['0x64657363', { Text: stringToHex(data.value) }]]);
     }
     setParams(newParams);
   };
@@ -24,7 +24,9 @@ function RegisterShipmentFormComponent (props) {
     }
     const paramFields = api.tx.productTracking.registerShipment.meta.args.map(arg => ({
       name: arg.name.toString(),
-      type: arg.type.toString()
+      type: arg.type.toString(),
+      // Shipments of this form reference products, not lots, and have no planned route
+      optional: ['lots', 'waypoints', 'expected_delivery'].includes(arg.name.toString())
     }));
     setParamFields(paramFields);
   };
@@ -112,7 +114,7 @@ function RegisterShipmentFormComponent (props) {
               attrs={{
                 palletRpc: 'productTracking',
                 callable: 'registerShipment',
-                inputParams: [state.shipmentId, state.owner, [state.productId1 || '', state.productId2 || ''].join(',')],
+                inputParams: [state.shipmentId, state.owner, [state.productId1 || '', state.productId2 || ''].join(','), '', '', ''],
                 paramFields: paramFields
               }}
             />
@@ -113,7 +113,7 @@ function ShipmentDetailsComponent (props) {
       ? <Container style={{ marginTop: '2em' }}>
         <Header as="h2">Shipment {shipmentId}</Header>
         <Segment>
-          <Grid columns="2" rows="2">
+          <Grid columns="2" rows="3">
             <Grid.Row>
               <Grid.Column>
                 <Header as="h4" floated="left">Owner: </Header>
@@ -136,6 +136,12 @@ function ShipmentDetailsComponent (props) {
                 }</span>
               </Grid.Column>
             </Grid.Row>
+            <Grid.Row>
+              <Grid.Column>
+                <Header as="h4" floated="left">Custodian: </Header>
+                <span style={{ fontSize: '0.8em' }}>{shipment.custodian.toString()}</span>
+              </Grid.Column>
+            </Grid.Row>
           </Grid>
         </Segment>
         <Grid container columns={3} style={{ marginTop: '1em' }}>
@@ -151,10 +157,12 @@ function ShipmentDetailsComponent (props) {
                       : eventType === 'ShipmentPickup'
                         ? 'truck'
                         : eventType === 'ShipmentScan'
-                          ? 'barcode' : 'home'
+                          ? 'barcode'
+                          : eventType === 'CustodyTransfer'
+                            ? 'exchange' : 'home'
                     } />
                     <Step.Content>
-                      <Step.Title>{event.event_type.toString().substring(8)}</Step.Title>
+                      <Step.Title>{eventType.replace(/^Shipment/, '')}</Step.Title>
                       <Step.Description>
                         { new Date(event.timestamp.toNumber()).toLocaleString() }
                       </Step.Description>
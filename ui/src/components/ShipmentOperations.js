@@ -1,18 +1,23 @@
-import React, { useState, Fragment } from 'react';
+import React, { useEffect, useState, Fragment } from 'react';
 import { Form } from 'semantic-ui-react';
 
+import { useSubstrate } from '../substrate-lib';
 import { TxButton } from '../substrate-lib/components';
 import { hexToString } from '@polkadot/util';
 
 export default function Main (props) {
+  const { api } = useSubstrate();
   const { accountPair, shipment } = props;
   const [status, setStatus] = useState(null);
+  const [container, setContainer] = useState(null);
   const [state, setState] = useState({
     latitude: 0.0,
     longitude: 0.0,
     deviceId: '',
     sensorType: '',
-    sensorValue: 0.0
+    sensorValue: 0.0,
+    readingTimestamp: '',
+    signature: ''
   });
   const sensorTypes = [
     'Humidity', 'Pressure', 'Shock', 'Tilt',
@@ -21,8 +26,42 @@ export default function Main (props) {
   const handleChange = (_, data) =>
     setState({ ...state, [data.state]: data.value });
 
+  // Consolidated shipments are tracked through their container
+  useEffect(() => {
+    let unsub = null;
+
+    async function containerOf (shipmentId) {
+      unsub = await api.query.productTracking.shipmentContainer(shipmentId,
+        data => setContainer(data.isSome ? hexToString(data.unwrap().toString()) : null));
+    }
+
+    if (shipment) containerOf(shipment.id);
+    return () => unsub && unsub();
+  }, [api.query.productTracking, shipment]);
+
+  const hasReading = state.deviceId !== '' && state.sensorType !== '' && state.sensorValue !== 0.0 &&
+    state.readingTimestamp !== '';
+
+  // Message the device must sign: the SCALE-encoded shipment ID, reading type, timestamp & value,
+  // as submitted with the reading
+  const signingPayload = () => {
+    try {
+      return api.createType('(Vec<u8>, ReadingType, u64, Decimal)',
+        [shipment.id, state.sensorType, state.readingTimestamp, state.sensorValue]).toHex();
+    } catch (e) {
+      return '';
+    }
+  };
+
   if (!shipment) return null;
 
+  if (container) {
+    return <div>
+      Shipment is consolidated into container {container}: track the container instead,
+      with readings signed for the container's ID.
+    </div>;
+  }
+
   return <Fragment>
     <TxButton
       accountPair={accountPair}
@@ -77,6 +116,26 @@ export default function Main (props) {
         value={state.sensorValue}
         onChange={handleChange}
       />
+      <Form.Input
+        name='readingTimestamp'
+        label='Reading timestamp (UNIX time, ms)'
+        state='readingTimestamp'
+        value={state.readingTimestamp}
+        onChange={handleChange}
+      />
+      <Form.Input
+        name='signingPayload'
+        label='Payload for the device to sign'
+        value={hasReading ? signingPayload() : ''}
+        readOnly
+      />
+      <Form.Input
+        name='signature'
+        label='Device signature'
+        state='signature'
+        value={state.signature}
+        onChange={handleChange}
+      />
       <Form.Field>
         <TxButton
           accountPair={accountPair}
@@ -88,8 +147,11 @@ export default function Main (props) {
             callable: 'trackShipment',
             inputParams: [shipment.id, 'Scan', Date.now(),
               (state.latitude !== 0.0 && state.longitude !== 0.0 ? { latitude: state.latitude, longitude: state.longitude } : null),
-              (state.deviceId !== '' && state.sensorType !== '' && state.sensorValue !== 0.0
-                ? [{ deviceId: state.deviceId, readingType: state.sensorType, timestamp: Date.now(), value: state.sensorValue }]
+              (hasReading && state.signature !== ''
+                ? [{
+                    reading: { deviceId: state.deviceId, readingType: state.sensorType, timestamp: state.readingTimestamp, value: state.sensorValue },
+                    signature: state.signature
+                  }]
                 : null)
             ],
             paramFields: [{ optional: false }, { optional: false }, { optional: false }, { optional: true }, { optional: true }]